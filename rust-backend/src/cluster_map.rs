@@ -0,0 +1,153 @@
+//! Cluster Ownership Module
+//! Maps every cluster claimed by an MFT entry, live or deleted, back to the
+//! entries claiming it, and reports deleted files whose runs overlap another
+//! file's
+//!
+//! A deleted file cross-linked with a live file has had those clusters
+//! reused; one cross-linked with another deleted file holds the content of
+//! only one of them. Either way a naive recovery returns the wrong bytes.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::ntfs_parser::DataRun;
+
+/// Another file claiming some of a deleted file's clusters
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CrossLink {
+    pub record_number: u64,  // MFT record of the conflicting file
+    pub name: String,        // Its name, "file:stream" for an alternate data stream
+    pub is_deleted: bool,    // False when a live file now owns the clusters
+    pub file_offset: u64,    // Byte offset of the overlap within the deleted file
+    pub disk_offset: u64,    // Byte offset of the overlap on the volume
+    pub length: u64,         // Overlap length in bytes
+}
+
+/// One stream of an MFT entry
+struct Owner {
+    record_number: u64,
+    stream: String,
+    name: String,
+    is_deleted: bool,
+}
+
+/// A run of clusters [start, end) claimed by an owner, starting at `vcn` within it
+struct Extent {
+    start: u64,
+    end: u64,
+    vcn: u64,
+    owner: usize,
+}
+
+#[derive(Default)]
+pub struct ClusterMap {
+    owners: Vec<Owner>,
+    extents: Vec<Extent>,
+}
+
+impl ClusterMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the clusters claimed by one stream of an MFT entry ("" for the unnamed $DATA)
+    pub fn add(&mut self, record_number: u64, stream: &str, name: &str, is_deleted: bool, runs: &[DataRun]) {
+        let owner = self.owners.len();
+        let mut vcn = 0u64;
+        for run in runs {
+            // Sparse runs claim nothing; negative LCNs are corrupt mapping pairs
+            if run.cluster_offset > 0 && run.cluster_count > 0 {
+                let start = run.cluster_offset as u64;
+                self.extents.push(Extent { start, end: start + run.cluster_count, vcn, owner });
+            }
+            vcn += run.cluster_count;
+        }
+
+        self.owners.push(Owner {
+            record_number,
+            stream: stream.to_string(),
+            name: name.to_string(),
+            is_deleted,
+        });
+    }
+
+    /// Cross-links of every deleted stream, keyed by (record number, stream name)
+    pub fn cross_links(&self, cluster_size: u32) -> HashMap<(u64, String), Vec<CrossLink>> {
+        let cluster_size = cluster_size as u64;
+        let mut order: Vec<usize> = (0..self.extents.len()).collect();
+        order.sort_by_key(|&i| self.extents[i].start);
+
+        // Sweep by start cluster, keeping the extents still open at each point
+        let mut links: HashMap<(u64, String), Vec<CrossLink>> = HashMap::new();
+        let mut active: Vec<usize> = Vec::new();
+        for i in order {
+            let extent = &self.extents[i];
+            active.retain(|&j| self.extents[j].end > extent.start);
+
+            for &j in &active {
+                let other = &self.extents[j];
+                if other.owner == extent.owner {
+                    continue;
+                }
+
+                let (start, end) = (extent.start, extent.end.min(other.end));
+                for (victim, claimant) in [(extent, other), (other, extent)] {
+                    let owner = &self.owners[victim.owner];
+                    if !owner.is_deleted {
+                        continue;
+                    }
+
+                    let claimant = &self.owners[claimant.owner];
+                    links.entry((owner.record_number, owner.stream.clone())).or_default().push(CrossLink {
+                        record_number: claimant.record_number,
+                        name: claimant.name.clone(),
+                        is_deleted: claimant.is_deleted,
+                        file_offset: (victim.vcn + start - victim.start) * cluster_size,
+                        disk_offset: start * cluster_size,
+                        length: (end - start) * cluster_size,
+                    });
+                }
+            }
+            active.push(i);
+        }
+
+        for file_links in links.values_mut() {
+            file_links.sort_by_key(|link| link.file_offset);
+        }
+        links
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(cluster_offset: i64, cluster_count: u64) -> DataRun {
+        DataRun { cluster_offset, cluster_count }
+    }
+
+    #[test]
+    fn test_cross_links_between_live_and_deleted_files() {
+        let mut map = ClusterMap::new();
+        map.add(40, "", "live.txt", false, &[run(100, 10)]);
+        map.add(41, "", "old.doc", true, &[run(90, 2), run(0, 3), run(95, 7)]);
+        map.add(42, "notes", "old2.doc:notes", true, &[run(101, 1)]);
+        map.add(43, "", "alone.bin", true, &[run(200, 5)]);
+        let links = map.cross_links(4096);
+
+        // old.doc's clusters 100-101 (its VCN 10-11) now belong to live.txt, and 101 to old2.doc too
+        let old = &links[&(41, String::new())];
+        assert_eq!(old.len(), 2);
+        assert_eq!((old[0].record_number, old[0].is_deleted), (40, false));
+        assert_eq!((old[0].file_offset, old[0].disk_offset, old[0].length), (10 * 4096, 100 * 4096, 2 * 4096));
+        assert_eq!((old[1].name.as_str(), old[1].file_offset, old[1].length), ("old2.doc:notes", 11 * 4096, 4096));
+
+        let stream = &links[&(42, "notes".to_string())];
+        assert_eq!(stream.len(), 2);
+        assert!(stream.iter().any(|link| link.record_number == 41 && link.is_deleted));
+
+        // Live files are never reported, nor are deleted files nobody overlaps
+        assert!(!links.contains_key(&(40, String::new())));
+        assert!(!links.contains_key(&(43, String::new())));
+    }
+}
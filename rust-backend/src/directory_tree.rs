@@ -0,0 +1,261 @@
+//! Directory Tree Module
+//! Rebuilds the folder hierarchy of deleted files from the parent references
+//! in their $FILE_NAME attributes and in stale $I30 index entries
+//!
+//! A parent reference only counts while the directory record still holds the
+//! directory it names (sequence check); items whose parent is gone or was
+//! reused hang under $OrphanFiles. Live directories appear only on the way
+//! to deleted content, so the tree stays the size of what was lost.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::ntfs_parser::sequence_matches;
+
+/// Deeper parent chains than this are treated as corrupt (a reference loop)
+pub const MAX_PATH_DEPTH: usize = 256;
+
+/// What the MFT scan knows about a directory record, live or deleted
+#[derive(Debug, Clone)]
+pub struct DirectoryRecord {
+    pub name: String,
+    pub sequence_number: u16,
+    pub is_in_use: bool,
+    pub parent_record: u64,
+    pub parent_sequence: u16,
+}
+
+/// A deleted file from the scan result and the directory reference it was found under
+#[derive(Debug, Clone)]
+pub struct TreeLeaf {
+    pub id: String,
+    pub parent_record: u64,
+    pub parent_sequence: u16,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DirectoryNode {
+    pub record_number: u64,
+    pub name: String,
+    pub path: String,       // Volume-relative, "" for the root directory
+    pub is_deleted: bool,
+    pub files: Vec<String>, // Scan-result ids of the deleted files directly inside
+    pub children: Vec<DirectoryNode>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DirectoryTree {
+    pub root: DirectoryNode,
+    pub orphans: Vec<DirectoryNode>, // Directories whose parent is gone or was reused
+    pub orphan_files: Vec<String>,   // Files whose parent is gone or was reused
+}
+
+impl DirectoryNode {
+    /// Every file at or below this directory, with its directory relative to it
+    pub fn files_below(&self) -> Vec<(PathBuf, &str)> {
+        let mut files = Vec::new();
+        self.collect_files(Path::new(""), &mut files);
+        files
+    }
+
+    fn collect_files<'a>(&'a self, relative: &Path, files: &mut Vec<(PathBuf, &'a str)>) {
+        files.extend(self.files.iter().map(|id| (relative.to_path_buf(), id.as_str())));
+        for child in &self.children {
+            child.collect_files(&relative.join(&child.name), files);
+        }
+    }
+
+    /// Every directory at or below this one, relative to it
+    pub fn directories_below(&self) -> Vec<PathBuf> {
+        let mut directories = vec![PathBuf::new()];
+        let mut at = 0;
+        let mut pending: Vec<&DirectoryNode> = vec![self];
+        while at < pending.len() {
+            let node = pending[at];
+            let relative = directories[at].clone();
+            for child in &node.children {
+                directories.push(relative.join(&child.name));
+                pending.push(child);
+            }
+            at += 1;
+        }
+        directories
+    }
+
+    fn find(&self, record_number: u64) -> Option<&DirectoryNode> {
+        if self.record_number == record_number {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.find(record_number))
+    }
+}
+
+impl DirectoryTree {
+    /// Hang every leaf, and every deleted directory, under the directories its
+    /// parent references lead to
+    pub fn build(directories: &HashMap<u64, DirectoryRecord>, leaves: &[TreeLeaf], root: u64) -> DirectoryTree {
+        // A reference is only followed while the record still holds the directory it named
+        let attached = |record: u64, sequence: u16| {
+            directories.get(&record).is_some_and(|d| sequence_matches(sequence, d.sequence_number, d.is_in_use))
+        };
+        let parent_of = |record: u64| {
+            let directory = directories.get(&record)?;
+            (record != root && attached(directory.parent_record, directory.parent_sequence))
+                .then_some(directory.parent_record)
+        };
+
+        let mut files: HashMap<u64, Vec<String>> = HashMap::new();
+        let mut orphan_files = Vec::new();
+        for leaf in leaves {
+            if attached(leaf.parent_record, leaf.parent_sequence) {
+                files.entry(leaf.parent_record).or_default().push(leaf.id.clone());
+            } else {
+                orphan_files.push(leaf.id.clone());
+            }
+        }
+
+        // Directory -> the parent it hangs under (None at the root and for orphans),
+        // for each directory on the way up from deleted content
+        let mut parents: HashMap<u64, Option<u64>> = HashMap::from([(root, None)]);
+        let mut starts: Vec<u64> = files.keys().copied().collect();
+        starts.extend(directories.iter().filter(|(_, d)| !d.is_in_use).map(|(&record, _)| record));
+        starts.sort_unstable();
+        for start in starts {
+            let mut chain = Vec::new();
+            let mut current = start;
+            while !parents.contains_key(&current) && !chain.contains(&current) && chain.len() < MAX_PATH_DEPTH {
+                chain.push(current);
+                match parent_of(current) {
+                    Some(parent) => current = parent,
+                    None => break,
+                }
+            }
+            // The chain ends at a known directory, a dead end, or a reference loop (cut it)
+            let end = chain.last().and_then(|&last| parent_of(last)).filter(|p| parents.contains_key(p));
+            for (i, &record) in chain.iter().enumerate() {
+                parents.insert(record, chain.get(i + 1).copied().or(end));
+            }
+        }
+
+        let mut children: HashMap<u64, Vec<u64>> = HashMap::new();
+        let mut orphans = Vec::new();
+        for (&record, &parent) in &parents {
+            match parent {
+                Some(parent) => children.entry(parent).or_default().push(record),
+                None if record != root => orphans.push(record),
+                None => {}
+            }
+        }
+
+        let mut builder = TreeBuilder { directories, files: &mut files, children: &children };
+        let mut orphans: Vec<DirectoryNode> = orphans.into_iter()
+            .map(|record| builder.node(record, "$OrphanFiles"))
+            .collect();
+        orphans.sort_by(|a, b| a.name.cmp(&b.name));
+        let root = builder.node(root, "");
+        orphan_files.sort();
+
+        DirectoryTree { root, orphans, orphan_files }
+    }
+
+    /// The directory with this MFT record number, wherever it hangs
+    pub fn find(&self, record_number: u64) -> Option<&DirectoryNode> {
+        self.root.find(record_number)
+            .or_else(|| self.orphans.iter().find_map(|orphan| orphan.find(record_number)))
+    }
+}
+
+struct TreeBuilder<'a> {
+    directories: &'a HashMap<u64, DirectoryRecord>,
+    files: &'a mut HashMap<u64, Vec<String>>,
+    children: &'a HashMap<u64, Vec<u64>>,
+}
+
+impl TreeBuilder<'_> {
+    fn node(&mut self, record: u64, parent_path: &str) -> DirectoryNode {
+        let directory = self.directories.get(&record);
+        // The root's own name is "."
+        let name = match directory {
+            Some(d) if !parent_path.is_empty() || d.parent_record != record => d.name.clone(),
+            _ => String::new(),
+        };
+        let path = match (parent_path.is_empty(), name.is_empty()) {
+            (true, _) => name.clone(),
+            (false, true) => parent_path.to_string(),
+            (false, false) => format!("{}\\{}", parent_path, name),
+        };
+
+        let mut files = self.files.remove(&record).unwrap_or_default();
+        files.sort();
+        let mut children: Vec<DirectoryNode> = self.children.get(&record).into_iter().flatten()
+            .map(|&child| self.node(child, &path))
+            .collect();
+        children.sort_by(|a, b| a.name.cmp(&b.name));
+
+        DirectoryNode {
+            record_number: record,
+            name,
+            path,
+            is_deleted: directory.is_some_and(|d| !d.is_in_use),
+            files,
+            children,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn directory(name: &str, sequence_number: u16, is_in_use: bool, parent_record: u64, parent_sequence: u16) -> DirectoryRecord {
+        DirectoryRecord { name: name.to_string(), sequence_number, is_in_use, parent_record, parent_sequence }
+    }
+
+    fn leaf(id: &str, parent_record: u64, parent_sequence: u16) -> TreeLeaf {
+        TreeLeaf { id: id.to_string(), parent_record, parent_sequence }
+    }
+
+    #[test]
+    fn test_build_tree_of_deleted_directories() {
+        let directories = HashMap::from([
+            (5, directory(".", 5, true, 5, 5)),
+            (30, directory("Projects", 3, false, 5, 5)),   // Deleted: sequence bumped from 2
+            (31, directory("src", 2, false, 30, 2)),
+            (40, directory("Users", 1, true, 5, 5)),       // Live, leads to a deleted file
+            (41, directory("Music", 1, true, 5, 5)),       // Live, nothing deleted inside
+            (50, directory("Reused", 9, true, 5, 5)),      // Reused since "stale" was created
+            (60, directory("loop-a", 2, false, 61, 2)),
+            (61, directory("loop-b", 2, false, 60, 2)),
+        ]);
+        let leaves = [
+            leaf("mft_100", 30, 2),
+            leaf("mft_101", 31, 2),
+            leaf("index_31_102", 31, 0),
+            leaf("mft_103", 40, 1),
+            leaf("mft_104", 50, 4),
+            leaf("mft_105", 5, 5),
+        ];
+        let tree = DirectoryTree::build(&directories, &leaves, 5);
+
+        assert_eq!(tree.root.path, "");
+        assert_eq!(tree.root.files, vec!["mft_105"]);
+        let names: Vec<&str> = tree.root.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["Projects", "Users"]);
+
+        let projects = tree.find(30).unwrap();
+        assert!(projects.is_deleted);
+        assert_eq!(projects.children[0].path, "Projects\\src");
+        assert_eq!(projects.children[0].files, vec!["index_31_102", "mft_101"]);
+        let below: Vec<(PathBuf, &str)> = projects.files_below();
+        assert_eq!(below[0], (PathBuf::new(), "mft_100"));
+        assert_eq!(below[1], (PathBuf::from("src"), "index_31_102"));
+        assert_eq!(projects.directories_below(), vec![PathBuf::new(), PathBuf::from("src")]);
+
+        // A reused parent orphans its old files; a reference loop is cut into an orphan directory
+        assert_eq!(tree.orphan_files, vec!["mft_104"]);
+        assert_eq!(tree.orphans.len(), 1);
+        assert!(tree.orphans[0].path.starts_with("$OrphanFiles\\loop-"));
+        assert!(tree.find(60).is_some() && tree.find(61).is_some());
+    }
+}
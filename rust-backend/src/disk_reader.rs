@@ -7,6 +7,8 @@ use std::path::Path;
 
 use serde::{Deserialize, Serialize};
 
+use crate::ntfs_parser::DataRun;
+
 #[cfg(windows)]
 use std::os::windows::fs::OpenOptionsExt;
 
@@ -36,12 +38,75 @@ pub struct ScanProgress {
     pub status: String,
 }
 
+/// Anything the disk reader can pull sectors from: a device/file handle or a
+/// decoding container (EWF, virtual disks) presenting a linear byte stream
+pub trait BlockSource: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> BlockSource for T {}
+
 /// Raw disk reader for direct sector access
 pub struct DiskReader {
-    handle: File,
+    handle: Box<dyn BlockSource>,
     sector_size: usize,
     total_size: u64,
     current_position: u64,
+    base_offset: u64,  // Byte offset of the volume inside the underlying device/image
+}
+
+/// Where the raw-disk engine reads volume data from
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScanSource {
+    /// Live volume by drive letter (e.g. "C")
+    Volume(String),
+    /// Disk image file; `offset` is the byte offset of the volume inside it
+    Image { path: String, offset: u64 },
+    /// Physical disk device (e.g. "\\.\PhysicalDrive0"); `offset` is the partition start
+    Device { path: String, offset: u64 },
+}
+
+impl ScanSource {
+    /// Open a reader positioned on this source's volume
+    pub fn open(&self) -> Result<DiskReader, String> {
+        match self {
+            ScanSource::Volume(letter) => DiskReader::open_volume(letter),
+            ScanSource::Image { path, offset } => DiskReader::open_image(path, *offset),
+            ScanSource::Device { path, offset } => DiskReader::open(path)?.with_base_offset(*offset),
+        }
+    }
+    
+    /// True for image files (no admin rights or BitLocker checks apply)
+    pub fn is_image(&self) -> bool {
+        matches!(self, ScanSource::Image { .. })
+    }
+    
+    /// Drive letter for live volumes (BitLocker and USN only apply there)
+    pub fn drive_letter(&self) -> Option<&str> {
+        match self {
+            ScanSource::Volume(letter) => Some(letter),
+            _ => None,
+        }
+    }
+    
+    /// Same image/device with the volume starting at `offset` (drive letters are unchanged)
+    pub fn at_offset(&self, offset: u64) -> ScanSource {
+        match self {
+            ScanSource::Volume(_) => self.clone(),
+            ScanSource::Image { path, .. } => ScanSource::Image { path: path.clone(), offset },
+            ScanSource::Device { path, .. } => ScanSource::Device { path: path.clone(), offset },
+        }
+    }
+    
+    /// Root used when building display paths ("C:", the image file name or "PhysicalDrive0")
+    pub fn label(&self) -> String {
+        match self {
+            ScanSource::Volume(letter) => format!("{}:", letter),
+            ScanSource::Image { path, .. } => Path::new(path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.clone()),
+            ScanSource::Device { path, .. } => path.trim_start_matches("\\\\.\\").to_string(),
+        }
+    }
 }
 
 impl DiskReader {
@@ -59,31 +124,37 @@ impl DiskReader {
                 .open(path)
                 .map_err(|e| format!("Failed to open disk {}: {}. Run as Administrator.", path, e))?;
             
-            // Get disk size
+            // Get disk size and logical sector size (4096 on 4K-native drives)
             let size = get_disk_size(&file, path)?;
+            let sector_size = get_sector_size(&file).unwrap_or(SECTOR_SIZE);
             
             Ok(DiskReader {
-                handle: file,
-                sector_size: SECTOR_SIZE,
+                handle: Box::new(file),
+                sector_size,
                 total_size: size,
                 current_position: 0,
+                base_offset: 0,
             })
         }
         
         #[cfg(not(windows))]
         {
-            let file = OpenOptions::new()
+            let mut file = OpenOptions::new()
                 .read(true)
                 .open(path)
                 .map_err(|e| format!("Failed to open disk {}: {}", path, e))?;
             
-            let metadata = file.metadata().map_err(|e| e.to_string())?;
+            // Block devices report a metadata length of 0; seeking to the end works for both
+            let size = file.seek(SeekFrom::End(0))
+                .and_then(|size| file.seek(SeekFrom::Start(0)).map(|_| size))
+                .map_err(|e| format!("Failed to get size of disk {}: {}", path, e))?;
             
             Ok(DiskReader {
-                handle: file,
-                sector_size: SECTOR_SIZE,
-                total_size: metadata.len(),
+                handle: Box::new(file),
+                sector_size: device_sector_size(path).unwrap_or(SECTOR_SIZE),
+                total_size: size,
                 current_position: 0,
+                base_offset: 0,
             })
         }
     }
@@ -95,6 +166,70 @@ impl DiskReader {
         Self::open(&path)
     }
     
+    /// Open a disk image file
+    /// Raw images (.dd/.img/.raw) are read as-is; EWF (.E01) and VM disk images
+    /// (VHD/VHDX/VMDK/QCOW2/VDI) are detected by signature and decoded transparently.
+    /// `offset` is the byte offset of the volume inside the image (0 for volume images,
+    /// partition start for whole-disk images). All reads are relative to that offset.
+    pub fn open_image(path: &str, offset: u64) -> Result<Self, String> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open image {}: {}", path, e))?;
+        
+        let mut magic = [0u8; 8];
+        let magic_len = file.read(&mut magic).unwrap_or(0);
+        
+        let (handle, image_size, sector_size): (Box<dyn BlockSource>, u64, usize) =
+            if crate::ewf::is_ewf(&magic[..magic_len]) {
+                let image = crate::ewf::EwfImage::open(path)?;
+                let size = image.media_size();
+                let sector_size = image.bytes_per_sector();
+                (Box::new(image), size, sector_size)
+            } else if let Some(disk) = crate::virtual_disk::VirtualDisk::open(path)? {
+                let size = disk.size();
+                let sector_size = disk.sector_size();
+                (Box::new(disk), size, sector_size)
+            } else {
+                let size = file
+                    .metadata()
+                    .map_err(|e| format!("Failed to read image size {}: {}", path, e))?
+                    .len();
+                (Box::new(file), size, SECTOR_SIZE)
+            };
+        
+        if offset >= image_size {
+            return Err(format!(
+                "Offset {} is beyond the end of image {} ({} bytes)",
+                offset, path, image_size
+            ));
+        }
+        
+        let mut reader = DiskReader {
+            handle,
+            sector_size,
+            total_size: image_size - offset,
+            current_position: 0,
+            base_offset: offset,
+        };
+        reader.seek_bytes(0)?;
+        Ok(reader)
+    }
+    
+    /// Restrict reads to the volume starting `offset` bytes into this disk
+    pub fn with_base_offset(mut self, offset: u64) -> Result<Self, String> {
+        if offset >= self.total_size {
+            return Err(format!(
+                "Offset {} is beyond the end of the disk ({} bytes)",
+                offset, self.total_size
+            ));
+        }
+        self.base_offset += offset;
+        self.total_size -= offset;
+        self.seek_bytes(0)?;
+        Ok(self)
+    }
+    
     /// Get total disk/volume size
     pub fn size(&self) -> u64 {
         self.total_size
@@ -105,6 +240,14 @@ impl DiskReader {
         self.sector_size
     }
     
+    /// Use the sector size the volume was formatted with; image files and
+    /// devices that don't report a geometry default to 512 bytes
+    pub fn set_sector_size(&mut self, sector_size: usize) {
+        if sector_size.is_power_of_two() && sector_size >= 512 {
+            self.sector_size = sector_size;
+        }
+    }
+    
     /// Get total number of sectors
     pub fn total_sectors(&self) -> u64 {
         self.total_size / self.sector_size as u64
@@ -114,7 +257,7 @@ impl DiskReader {
     pub fn seek_sector(&mut self, sector: u64) -> Result<(), String> {
         let byte_offset = sector * self.sector_size as u64;
         self.handle
-            .seek(SeekFrom::Start(byte_offset))
+            .seek(SeekFrom::Start(self.base_offset + byte_offset))
             .map_err(|e| format!("Failed to seek to sector {}: {}", sector, e))?;
         self.current_position = byte_offset;
         Ok(())
@@ -123,7 +266,7 @@ impl DiskReader {
     /// Seek to a specific byte offset
     pub fn seek_bytes(&mut self, offset: u64) -> Result<(), String> {
         self.handle
-            .seek(SeekFrom::Start(offset))
+            .seek(SeekFrom::Start(self.base_offset + offset))
             .map_err(|e| format!("Failed to seek to offset {}: {}", offset, e))?;
         self.current_position = offset;
         Ok(())
@@ -132,27 +275,33 @@ impl DiskReader {
     /// Read a specific number of sectors
     pub fn read_sectors(&mut self, count: usize) -> Result<Vec<u8>, String> {
         let bytes_to_read = count * self.sector_size;
-        let mut buffer = vec![0u8; bytes_to_read];
-        
-        let bytes_read = self.handle
-            .read(&mut buffer)
-            .map_err(|e| format!("Failed to read sectors: {}", e))?;
-        
-        self.current_position += bytes_read as u64;
-        buffer.truncate(bytes_read);
-        Ok(buffer)
+        self.read_full(bytes_to_read)
+            .map_err(|e| format!("Failed to read sectors: {}", e))
     }
     
     /// Read a specific number of bytes
     pub fn read_bytes(&mut self, count: usize) -> Result<Vec<u8>, String> {
+        self.read_full(count)
+            .map_err(|e| format!("Failed to read bytes: {}", e))
+    }
+    
+    /// Keep reading until `count` bytes are in or the source hits EOF
+    /// (image containers may return short reads at chunk boundaries)
+    fn read_full(&mut self, count: usize) -> std::io::Result<Vec<u8>> {
         let mut buffer = vec![0u8; count];
+        let mut filled = 0;
         
-        let bytes_read = self.handle
-            .read(&mut buffer)
-            .map_err(|e| format!("Failed to read bytes: {}", e))?;
+        while filled < count {
+            match self.handle.read(&mut buffer[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
         
-        self.current_position += bytes_read as u64;
-        buffer.truncate(bytes_read);
+        self.current_position += filled as u64;
+        buffer.truncate(filled);
         Ok(buffer)
     }
     
@@ -286,6 +435,44 @@ fn get_disk_size(file: &File, path: &str) -> Result<u64, String> {
     }
 }
 
+/// Logical sector size reported by the drive geometry
+#[cfg(windows)]
+fn get_sector_size(file: &File) -> Option<usize> {
+    use std::mem;
+    use winapi::um::ioapiset::DeviceIoControl;
+    use winapi::um::winioctl::{DISK_GEOMETRY, IOCTL_DISK_GET_DRIVE_GEOMETRY};
+    
+    unsafe {
+        let mut geometry: DISK_GEOMETRY = mem::zeroed();
+        let mut bytes_returned: u32 = 0;
+        let result = DeviceIoControl(
+            file.as_raw_handle() as *mut _,
+            IOCTL_DISK_GET_DRIVE_GEOMETRY,
+            std::ptr::null_mut(),
+            0,
+            &mut geometry as *mut _ as *mut _,
+            mem::size_of::<DISK_GEOMETRY>() as u32,
+            &mut bytes_returned,
+            std::ptr::null_mut(),
+        );
+        
+        let sector_size = geometry.BytesPerSector as usize;
+        (result != 0 && sector_size.is_power_of_two() && sector_size >= 512).then_some(sector_size)
+    }
+}
+
+/// Logical sector size of a block device from sysfs; a partition reads its
+/// parent disk's queue
+#[cfg(not(windows))]
+fn device_sector_size(path: &str) -> Option<usize> {
+    let device = std::fs::canonicalize(path).ok()?;
+    let name = device.file_name()?.to_string_lossy().to_string();
+    ["queue", "../queue"].iter()
+        .filter_map(|queue| std::fs::read_to_string(format!("/sys/class/block/{}/{}/logical_block_size", name, queue)).ok())
+        .find_map(|size| size.trim().parse::<usize>().ok())
+        .filter(|size| size.is_power_of_two() && *size >= 512)
+}
+
 /// Save carved data to a file
 pub fn save_carved_file(
     data: &[u8],
@@ -320,6 +507,39 @@ pub fn read_clusters(
     disk.read_bytes(byte_count as usize)
 }
 
+/// Read `length` bytes starting at byte `offset` of a non-resident attribute,
+/// following its run list. Sparse and unmapped ranges read as zeros.
+pub fn read_runs_range(
+    disk: &mut DiskReader,
+    runs: &[DataRun],
+    cluster_size: u32,
+    offset: u64,
+    length: usize,
+) -> Result<Vec<u8>, String> {
+    let cluster_size = cluster_size as u64;
+    let mut data = vec![0u8; length];
+    let end = offset + length as u64;
+    let mut run_start = 0u64; // Byte offset of the current run within the attribute
+    
+    for run in runs {
+        let run_end = run_start + run.cluster_count * cluster_size;
+        let from = offset.max(run_start);
+        let to = end.min(run_end);
+        if from < to && run.cluster_offset > 0 {
+            let physical = run.cluster_offset as u64 * cluster_size + (from - run_start);
+            let chunk = disk.read_at(physical, (to - from) as usize)?;
+            let at = (from - offset) as usize;
+            data[at..at + chunk.len()].copy_from_slice(&chunk);
+        }
+        if run_end >= end {
+            break;
+        }
+        run_start = run_end;
+    }
+    
+    Ok(data)
+}
+
 /// Get the physical path for a drive letter
 pub fn get_volume_path(drive_letter: &str) -> String {
     let letter = drive_letter
@@ -355,4 +575,43 @@ mod tests {
         assert_eq!(get_volume_path("C:\\"), "\\\\.\\C:");
         assert_eq!(get_volume_path("D"), "\\\\.\\D:");
     }
+    
+    #[test]
+    fn test_open_image_with_offset() {
+        let path = std::env::temp_dir().join("disk_reader_image_offset_test.img");
+        let mut data = vec![0u8; 2048];
+        data[1024..1028].copy_from_slice(b"BOOT");
+        std::fs::write(&path, &data).unwrap();
+        
+        let mut disk = DiskReader::open_image(path.to_str().unwrap(), 1024).unwrap();
+        assert_eq!(disk.size(), 1024);
+        assert_eq!(disk.total_sectors(), 2);
+        assert_eq!(&disk.read_boot_sector().unwrap()[0..4], b"BOOT");
+        assert!(DiskReader::open_image(path.to_str().unwrap(), 4096).is_err());
+        
+        std::fs::remove_file(&path).ok();
+    }
+    
+    #[test]
+    fn test_read_runs_range_across_fragments() {
+        let path = std::env::temp_dir().join("disk_reader_runs_test.img");
+        let mut data = vec![0u8; 8 * 512];
+        data[6 * 512..7 * 512].fill(b'A');
+        data[2 * 512..3 * 512].fill(b'B');
+        std::fs::write(&path, &data).unwrap();
+        
+        // Cluster 6, a sparse cluster, then cluster 2 (512-byte clusters)
+        let runs = vec![
+            DataRun { cluster_offset: 6, cluster_count: 1 },
+            DataRun { cluster_offset: 0, cluster_count: 1 },
+            DataRun { cluster_offset: 2, cluster_count: 1 },
+        ];
+        let mut disk = DiskReader::open_image(path.to_str().unwrap(), 0).unwrap();
+        let read = read_runs_range(&mut disk, &runs, 512, 256, 1024).unwrap();
+        std::fs::remove_file(&path).ok();
+        
+        assert!(read[..256].iter().all(|&b| b == b'A'));
+        assert!(read[256..768].iter().all(|&b| b == 0));
+        assert!(read[768..].iter().all(|&b| b == b'B'));
+    }
 }
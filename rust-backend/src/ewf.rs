@@ -0,0 +1,618 @@
+//! EWF (Expert Witness Format) Image Reader
+//! Reads EnCase E01/E02/... evidence files as a linear, seekable media stream
+//!
+//! Supports:
+//! - Segment chaining (.E01 → .E99 → .EAA → ...)
+//! - zlib-compressed and uncompressed chunks
+//! - header/header2 case metadata
+//! - Stored MD5 ("hash") and MD5/SHA1 ("digest") acquisition hashes
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use flate2::read::ZlibDecoder;
+use serde::{Deserialize, Serialize};
+
+/// Signature at the start of every EWF-E01 segment file
+pub const EWF_SIGNATURE: &[u8; 8] = b"EVF\x09\x0D\x0A\xFF\x00";
+
+const FILE_HEADER_SIZE: u64 = 13;
+const SECTION_DESCRIPTOR_SIZE: usize = 76;
+const TABLE_HEADER_SIZE: usize = 24;
+const CHUNK_COMPRESSED_FLAG: u32 = 0x8000_0000;
+const MAX_SEGMENTS: u32 = 14_971; // E01..EZZ..ZZZ
+
+/// Case metadata and acquisition hashes stored in an EWF image
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct EwfInfo {
+    pub segments: Vec<String>,
+    pub media_size: u64,
+    pub bytes_per_sector: u32,
+    pub sectors_per_chunk: u32,
+    pub chunk_count: u64,
+    pub case_number: String,
+    pub evidence_number: String,
+    pub description: String,
+    pub examiner: String,
+    pub notes: String,
+    pub acquisition_software: String,
+    pub acquisition_os: String,
+    pub acquisition_date: String,
+    pub stored_md5: Option<String>,
+    pub stored_sha1: Option<String>,
+}
+
+/// Result of hashing the media stream and comparing with the stored hashes
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EwfVerification {
+    pub computed_md5: String,
+    pub computed_sha1: String,
+    pub md5_match: Option<bool>,
+    pub sha1_match: Option<bool>,
+}
+
+/// Location of one chunk inside a segment file
+#[derive(Debug, Clone)]
+struct ChunkLocation {
+    segment: usize,
+    offset: u64,
+    size: u64,
+    compressed: bool,
+}
+
+/// A parsed section descriptor
+struct Section {
+    kind: String,
+    start: u64,  // Offset of the descriptor in the segment file
+    next: u64,
+    size: u64,
+}
+
+/// Seekable view over the media stored in a (possibly segmented) EWF image
+pub struct EwfImage {
+    segments: Vec<File>,
+    chunks: Vec<ChunkLocation>,
+    chunk_size: u64,
+    info: EwfInfo,
+    position: u64,
+    cached_chunk: Option<(usize, Vec<u8>)>,
+}
+
+/// Check whether data starts with the EWF-E01 segment signature
+pub fn is_ewf(data: &[u8]) -> bool {
+    data.len() >= EWF_SIGNATURE.len() && &data[..EWF_SIGNATURE.len()] == EWF_SIGNATURE
+}
+
+impl EwfImage {
+    /// Open an EWF image from its first segment (e.g. "evidence.E01")
+    pub fn open(path: &str) -> Result<Self, String> {
+        let mut image = EwfImage {
+            segments: Vec::new(),
+            chunks: Vec::new(),
+            chunk_size: 0,
+            info: EwfInfo::default(),
+            position: 0,
+            cached_chunk: None,
+        };
+
+        let first = PathBuf::from(path);
+        let mut segment_number = 1u32;
+        let mut media_size = 0u64;
+
+        loop {
+            let segment_path = if segment_number == 1 {
+                first.clone()
+            } else {
+                segment_file_path(&first, segment_number)
+                    .ok_or_else(|| format!("Too many EWF segments after {}", path))?
+            };
+
+            let mut file = File::open(&segment_path).map_err(|e| {
+                format!("Failed to open EWF segment {}: {}", segment_path.display(), e)
+            })?;
+
+            let mut header = [0u8; FILE_HEADER_SIZE as usize];
+            file.read_exact(&mut header)
+                .map_err(|e| format!("Failed to read EWF segment header: {}", e))?;
+            if !is_ewf(&header) {
+                return Err(format!("{} is not an EWF segment file", segment_path.display()));
+            }
+            let stored_number = u16::from_le_bytes([header[9], header[10]]) as u32;
+            if stored_number != segment_number {
+                return Err(format!(
+                    "EWF segment {} has segment number {} (expected {})",
+                    segment_path.display(), stored_number, segment_number
+                ));
+            }
+
+            let segment_index = image.segments.len();
+            let done = image.parse_segment(&mut file, segment_index, &mut media_size)?;
+
+            image.info.segments.push(segment_path.display().to_string());
+            image.segments.push(file);
+
+            if done {
+                break;
+            }
+            segment_number += 1;
+            if segment_number > MAX_SEGMENTS {
+                return Err("EWF image has no 'done' section".to_string());
+            }
+        }
+
+        if image.chunk_size == 0 {
+            return Err("EWF image has no volume/disk section".to_string());
+        }
+
+        image.info.media_size = media_size;
+        let expected_chunks = media_size.div_ceil(image.chunk_size);
+        if (image.chunks.len() as u64) < expected_chunks {
+            eprintln!(
+                "[EWF] WARNING: {} chunks in tables, {} expected from media size — image may be truncated",
+                image.chunks.len(), expected_chunks
+            );
+        }
+        image.info.chunk_count = image.chunks.len() as u64;
+
+        eprintln!(
+            "[EWF] Opened {} segment(s): {} bytes media, {} chunks of {} bytes",
+            image.segments.len(), media_size, image.chunks.len(), image.chunk_size
+        );
+
+        Ok(image)
+    }
+
+    /// Walk all sections of one segment file. Returns true when the "done" section was seen.
+    fn parse_segment(&mut self, file: &mut File, segment_index: usize, media_size: &mut u64) -> Result<bool, String> {
+        let file_size = file.metadata().map_err(|e| e.to_string())?.len();
+        let mut offset = FILE_HEADER_SIZE;
+        let mut sectors_end: Option<u64> = None;
+
+        while offset + SECTION_DESCRIPTOR_SIZE as u64 <= file_size {
+            let section = read_section_descriptor(file, offset)?;
+            let data_offset = offset + SECTION_DESCRIPTOR_SIZE as u64;
+
+            match section.kind.as_str() {
+                "header" | "header2" => {
+                    let raw = read_range(file, data_offset, section.size.saturating_sub(SECTION_DESCRIPTOR_SIZE as u64) as usize)?;
+                    if let Some(text) = decode_header_section(&raw, section.kind == "header2") {
+                        // header2 is the richer (UTF-16) copy; header only fills blanks
+                        apply_header_values(&mut self.info, &text, section.kind == "header2");
+                    }
+                }
+                "volume" | "disk" | "data" if self.chunk_size == 0 => {
+                    let raw = read_range(file, data_offset, 24)?;
+                    let sectors_per_chunk = u32::from_le_bytes([raw[8], raw[9], raw[10], raw[11]]);
+                    let bytes_per_sector = u32::from_le_bytes([raw[12], raw[13], raw[14], raw[15]]);
+                    let sector_count = u64::from_le_bytes([
+                        raw[16], raw[17], raw[18], raw[19], raw[20], raw[21], raw[22], raw[23],
+                    ]);
+                    if sectors_per_chunk == 0 || bytes_per_sector == 0 {
+                        return Err("EWF volume section has zero chunk geometry".to_string());
+                    }
+                    self.info.sectors_per_chunk = sectors_per_chunk;
+                    self.info.bytes_per_sector = bytes_per_sector;
+                    self.chunk_size = sectors_per_chunk as u64 * bytes_per_sector as u64;
+                    *media_size = sector_count * bytes_per_sector as u64;
+                }
+                "sectors" => {
+                    sectors_end = Some(section.start + section.size);
+                }
+                "table" => {
+                    let section_end = section.start + section.size;
+                    let chunk_data_end = sectors_end.take().unwrap_or(section_end);
+                    self.parse_table(file, segment_index, data_offset, chunk_data_end)?;
+                }
+                "hash" => {
+                    let raw = read_range(file, data_offset, 16)?;
+                    if raw.iter().any(|&b| b != 0) {
+                        self.info.stored_md5 = Some(hex::encode(&raw));
+                    }
+                }
+                "digest" => {
+                    let raw = read_range(file, data_offset, 36)?;
+                    if raw[..16].iter().any(|&b| b != 0) {
+                        self.info.stored_md5 = Some(hex::encode(&raw[..16]));
+                    }
+                    if raw[16..36].iter().any(|&b| b != 0) {
+                        self.info.stored_sha1 = Some(hex::encode(&raw[16..36]));
+                    }
+                }
+                "done" => return Ok(true),
+                "next" => return Ok(false),
+                _ => {} // table2, error2, session, ltree, ...: not needed for reading
+            }
+
+            // "next"/"done" point at themselves; anything else must move forward
+            if section.next <= offset {
+                break;
+            }
+            offset = section.next;
+        }
+
+        Err(format!("EWF segment {} ended without a 'next' or 'done' section", segment_index + 1))
+    }
+
+    /// Parse a table section's chunk offsets
+    fn parse_table(&mut self, file: &mut File, segment_index: usize, data_offset: u64, chunk_data_end: u64) -> Result<(), String> {
+        let header = read_range(file, data_offset, TABLE_HEADER_SIZE)?;
+        let entry_count = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let base_offset = u64::from_le_bytes([
+            header[8], header[9], header[10], header[11], header[12], header[13], header[14], header[15],
+        ]);
+
+        let raw = read_range(file, data_offset + TABLE_HEADER_SIZE as u64, entry_count * 4)?;
+        let entries: Vec<(u64, bool)> = raw
+            .chunks_exact(4)
+            .map(|e| {
+                let value = u32::from_le_bytes([e[0], e[1], e[2], e[3]]);
+                (
+                    base_offset + (value & !CHUNK_COMPRESSED_FLAG) as u64,
+                    value & CHUNK_COMPRESSED_FLAG != 0,
+                )
+            })
+            .collect();
+
+        for (i, &(offset, compressed)) in entries.iter().enumerate() {
+            let end = entries.get(i + 1).map(|e| e.0).unwrap_or(chunk_data_end);
+            self.chunks.push(ChunkLocation {
+                segment: segment_index,
+                offset,
+                size: end.saturating_sub(offset),
+                compressed,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Metadata and stored hashes
+    pub fn info(&self) -> &EwfInfo {
+        &self.info
+    }
+
+    /// Size of the acquired media in bytes
+    pub fn media_size(&self) -> u64 {
+        self.info.media_size
+    }
+
+    /// Bytes per sector of the acquired media
+    pub fn bytes_per_sector(&self) -> usize {
+        self.info.bytes_per_sector as usize
+    }
+
+    /// Read and decode one chunk (served from a single-chunk cache)
+    fn load_chunk(&mut self, index: usize) -> Result<&[u8], String> {
+        if self.cached_chunk.as_ref().map(|(i, _)| *i) != Some(index) {
+            let location = self.chunks.get(index)
+                .ok_or_else(|| format!("EWF chunk {} out of range", index))?
+                .clone();
+            let expected = (self.media_size() - index as u64 * self.chunk_size).min(self.chunk_size) as usize;
+            let raw = read_range(&mut self.segments[location.segment], location.offset, location.size as usize)?;
+
+            let data = if location.compressed {
+                let mut decoded = Vec::with_capacity(self.chunk_size as usize);
+                ZlibDecoder::new(&raw[..])
+                    .read_to_end(&mut decoded)
+                    .map_err(|e| format!("Failed to decompress EWF chunk {}: {}", index, e))?;
+                decoded
+            } else {
+                // Uncompressed chunks carry a trailing Adler-32 checksum
+                raw[..raw.len().min(expected)].to_vec()
+            };
+
+            if data.len() < expected {
+                return Err(format!(
+                    "EWF chunk {} is short: {} of {} bytes", index, data.len(), expected
+                ));
+            }
+            self.cached_chunk = Some((index, data));
+        }
+        Ok(&self.cached_chunk.as_ref().unwrap().1)
+    }
+
+    /// Hash the whole media stream and compare with the stored acquisition hashes
+    pub fn verify(&mut self) -> Result<EwfVerification, String> {
+        use md5::{Digest, Md5};
+        use sha1::Sha1;
+
+        let mut md5 = Md5::new();
+        let mut sha1 = Sha1::new();
+        let chunk_total = self.media_size().div_ceil(self.chunk_size) as usize;
+
+        for index in 0..chunk_total {
+            let expected = (self.media_size() - index as u64 * self.chunk_size).min(self.chunk_size) as usize;
+            let chunk = self.load_chunk(index)?;
+            md5.update(&chunk[..expected]);
+            sha1.update(&chunk[..expected]);
+        }
+
+        let computed_md5 = hex::encode(md5.finalize());
+        let computed_sha1 = hex::encode(sha1.finalize());
+
+        Ok(EwfVerification {
+            md5_match: self.info.stored_md5.as_ref().map(|h| h.eq_ignore_ascii_case(&computed_md5)),
+            sha1_match: self.info.stored_sha1.as_ref().map(|h| h.eq_ignore_ascii_case(&computed_sha1)),
+            computed_md5,
+            computed_sha1,
+        })
+    }
+}
+
+impl Read for EwfImage {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut written = 0;
+
+        while written < buf.len() && self.position < self.media_size() {
+            let index = (self.position / self.chunk_size) as usize;
+            let within = (self.position % self.chunk_size) as usize;
+            let remaining_media = (self.media_size() - self.position) as usize;
+            let chunk = self.load_chunk(index)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+            let available = chunk.len().saturating_sub(within);
+            let count = available.min(buf.len() - written).min(remaining_media);
+            if count == 0 {
+                break;
+            }
+            buf[written..written + count].copy_from_slice(&chunk[within..within + count]);
+            written += count;
+            self.position += count as u64;
+        }
+
+        Ok(written)
+    }
+}
+
+impl Seek for EwfImage {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => offset as i128,
+            SeekFrom::End(delta) => self.media_size() as i128 + delta as i128,
+            SeekFrom::Current(delta) => self.position as i128 + delta as i128,
+        };
+        if target < 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "seek before start of EWF media",
+            ));
+        }
+        self.position = target as u64;
+        Ok(self.position)
+    }
+}
+
+/// Read a section descriptor and verify its Adler-32 checksum
+fn read_section_descriptor(file: &mut File, offset: u64) -> Result<Section, String> {
+    let raw = read_range(file, offset, SECTION_DESCRIPTOR_SIZE)?;
+
+    let kind_end = raw[..16].iter().position(|&b| b == 0).unwrap_or(16);
+    let kind = String::from_utf8_lossy(&raw[..kind_end]).to_string();
+    let next = u64::from_le_bytes([raw[16], raw[17], raw[18], raw[19], raw[20], raw[21], raw[22], raw[23]]);
+    let size = u64::from_le_bytes([raw[24], raw[25], raw[26], raw[27], raw[28], raw[29], raw[30], raw[31]]);
+    let stored_checksum = u32::from_le_bytes([raw[72], raw[73], raw[74], raw[75]]);
+
+    if adler32(&raw[..72]) != stored_checksum {
+        eprintln!("[EWF] WARNING: checksum mismatch in '{}' section descriptor at offset {}", kind, offset);
+    }
+
+    Ok(Section { kind, start: offset, next, size })
+}
+
+/// Read exactly `len` bytes at `offset`
+fn read_range(file: &mut File, offset: u64, len: usize) -> Result<Vec<u8>, String> {
+    let mut buffer = vec![0u8; len];
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| format!("Failed to seek to EWF offset {}: {}", offset, e))?;
+    file.read_exact(&mut buffer)
+        .map_err(|e| format!("Failed to read {} bytes at EWF offset {}: {}", len, offset, e))?;
+    Ok(buffer)
+}
+
+/// Adler-32 as used for EWF section and chunk checksums
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+/// Decompress a header/header2 section into text
+fn decode_header_section(raw: &[u8], utf16: bool) -> Option<String> {
+    let mut decoded = Vec::new();
+    ZlibDecoder::new(raw).read_to_end(&mut decoded).ok()?;
+
+    if utf16 {
+        let units: Vec<u16> = decoded
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        Some(String::from_utf16_lossy(&units).trim_start_matches('\u{feff}').to_string())
+    } else {
+        Some(String::from_utf8_lossy(&decoded).to_string())
+    }
+}
+
+/// Map the tab-separated header key/value rows onto EwfInfo
+/// Layout: "1" / "main" / keys / values
+fn apply_header_values(info: &mut EwfInfo, text: &str, overwrite: bool) {
+    let lines: Vec<&str> = text.lines().map(|l| l.trim_end_matches('\r')).collect();
+    if lines.len() < 4 {
+        return;
+    }
+
+    for (key, value) in lines[2].split('\t').zip(lines[3].split('\t')) {
+        let field = match key {
+            "c" => &mut info.case_number,
+            "n" => &mut info.evidence_number,
+            "a" => &mut info.description,
+            "e" => &mut info.examiner,
+            "t" => &mut info.notes,
+            "av" => &mut info.acquisition_software,
+            "ov" => &mut info.acquisition_os,
+            "m" => &mut info.acquisition_date,
+            _ => continue,
+        };
+        if overwrite || field.is_empty() {
+            *field = value.to_string();
+        }
+    }
+}
+
+/// Path of segment `number` (1-based) given the first segment's path.
+/// Numbering: E01..E99, then EAA..EZZ, FAA..ZZZ (case follows the first segment).
+fn segment_file_path(first: &Path, number: u32) -> Option<PathBuf> {
+    let extension = first.extension()?.to_string_lossy().to_string();
+    let lowercase = extension.chars().next()?.is_ascii_lowercase();
+    let first_letter = extension.chars().next()?.to_ascii_uppercase() as u8;
+
+    let new_extension = if number <= 99 {
+        format!("{}{:02}", first_letter as char, number)
+    } else {
+        let n = number - 100;
+        let third = (n % 26) as u8;
+        let second = ((n / 26) % 26) as u8;
+        let first_offset = (n / (26 * 26)) as u8;
+        let letter = first_letter.checked_add(first_offset).filter(|l| *l <= b'Z')?;
+        format!("{}{}{}", letter as char, (b'A' + second) as char, (b'A' + third) as char)
+    };
+
+    let new_extension = if lowercase {
+        new_extension.to_lowercase()
+    } else {
+        new_extension
+    };
+    Some(first.with_extension(new_extension))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn zlib(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn section(out: &mut Vec<u8>, kind: &str, payload: &[u8], last: bool) {
+        let start = out.len() as u64;
+        let size = (SECTION_DESCRIPTOR_SIZE + payload.len()) as u64;
+        let mut descriptor = vec![0u8; SECTION_DESCRIPTOR_SIZE];
+        descriptor[..kind.len()].copy_from_slice(kind.as_bytes());
+        let next = if last { start } else { start + size };
+        descriptor[16..24].copy_from_slice(&next.to_le_bytes());
+        descriptor[24..32].copy_from_slice(&size.to_le_bytes());
+        let checksum = adler32(&descriptor[..72]);
+        descriptor[72..76].copy_from_slice(&checksum.to_le_bytes());
+        out.extend_from_slice(&descriptor);
+        out.extend_from_slice(payload);
+    }
+
+    /// Build one segment holding `chunks` (compressed when the flag is set)
+    fn segment(number: u16, chunks: &[(Vec<u8>, bool)], media_sectors: u64, last: bool) -> Vec<u8> {
+        let mut out = EWF_SIGNATURE.to_vec();
+        out.push(1);
+        out.extend_from_slice(&number.to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+
+        if number == 1 {
+            let header = "1\nmain\nc\tn\te\tt\nCASE-7\tEV-1\tJ. Doe\tseized laptop\n\n";
+            section(&mut out, "header", &zlib(header.as_bytes()), false);
+            let mut volume = vec![0u8; 1052];
+            volume[8..12].copy_from_slice(&2u32.to_le_bytes()); // 2 sectors per chunk
+            volume[12..16].copy_from_slice(&512u32.to_le_bytes());
+            volume[16..24].copy_from_slice(&media_sectors.to_le_bytes());
+            section(&mut out, "volume", &volume, false);
+        }
+
+        // sectors section followed by its table
+        let sectors_start = out.len();
+        let mut payload = Vec::new();
+        let mut offsets = Vec::new();
+        for (data, compressed) in chunks {
+            let absolute = sectors_start + SECTION_DESCRIPTOR_SIZE + payload.len();
+            offsets.push(absolute as u32 | if *compressed { CHUNK_COMPRESSED_FLAG } else { 0 });
+            if *compressed {
+                payload.extend_from_slice(&zlib(data));
+            } else {
+                payload.extend_from_slice(data);
+                payload.extend_from_slice(&adler32(data).to_le_bytes());
+            }
+        }
+        section(&mut out, "sectors", &payload, false);
+
+        let mut table = vec![0u8; TABLE_HEADER_SIZE];
+        table[0..4].copy_from_slice(&(offsets.len() as u32).to_le_bytes());
+        for offset in offsets {
+            table.extend_from_slice(&offset.to_le_bytes());
+        }
+        table.extend_from_slice(&[0u8; 4]);
+        section(&mut out, "table", &table, false);
+
+        if last {
+            let mut digest = vec![0u8; 80];
+            digest[..16].copy_from_slice(&[0xAB; 16]);
+            digest[16..36].copy_from_slice(&[0xCD; 20]);
+            section(&mut out, "digest", &digest, false);
+            section(&mut out, "done", &[], true);
+        } else {
+            section(&mut out, "next", &[], true);
+        }
+        out
+    }
+
+    #[test]
+    fn test_segment_naming() {
+        let first = Path::new("/cases/disk.E01");
+        assert_eq!(segment_file_path(first, 2).unwrap(), Path::new("/cases/disk.E02"));
+        assert_eq!(segment_file_path(first, 99).unwrap(), Path::new("/cases/disk.E99"));
+        assert_eq!(segment_file_path(first, 100).unwrap(), Path::new("/cases/disk.EAA"));
+        assert_eq!(segment_file_path(first, 127).unwrap(), Path::new("/cases/disk.EBB"));
+        assert_eq!(segment_file_path(Path::new("disk.e01"), 100).unwrap(), Path::new("disk.eaa"));
+    }
+
+    #[test]
+    fn test_read_segmented_image() {
+        let chunk = |fill: u8| vec![fill; 1024];
+        let dir = std::env::temp_dir();
+        let first = dir.join("ewf_reader_test.E01");
+        let second = dir.join("ewf_reader_test.E02");
+
+        // 5 sectors of media: chunks of 2 sectors, last chunk holds 1 sector
+        std::fs::write(&first, segment(1, &[(chunk(0x11), true), (chunk(0x22), false)], 5, false)).unwrap();
+        std::fs::write(&second, segment(2, &[(vec![0x33; 512], true)], 5, true)).unwrap();
+
+        let mut image = EwfImage::open(first.to_str().unwrap()).unwrap();
+        assert_eq!(image.media_size(), 2560);
+        assert_eq!(image.bytes_per_sector(), 512);
+        assert_eq!(image.info().segments.len(), 2);
+        assert_eq!(image.info().case_number, "CASE-7");
+        assert_eq!(image.info().examiner, "J. Doe");
+        assert_eq!(image.info().stored_md5.as_deref(), Some("abababababababababababababababab"));
+
+        // Read across the compressed/uncompressed chunk and segment boundaries
+        image.seek(SeekFrom::Start(1000)).unwrap();
+        let mut buffer = vec![0u8; 1200];
+        image.read_exact(&mut buffer).unwrap();
+        assert!(buffer[..24].iter().all(|&b| b == 0x11));
+        assert!(buffer[24..1048].iter().all(|&b| b == 0x22));
+        assert!(buffer[1048..].iter().all(|&b| b == 0x33));
+
+        let mut rest = Vec::new();
+        image.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest.len(), 2560 - 2200);
+
+        let verification = image.verify().unwrap();
+        assert_eq!(verification.md5_match, Some(false));
+
+        std::fs::remove_file(&first).ok();
+        std::fs::remove_file(&second).ok();
+    }
+}
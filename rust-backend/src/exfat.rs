@@ -0,0 +1,626 @@
+//! exFAT Module
+//! Parses exFAT volumes (boot region checksum, allocation bitmap, up-case
+//! table, FAT) and finds deleted directory entry sets
+//!
+//! Deleting a file only clears the InUse bit of each entry of its set (File,
+//! Stream Extension, File Name) and frees its clusters in the bitmap; the
+//! first cluster, lengths and NoFatChain flag survive. Contiguous files are
+//! read straight from the first cluster, chained ones follow the FAT entries
+//! exFAT leaves behind. The bitmap tells which clusters were reused since.
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::directory_tree::MAX_PATH_DEPTH;
+use crate::disk_reader::DiskReader;
+use crate::fat::{read_cluster_runs, read_directory_clusters};
+use crate::ntfs_parser::{ClusterBitmap, DataRun};
+use crate::partition::exfat_boot_checksum_valid;
+
+const DIRECTORY_ENTRY_SIZE: usize = 32;
+
+/// Entry types with the InUse bit (0x80) masked off
+const ENTRY_ALLOCATION_BITMAP: u8 = 0x01;
+const ENTRY_UPCASE_TABLE: u8 = 0x02;
+const ENTRY_FILE: u8 = 0x05;
+const ENTRY_STREAM_EXTENSION: u8 = 0x40;
+const ENTRY_FILE_NAME: u8 = 0x41;
+const ENTRY_IN_USE: u8 = 0x80;
+
+const ATTRIBUTE_DIRECTORY: u16 = 0x10;
+
+/// GeneralSecondaryFlags: the clusters are contiguous and the FAT is not kept
+const FLAG_NO_FAT_CHAIN: u8 = 0x02;
+
+/// Boot region: boot sector, 8 extended boot sectors, OEM parameters,
+/// reserved sector and checksum sector; the backup region follows it
+const BOOT_REGION_SECTORS: usize = 12;
+
+/// Geometry from the main (or backup) boot sector
+#[derive(Debug, Clone)]
+pub struct ExfatBootSector {
+    pub bytes_per_sector: u32,
+    pub sectors_per_cluster: u32,
+    pub cluster_size: u32,
+    pub volume_length: u64,       // Sectors
+    pub fat_offset: u64,          // Sectors
+    pub fat_length: u64,          // Sectors per FAT
+    pub active_fat: u8,           // VolumeFlags bit 0: which FAT and bitmap are current
+    pub cluster_heap_offset: u64, // Sectors
+    pub cluster_count: u32,
+    pub root_cluster: u32,
+    pub volume_serial: u32,
+}
+
+impl ExfatBootSector {
+    /// Byte offset of a cluster of the cluster heap
+    pub fn cluster_offset(&self, cluster: u32) -> u64 {
+        (self.cluster_heap_offset + (cluster as u64 - 2) * self.sectors_per_cluster as u64) * self.bytes_per_sector as u64
+    }
+
+    pub fn is_valid_cluster(&self, cluster: u32) -> bool {
+        cluster >= 2 && cluster - 2 < self.cluster_count
+    }
+}
+
+/// Parse an exFAT boot sector; the boot region checksum is checked separately
+pub fn parse_exfat_boot_sector(data: &[u8]) -> Option<ExfatBootSector> {
+    if data.len() < 512 || &data[3..11] != b"EXFAT   " || data[11..64].iter().any(|&b| b != 0) || data[510..512] != [0x55, 0xAA] {
+        return None;
+    }
+    let u32_at = |at: usize| u32::from_le_bytes(data[at..at + 4].try_into().unwrap());
+    let bytes_per_sector_shift = data[108];
+    let sectors_per_cluster_shift = data[109];
+    let fat_count = data[110];
+    if !(9..=12).contains(&bytes_per_sector_shift)
+        || sectors_per_cluster_shift > 25 - bytes_per_sector_shift
+        || !(1..=2).contains(&fat_count)
+    {
+        return None;
+    }
+
+    let boot = ExfatBootSector {
+        bytes_per_sector: 1 << bytes_per_sector_shift,
+        sectors_per_cluster: 1 << sectors_per_cluster_shift,
+        cluster_size: 1 << (bytes_per_sector_shift + sectors_per_cluster_shift),
+        volume_length: u64::from_le_bytes(data[72..80].try_into().unwrap()),
+        fat_offset: u32_at(80) as u64,
+        fat_length: u32_at(84) as u64,
+        active_fat: (data[106] & 1).min(fat_count - 1),
+        cluster_heap_offset: u32_at(88) as u64,
+        cluster_count: u32_at(92),
+        root_cluster: u32_at(96),
+        volume_serial: u32_at(100),
+    };
+    let heap_sectors = boot.cluster_count as u64 * boot.sectors_per_cluster as u64;
+    let plausible = boot.fat_offset >= 24
+        && boot.fat_length * boot.bytes_per_sector as u64 >= (boot.cluster_count as u64 + 2) * 4
+        && boot.cluster_heap_offset >= boot.fat_offset + boot.fat_length * fat_count as u64
+        && boot.cluster_count > 0
+        && boot.cluster_heap_offset + heap_sectors <= boot.volume_length
+        && boot.is_valid_cluster(boot.root_cluster);
+    plausible.then_some(boot)
+}
+
+/// Boot sector of the main boot region, or of the backup region when the
+/// main one fails its checksum
+pub fn read_boot_region(disk: &mut DiskReader) -> Option<ExfatBootSector> {
+    for bytes_per_sector in [512usize, 1024, 2048, 4096] {
+        let length = BOOT_REGION_SECTORS * bytes_per_sector;
+        for start in [0, length as u64] {
+            let Ok(region) = disk.read_at(start, length) else { continue };
+            let Some(boot) = parse_exfat_boot_sector(&region) else { continue };
+            if boot.bytes_per_sector as usize == bytes_per_sector && exfat_boot_checksum_valid(&region, bytes_per_sector) {
+                if start > 0 {
+                    eprintln!("DEBUG: exFAT main boot region fails its checksum, using the backup region");
+                }
+                return Some(boot);
+            }
+        }
+    }
+    None
+}
+
+/// The up-case table maps each UTF-16 unit to its upper case; names compare
+/// and hash through it
+pub struct UpcaseTable {
+    map: Vec<u16>,
+}
+
+impl UpcaseTable {
+    /// Decode a (usually compressed) table: 0xFFFF followed by a count stands
+    /// for that many characters that map to themselves
+    pub fn parse(data: &[u8]) -> UpcaseTable {
+        let mut map = Vec::new();
+        let mut units = data.chunks_exact(2).map(|u| u16::from_le_bytes([u[0], u[1]]));
+        while let Some(unit) = units.next() {
+            if map.len() > u16::MAX as usize {
+                break;
+            }
+            if unit == 0xFFFF {
+                let start = map.len();
+                let count = units.next().unwrap_or(0) as usize;
+                map.extend((start..(start + count).min(u16::MAX as usize + 1)).map(|c| c as u16));
+            } else {
+                map.push(unit);
+            }
+        }
+        UpcaseTable { map }
+    }
+
+    /// Fallback when the volume's table is unreadable: ASCII letters only
+    pub fn ascii() -> UpcaseTable {
+        UpcaseTable { map: (0..128u16).map(|c| if (b'a' as u16..=b'z' as u16).contains(&c) { c - 32 } else { c }).collect() }
+    }
+
+    pub fn upcase(&self, unit: u16) -> u16 {
+        self.map.get(unit as usize).copied().unwrap_or(unit)
+    }
+
+    /// NameHash of the Stream Extension entry, over the up-cased name
+    pub fn name_hash(&self, name: &[u16]) -> u16 {
+        name.iter()
+            .flat_map(|&unit| self.upcase(unit).to_le_bytes())
+            .fold(0u16, |hash, byte| hash.rotate_right(1).wrapping_add(byte as u16))
+    }
+}
+
+/// TableChecksum of the up-case table entry
+fn upcase_table_checksum(data: &[u8]) -> u32 {
+    data.iter().fold(0u32, |sum, &byte| sum.rotate_right(1).wrapping_add(byte as u32))
+}
+
+/// SetChecksum of a File entry, over the whole set but its own checksum field.
+/// A deleted set was summed while its entries were still in use.
+fn entry_set_checksum(set: &[u8], deleted: bool) -> u16 {
+    set.iter().enumerate()
+        .filter(|&(i, _)| i != 2 && i != 3)
+        .map(|(i, &byte)| if deleted && i % DIRECTORY_ENTRY_SIZE == 0 { byte | ENTRY_IN_USE } else { byte })
+        .fold(0u16, |sum, byte| sum.rotate_right(1).wrapping_add(byte as u16))
+}
+
+/// One exFAT timestamp: local time and, when it was recorded, the offset
+/// from UTC in minutes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExfatTime {
+    pub local: NaiveDateTime,
+    pub utc_offset: Option<i32>,
+}
+
+impl ExfatTime {
+    /// `increment` adds 0-199 hundredths of a second; the offset byte holds a
+    /// valid flag and a signed count of 15-minute steps
+    fn decode(timestamp: u32, increment: u8, utc_offset: u8) -> Option<ExfatTime> {
+        let date = NaiveDate::from_ymd_opt(1980 + (timestamp >> 25) as i32, (timestamp >> 21) & 0x0F, (timestamp >> 16) & 0x1F)?;
+        let seconds = (timestamp & 0x1F) * 2 + increment as u32 / 100;
+        let local = date.and_hms_milli_opt((timestamp >> 11) & 0x1F, (timestamp >> 5) & 0x3F, seconds, (increment as u32 % 100) * 10)?;
+        let utc_offset = (utc_offset & 0x80 != 0).then(|| (((utc_offset << 1) as i8) >> 1) as i32 * 15);
+        Some(ExfatTime { local, utc_offset })
+    }
+
+    /// ISO-8601 at 10 ms precision, with the offset when it is known
+    pub fn iso8601(&self) -> String {
+        let local = self.local.format("%Y-%m-%dT%H:%M:%S%.3f");
+        match self.utc_offset {
+            Some(minutes) => format!("{}{}{:02}:{:02}", local, if minutes < 0 { '-' } else { '+' }, minutes.abs() / 60, minutes.abs() % 60),
+            None => local.to_string(),
+        }
+    }
+
+    /// UTC when the offset is known, else the local time as recorded
+    pub fn best_utc(&self) -> NaiveDateTime {
+        self.local - chrono::Duration::minutes(self.utc_offset.unwrap_or(0) as i64)
+    }
+}
+
+/// The times of a file as exFAT keeps them
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ExfatTimestamps {
+    pub created: Option<String>, // ISO-8601, "+hh:mm" suffixed when the UTC offset was recorded
+    pub modified: Option<String>,
+    pub accessed: Option<String>,
+}
+
+/// One File / Stream Extension / File Name entry set
+#[derive(Debug, Clone)]
+pub struct EntrySet {
+    pub name: String,
+    pub is_deleted: bool,
+    pub is_directory: bool,
+    pub first_cluster: u32,
+    pub data_length: u64,
+    pub valid_data_length: u64,
+    pub no_fat_chain: bool,
+    pub created: Option<ExfatTime>,
+    pub modified: Option<ExfatTime>,
+    pub accessed: Option<ExfatTime>,
+    pub checksum_valid: bool, // SetChecksum and NameHash both still match
+    pub offset: usize,        // Byte offset of the File entry within the directory
+}
+
+impl EntrySet {
+    pub fn timestamps(&self) -> ExfatTimestamps {
+        ExfatTimestamps {
+            created: self.created.map(|t| t.iso8601()),
+            modified: self.modified.map(|t| t.iso8601()),
+            accessed: self.accessed.map(|t| t.iso8601()),
+        }
+    }
+}
+
+/// Parse the entry sets of a directory, deleted ones included. A deleted set
+/// only counts while none of its entries has been taken by a newer set.
+pub fn parse_directory(data: &[u8], upcase: &UpcaseTable) -> Vec<EntrySet> {
+    let mut sets = Vec::new();
+    let mut at = 0;
+    while at + DIRECTORY_ENTRY_SIZE <= data.len() {
+        let entry_type = data[at];
+        if entry_type == 0x00 {
+            break;
+        }
+        match parse_entry_set(&data[at..], upcase) {
+            Some(mut set) => {
+                set.offset = at;
+                at += (data[at + 1] as usize + 1) * DIRECTORY_ENTRY_SIZE;
+                sets.push(set);
+            }
+            None => at += DIRECTORY_ENTRY_SIZE,
+        }
+    }
+    sets
+}
+
+fn parse_entry_set(data: &[u8], upcase: &UpcaseTable) -> Option<EntrySet> {
+    let primary = &data[..DIRECTORY_ENTRY_SIZE];
+    if primary[0] & !ENTRY_IN_USE != ENTRY_FILE {
+        return None;
+    }
+    let is_deleted = primary[0] & ENTRY_IN_USE == 0;
+    let secondary_count = primary[1] as usize;
+    let set = data.get(..(secondary_count + 1) * DIRECTORY_ENTRY_SIZE)?;
+    let entries: Vec<&[u8]> = set.chunks_exact(DIRECTORY_ENTRY_SIZE).collect();
+    // Every entry of a set shares its InUse state
+    if !(2..=18).contains(&secondary_count) || entries.iter().any(|e| (e[0] & ENTRY_IN_USE == 0) != is_deleted) {
+        return None;
+    }
+
+    let stream = entries[1];
+    let name_length = stream[3] as usize;
+    let name_entries = &entries[2..];
+    if stream[0] & !ENTRY_IN_USE != ENTRY_STREAM_EXTENSION
+        || name_length == 0
+        || name_entries.len() < name_length.div_ceil(15)
+        || name_entries.iter().take(name_length.div_ceil(15)).any(|e| e[0] & !ENTRY_IN_USE != ENTRY_FILE_NAME)
+    {
+        return None;
+    }
+    let units: Vec<u16> = name_entries.iter()
+        .flat_map(|e| e[2..32].chunks_exact(2).map(|u| u16::from_le_bytes([u[0], u[1]])))
+        .take(name_length)
+        .collect();
+
+    let u16_at = |entry: &[u8], at: usize| u16::from_le_bytes([entry[at], entry[at + 1]]);
+    let u32_at = |entry: &[u8], at: usize| u32::from_le_bytes(entry[at..at + 4].try_into().unwrap());
+    let u64_at = |entry: &[u8], at: usize| u64::from_le_bytes(entry[at..at + 8].try_into().unwrap());
+    let checksum_valid = u16_at(primary, 2) == entry_set_checksum(set, is_deleted)
+        && u16_at(stream, 4) == upcase.name_hash(&units);
+
+    Some(EntrySet {
+        name: String::from_utf16_lossy(&units),
+        is_deleted,
+        is_directory: u16_at(primary, 4) & ATTRIBUTE_DIRECTORY != 0,
+        first_cluster: u32_at(stream, 20),
+        data_length: u64_at(stream, 24),
+        valid_data_length: u64_at(stream, 8),
+        no_fat_chain: stream[1] & FLAG_NO_FAT_CHAIN != 0,
+        created: ExfatTime::decode(u32_at(primary, 8), primary[20], primary[22]),
+        modified: ExfatTime::decode(u32_at(primary, 12), primary[21], primary[23]),
+        accessed: ExfatTime::decode(u32_at(primary, 16), 0, primary[24]),
+        checksum_valid,
+        offset: 0,
+    })
+}
+
+/// The allocation bitmap or up-case table, as its root directory entry describes it
+struct MetadataStream {
+    first_cluster: u32,
+    length: u64,
+    checksum: u32, // TableChecksum; up-case table only
+}
+
+/// The active allocation bitmap and the up-case table, from the root directory
+fn root_metadata(root: &[u8], active_fat: u8) -> (Option<MetadataStream>, Option<MetadataStream>) {
+    let mut bitmap = None;
+    let mut upcase = None;
+    for entry in root.chunks_exact(DIRECTORY_ENTRY_SIZE) {
+        let stream = MetadataStream {
+            first_cluster: u32::from_le_bytes(entry[20..24].try_into().unwrap()),
+            length: u64::from_le_bytes(entry[24..32].try_into().unwrap()),
+            checksum: u32::from_le_bytes(entry[4..8].try_into().unwrap()),
+        };
+        match entry[0] {
+            0x00 => break,
+            // With two FATs there are two bitmaps; bit 0 of the flags says which
+            t if t == ENTRY_ALLOCATION_BITMAP | ENTRY_IN_USE && entry[1] & 1 == active_fat => bitmap = Some(stream),
+            t if t == ENTRY_UPCASE_TABLE | ENTRY_IN_USE => upcase = Some(stream),
+            _ => {}
+        }
+    }
+    (bitmap, upcase)
+}
+
+/// Where a deleted file's content lies
+#[derive(Debug, Clone)]
+pub struct FileLayout {
+    pub runs: Vec<DataRun>, // Cluster numbers, not volume clusters
+    pub clusters: u64,      // Fewer than the size needs when the FAT chain breaks
+    pub allocated: u64,     // Clusters the bitmap has handed out again since
+}
+
+/// An exFAT volume ready to be walked
+pub struct ExfatVolume {
+    pub boot: ExfatBootSector,
+    pub bitmap: ClusterBitmap,
+    fat: Vec<u32>,
+    upcase: UpcaseTable,
+}
+
+impl ExfatVolume {
+    pub fn open(disk: &mut DiskReader, boot: ExfatBootSector) -> Result<ExfatVolume, String> {
+        let fat_offset = (boot.fat_offset + boot.active_fat as u64 * boot.fat_length) * boot.bytes_per_sector as u64;
+        let fat_bytes = (boot.cluster_count as usize + 2) * 4;
+        let fat = disk.read_at(fat_offset, fat_bytes)
+            .map_err(|e| format!("Failed to read the FAT: {}", e))?
+            .chunks_exact(4)
+            .map(|e| u32::from_le_bytes([e[0], e[1], e[2], e[3]]))
+            .collect();
+        let mut volume = ExfatVolume {
+            boot,
+            bitmap: ClusterBitmap::new(Vec::new()),
+            fat,
+            upcase: UpcaseTable::ascii(),
+        };
+
+        let (root, _) = volume.read_clusters(disk, &volume.root_clusters());
+        let (bitmap, upcase) = root_metadata(&root, volume.boot.active_fat);
+
+        let bitmap = bitmap.ok_or("Root directory has no allocation bitmap entry")?;
+        volume.bitmap = ClusterBitmap::new(volume.read_stream(disk, bitmap.first_cluster, bitmap.length)?);
+
+        match upcase {
+            Some(upcase) => {
+                let table = volume.read_stream(disk, upcase.first_cluster, upcase.length)?;
+                if upcase_table_checksum(&table) == upcase.checksum {
+                    volume.upcase = UpcaseTable::parse(&table);
+                } else {
+                    eprintln!("DEBUG: exFAT up-case table fails its checksum, matching names by ASCII case only");
+                }
+            }
+            None => eprintln!("DEBUG: Root directory has no up-case table entry, matching names by ASCII case only"),
+        }
+        Ok(volume)
+    }
+
+    /// The root directory has no entry of its own; its length is its FAT chain
+    fn root_clusters(&self) -> Vec<u32> {
+        self.chain(self.boot.root_cluster, u64::MAX)
+    }
+
+    /// Whether the bitmap shows a cluster as in use
+    pub fn is_allocated(&self, cluster: u32) -> bool {
+        cluster < 2 || self.bitmap.is_allocated(cluster as u64 - 2)
+    }
+
+    /// Free clusters as (first cluster, count) extents
+    pub fn free_extents(&self) -> Vec<(u32, u32)> {
+        self.bitmap.free_extents().into_iter()
+            .filter(|&(index, _)| index < self.boot.cluster_count as u64)
+            .map(|(index, count)| (index as u32 + 2, count.min(self.boot.cluster_count as u64 - index) as u32))
+            .collect()
+    }
+
+    /// Up to `limit` clusters of a FAT chain; stops at a free, bad or
+    /// end-of-chain entry and at a loop
+    fn chain(&self, first: u32, limit: u64) -> Vec<u32> {
+        let mut clusters = Vec::new();
+        let mut cluster = first;
+        while self.boot.is_valid_cluster(cluster) && (clusters.len() as u64) < limit.min(self.boot.cluster_count as u64) {
+            clusters.push(cluster);
+            cluster = self.fat.get(cluster as usize).copied().unwrap_or(0);
+        }
+        clusters
+    }
+
+    /// Clusters of a stream: contiguous under NoFatChain, else the FAT chain
+    fn stream_clusters(&self, first: u32, length: u64, no_fat_chain: bool) -> Vec<u32> {
+        let count = length.div_ceil(self.boot.cluster_size as u64);
+        if no_fat_chain {
+            (first as u64..first as u64 + count)
+                .take_while(|&c| self.boot.is_valid_cluster(c as u32))
+                .map(|c| c as u32)
+                .collect()
+        } else {
+            self.chain(first, count)
+        }
+    }
+
+    /// Read a metadata stream (bitmap, up-case table) through the FAT
+    fn read_stream(&self, disk: &mut DiskReader, first: u32, length: u64) -> Result<Vec<u8>, String> {
+        let runs = cluster_runs(&self.stream_clusters(first, length, false));
+        let (data, failed) = self.read_runs(disk, &runs, length);
+        if failed > 0 || (data.len() as u64) < length {
+            return Err(format!("Failed to read {} bytes at cluster {}", length, first));
+        }
+        Ok(data)
+    }
+
+    /// Read `size` bytes laid out by `runs`; unreadable clusters become zeros
+    pub fn read_runs(&self, disk: &mut DiskReader, runs: &[DataRun], size: u64) -> (Vec<u8>, usize) {
+        read_cluster_runs(disk, runs, size, self.boot.cluster_size, |cluster| self.boot.cluster_offset(cluster))
+    }
+
+    fn read_clusters(&self, disk: &mut DiskReader, clusters: &[u32]) -> (Vec<u8>, Vec<u64>) {
+        read_directory_clusters(disk, clusters, self.boot.cluster_size, |cluster| self.boot.cluster_offset(cluster))
+    }
+
+    /// Lay out a deleted file and count how much of it the bitmap has given away
+    pub fn file_layout(&self, set: &EntrySet) -> FileLayout {
+        let clusters = self.stream_clusters(set.first_cluster, set.data_length, set.no_fat_chain);
+        FileLayout {
+            runs: cluster_runs(&clusters),
+            clusters: clusters.len() as u64,
+            allocated: clusters.iter().filter(|&&c| self.is_allocated(c)).count() as u64,
+        }
+    }
+}
+
+/// Merge a cluster list into runs of consecutive clusters
+fn cluster_runs(clusters: &[u32]) -> Vec<DataRun> {
+    let mut runs: Vec<DataRun> = Vec::new();
+    for &cluster in clusters {
+        match runs.last_mut() {
+            Some(run) if run.cluster_offset + run.cluster_count as i64 == cluster as i64 => run.cluster_count += 1,
+            _ => runs.push(DataRun { cluster_offset: cluster as i64, cluster_count: 1 }),
+        }
+    }
+    runs
+}
+
+/// A deleted entry set found while walking the directory tree
+#[derive(Debug, Clone)]
+pub struct DeletedEntrySet {
+    pub set: EntrySet,
+    pub directory: String, // Volume-relative path of the directory holding it
+    pub entry_offset: u64, // Volume byte offset of its File entry
+}
+
+/// Walk every directory, live and deleted, and collect the deleted files.
+/// Everything inside a deleted directory counts as deleted; a deleted
+/// directory is only walked while the bitmap shows its first cluster free.
+pub fn scan_deleted(disk: &mut DiskReader, volume: &ExfatVolume, cancelled: &AtomicBool) -> Vec<DeletedEntrySet> {
+    let cluster_size = volume.boot.cluster_size as usize;
+    let mut found = Vec::new();
+    let mut visited: HashSet<u32> = HashSet::from([volume.boot.root_cluster]);
+
+    // (directory data, volume offset of each cluster, path, deleted, depth)
+    let mut pending = vec![(volume.read_clusters(disk, &volume.root_clusters()), String::new(), false, 0usize)];
+    while let Some(((data, offsets), path, deleted, depth)) = pending.pop() {
+        if cancelled.load(Ordering::Relaxed) {
+            break;
+        }
+
+        for set in parse_directory(&data, &volume.upcase) {
+            let entry_path = if path.is_empty() { set.name.clone() } else { format!("{}\\{}", path, set.name) };
+            let is_deleted = deleted || set.is_deleted;
+
+            if set.is_directory {
+                if depth + 1 >= MAX_PATH_DEPTH || !visited.insert(set.first_cluster) {
+                    continue;
+                }
+                if is_deleted && volume.is_allocated(set.first_cluster) {
+                    continue;
+                }
+                let clusters = volume.stream_clusters(set.first_cluster, set.data_length, set.no_fat_chain);
+                let contents = volume.read_clusters(disk, &clusters);
+                if !contents.1.is_empty() {
+                    pending.push((contents, entry_path, is_deleted, depth + 1));
+                }
+            } else if is_deleted {
+                let Some(&cluster_offset) = offsets.get(set.offset / cluster_size) else { continue };
+                found.push(DeletedEntrySet {
+                    entry_offset: cluster_offset + (set.offset % cluster_size) as u64,
+                    set: EntrySet { is_deleted, ..set },
+                    directory: path.clone(),
+                });
+            }
+        }
+    }
+
+    found.sort_by_key(|f| f.entry_offset);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry_set(name: &str, in_use: bool) -> Vec<u8> {
+        let units: Vec<u16> = name.encode_utf16().collect();
+        let name_entries = units.len().div_ceil(15);
+        let mut set = vec![0u8; (2 + name_entries) * DIRECTORY_ENTRY_SIZE];
+        set[0] = ENTRY_FILE | ENTRY_IN_USE;
+        set[1] = 1 + name_entries as u8;
+        set[4] = 0x20;
+        // 2024-05-20 09:15:20 + 1.37 s, UTC+05:30; modified 2023-12-31 23:59:58, UTC-08:00
+        set[8..12].copy_from_slice(&((44u32 << 25) | (5 << 21) | (20 << 16) | (9 << 11) | (15 << 5) | 10).to_le_bytes());
+        set[12..16].copy_from_slice(&((43u32 << 25) | (12 << 21) | (31 << 16) | (23 << 11) | (59 << 5) | 29).to_le_bytes());
+        set[20] = 137;
+        set[22] = 0x80 | 22;
+        set[23] = 0x80 | (-32i8 as u8 & 0x7F);
+
+        let stream = &mut set[32..64];
+        stream[0] = ENTRY_STREAM_EXTENSION | ENTRY_IN_USE;
+        stream[1] = 0x01 | FLAG_NO_FAT_CHAIN;
+        stream[3] = units.len() as u8;
+        stream[4..6].copy_from_slice(&UpcaseTable::ascii().name_hash(&units).to_le_bytes());
+        stream[8..16].copy_from_slice(&4000u64.to_le_bytes());
+        stream[20..24].copy_from_slice(&9u32.to_le_bytes());
+        stream[24..32].copy_from_slice(&5000u64.to_le_bytes());
+        for (i, chunk) in units.chunks(15).enumerate() {
+            let entry = &mut set[(2 + i) * DIRECTORY_ENTRY_SIZE..(3 + i) * DIRECTORY_ENTRY_SIZE];
+            entry[0] = ENTRY_FILE_NAME | ENTRY_IN_USE;
+            for (j, unit) in chunk.iter().enumerate() {
+                entry[2 + j * 2..4 + j * 2].copy_from_slice(&unit.to_le_bytes());
+            }
+        }
+        let checksum = entry_set_checksum(&set, false);
+        set[2..4].copy_from_slice(&checksum.to_le_bytes());
+        if !in_use {
+            for entry in set.chunks_exact_mut(DIRECTORY_ENTRY_SIZE) {
+                entry[0] &= !ENTRY_IN_USE;
+            }
+        }
+        set
+    }
+
+    #[test]
+    fn test_parse_deleted_entry_sets() {
+        let mut data = entry_set("Quarterly report (final).xlsx", false);
+        data.extend(entry_set("live.txt", true));
+        // A deleted set whose name entry a newer set has taken is not a file any more
+        let mut torn = entry_set("torn.txt", false);
+        torn[64] |= ENTRY_IN_USE;
+        data.extend(torn);
+        data.extend(vec![0u8; DIRECTORY_ENTRY_SIZE]);
+
+        let sets = parse_directory(&data, &UpcaseTable::ascii());
+        assert_eq!(sets.len(), 2);
+        let report = &sets[0];
+        assert_eq!((report.name.as_str(), report.is_deleted, report.checksum_valid), ("Quarterly report (final).xlsx", true, true));
+        assert_eq!((report.first_cluster, report.data_length, report.valid_data_length, report.no_fat_chain), (9, 5000, 4000, true));
+        assert_eq!(report.created.unwrap().iso8601(), "2024-05-20T09:15:21.370+05:30");
+        assert_eq!(report.modified.unwrap().iso8601(), "2023-12-31T23:59:58.000-08:00");
+        assert_eq!(report.modified.unwrap().best_utc().to_string(), "2024-01-01 07:59:58");
+        assert_eq!((sets[1].name.as_str(), sets[1].is_deleted, sets[1].offset), ("live.txt", false, 4 * DIRECTORY_ENTRY_SIZE));
+
+        // A changed name no longer matches the set checksum
+        data[66] = b'q';
+        assert!(!parse_directory(&data, &UpcaseTable::ascii())[0].checksum_valid);
+    }
+
+    #[test]
+    fn test_upcase_table_decompresses_identity_runs() {
+        // a-z map to A-Z, everything else to itself
+        let mut units: Vec<u16> = vec![0xFFFF, 0x61];
+        units.extend(0x41..=0x5A);
+        units.extend([0xFFFF, 0xFF00 - 0x7B]);
+        let data: Vec<u8> = units.iter().flat_map(|u| u.to_le_bytes()).collect();
+        let table = UpcaseTable::parse(&data);
+        assert_eq!((table.upcase('q' as u16), table.upcase('Q' as u16), table.upcase(0x00E9)), ('Q' as u16, 'Q' as u16, 0x00E9));
+        let name: Vec<u16> = "Report.xlsx".encode_utf16().collect();
+        assert_eq!(table.name_hash(&name), UpcaseTable::ascii().name_hash(&name));
+        assert_eq!(upcase_table_checksum(&[1, 2]), 0x8000_0002);
+    }
+}
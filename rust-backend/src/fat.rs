@@ -0,0 +1,679 @@
+//! FAT Module
+//! Parses FAT12/FAT16/FAT32 volumes (BIOS parameter block, allocation table,
+//! directories with VFAT long names) and finds deleted directory entries
+//!
+//! Deleting a file marks its directory entry with 0xE5 and frees its cluster
+//! chain, so only the first cluster and the size survive. Content is rebuilt
+//! on the assumption that FAT allocates forward: the clusters from the first
+//! one on, skipping those a live file owns now.
+
+use chrono::{NaiveDate, NaiveDateTime};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::directory_tree::MAX_PATH_DEPTH;
+use crate::disk_reader::DiskReader;
+use crate::ntfs_parser::DataRun;
+
+const DIRECTORY_ENTRY_SIZE: usize = 32;
+const DELETED_MARKER: u8 = 0xE5;
+
+const ATTRIBUTE_VOLUME_LABEL: u8 = 0x08;
+const ATTRIBUTE_DIRECTORY: u8 = 0x10;
+const ATTRIBUTE_LONG_NAME: u8 = 0x0F;
+
+/// Character offsets of the 13 UTF-16 characters in a long name entry
+const LONG_NAME_CHARACTERS: [usize; 13] = [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30];
+
+/// Clusters read from a deleted directory before giving up on it
+const MAX_DELETED_DIRECTORY_CLUSTERS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FatType {
+    Fat12,
+    Fat16,
+    Fat32,
+}
+
+impl FatType {
+    pub fn name(self) -> &'static str {
+        match self {
+            FatType::Fat12 => "FAT12",
+            FatType::Fat16 => "FAT16",
+            FatType::Fat32 => "FAT32",
+        }
+    }
+}
+
+/// Geometry from the BIOS parameter block
+#[derive(Debug, Clone)]
+pub struct FatBootSector {
+    pub fat_type: FatType,
+    pub bytes_per_sector: u32,
+    pub sectors_per_cluster: u32,
+    pub cluster_size: u32,
+    pub reserved_sectors: u64,
+    pub fat_count: u64,
+    pub fat_size: u64,       // Sectors per FAT copy
+    pub root_entries: u64,   // Fixed root directory size on FAT12/16
+    pub root_cluster: u32,   // First cluster of the root directory on FAT32
+    pub total_sectors: u64,
+    pub cluster_count: u32,  // Data clusters, numbered from 2
+    pub volume_serial: u32,
+}
+
+impl FatBootSector {
+    /// First sector of the data region (cluster 2)
+    pub fn first_data_sector(&self) -> u64 {
+        let root_sectors = (self.root_entries * DIRECTORY_ENTRY_SIZE as u64).div_ceil(self.bytes_per_sector as u64);
+        self.reserved_sectors + self.fat_count * self.fat_size + root_sectors
+    }
+
+    /// Byte offset of a data cluster on the volume
+    pub fn cluster_offset(&self, cluster: u32) -> u64 {
+        (self.first_data_sector() + (cluster as u64 - 2) * self.sectors_per_cluster as u64) * self.bytes_per_sector as u64
+    }
+
+    pub fn is_valid_cluster(&self, cluster: u32) -> bool {
+        cluster >= 2 && cluster - 2 < self.cluster_count
+    }
+}
+
+/// Parse a FAT boot sector. The FAT type follows from the cluster count, as
+/// the specification requires, not from the "FAT16   " label string.
+pub fn parse_fat_boot_sector(data: &[u8]) -> Option<FatBootSector> {
+    if data.len() < 512 || data[510] != 0x55 || data[511] != 0xAA {
+        return None;
+    }
+    let jump_ok = (data[0] == 0xEB && data[2] == 0x90) || data[0] == 0xE9;
+    let bytes_per_sector = u16::from_le_bytes([data[11], data[12]]) as u32;
+    let sectors_per_cluster = data[13] as u32;
+    let reserved_sectors = u16::from_le_bytes([data[14], data[15]]) as u64;
+    let fat_count = data[16] as u64;
+    let root_entries = u16::from_le_bytes([data[17], data[18]]) as u64;
+    let media = data[21];
+    let total_sectors = match u16::from_le_bytes([data[19], data[20]]) {
+        0 => u32::from_le_bytes(data[32..36].try_into().unwrap()) as u64,
+        n => n as u64,
+    };
+    let fat_size = match u16::from_le_bytes([data[22], data[23]]) {
+        0 => u32::from_le_bytes(data[36..40].try_into().unwrap()) as u64,
+        n => n as u64,
+    };
+
+    if !jump_ok
+        || !matches!(bytes_per_sector, 512 | 1024 | 2048 | 4096)
+        || !sectors_per_cluster.is_power_of_two()
+        || reserved_sectors == 0
+        || !(1..=2).contains(&fat_count)
+        || !(media == 0xF0 || media >= 0xF8)
+        || fat_size == 0
+    {
+        return None;
+    }
+
+    let mut boot = FatBootSector {
+        fat_type: FatType::Fat12,
+        bytes_per_sector,
+        sectors_per_cluster,
+        cluster_size: bytes_per_sector * sectors_per_cluster,
+        reserved_sectors,
+        fat_count,
+        fat_size,
+        root_entries,
+        root_cluster: 0,
+        total_sectors,
+        cluster_count: 0,
+        volume_serial: 0,
+    };
+    let data_sectors = total_sectors.checked_sub(boot.first_data_sector()).filter(|&s| s > 0)?;
+    boot.cluster_count = (data_sectors / sectors_per_cluster as u64).min(0x0FFF_FFF5) as u32;
+    boot.fat_type = match boot.cluster_count {
+        0..=4084 => FatType::Fat12,
+        4085..=65524 => FatType::Fat16,
+        _ => FatType::Fat32,
+    };
+
+    // The FAT must be large enough to describe every cluster
+    let entry_bits = match boot.fat_type {
+        FatType::Fat12 => 12,
+        FatType::Fat16 => 16,
+        FatType::Fat32 => 32,
+    };
+    if (boot.cluster_count as u64 + 2) * entry_bits > fat_size * bytes_per_sector as u64 * 8 {
+        return None;
+    }
+
+    let serial_offset = if boot.fat_type == FatType::Fat32 {
+        boot.root_cluster = u32::from_le_bytes(data[44..48].try_into().unwrap());
+        if root_entries != 0 || !boot.is_valid_cluster(boot.root_cluster) {
+            return None;
+        }
+        67
+    } else {
+        if root_entries == 0 {
+            return None;
+        }
+        39
+    };
+    boot.volume_serial = u32::from_le_bytes(data[serial_offset..serial_offset + 4].try_into().unwrap());
+    Some(boot)
+}
+
+/// The file allocation table: one next-cluster entry per cluster
+pub struct FatTable {
+    entries: Vec<u32>,
+    bad_cluster: u32,
+}
+
+/// How the content of a deleted file was laid out again
+#[derive(Debug, Clone)]
+pub struct Reassembly {
+    pub runs: Vec<DataRun>, // Cluster numbers, not volume clusters
+    pub skipped: u64,       // Allocated clusters stepped over on the way
+    pub missing: u64,       // Clusters the volume ran out of
+    pub overwritten: bool,  // The first cluster belongs to another file now
+}
+
+impl FatTable {
+    /// Decode a FAT copy, keeping the entries of clusters 0 to cluster_count + 1
+    pub fn parse(data: &[u8], boot: &FatBootSector) -> FatTable {
+        let count = boot.cluster_count as usize + 2;
+        let entries = match boot.fat_type {
+            FatType::Fat12 => (0..count)
+                .map(|n| {
+                    let at = n + n / 2;
+                    let pair = u16::from_le_bytes([data.get(at).copied().unwrap_or(0), data.get(at + 1).copied().unwrap_or(0)]);
+                    (if n % 2 == 0 { pair & 0x0FFF } else { pair >> 4 }) as u32
+                })
+                .collect(),
+            FatType::Fat16 => data.chunks_exact(2).take(count).map(|e| u16::from_le_bytes([e[0], e[1]]) as u32).collect(),
+            FatType::Fat32 => data.chunks_exact(4).take(count).map(|e| u32::from_le_bytes([e[0], e[1], e[2], e[3]]) & 0x0FFF_FFFF).collect(),
+        };
+        let bad_cluster = match boot.fat_type {
+            FatType::Fat12 => 0xFF7,
+            FatType::Fat16 => 0xFFF7,
+            FatType::Fat32 => 0x0FFF_FFF7,
+        };
+        FatTable { entries, bad_cluster }
+    }
+
+    /// Read the first readable FAT copy
+    pub fn read(disk: &mut DiskReader, boot: &FatBootSector) -> Result<FatTable, String> {
+        let fat_bytes = (boot.fat_size * boot.bytes_per_sector as u64) as usize;
+        let mut last_error = String::new();
+        for copy in 0..boot.fat_count {
+            let offset = (boot.reserved_sectors + copy * boot.fat_size) * boot.bytes_per_sector as u64;
+            match disk.read_at(offset, fat_bytes) {
+                Ok(data) => return Ok(FatTable::parse(&data, boot)),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(format!("Failed to read the FAT: {}", last_error))
+    }
+
+    pub fn is_free(&self, cluster: u32) -> bool {
+        self.entries.get(cluster as usize) == Some(&0)
+    }
+
+    /// Clusters still free, as (first cluster, count) extents
+    pub fn free_extents(&self) -> Vec<(u32, u32)> {
+        let mut extents: Vec<(u32, u32)> = Vec::new();
+        for cluster in 2..self.entries.len() as u32 {
+            if !self.is_free(cluster) {
+                continue;
+            }
+            match extents.last_mut() {
+                Some((start, count)) if *start + *count == cluster => *count += 1,
+                _ => extents.push((cluster, 1)),
+            }
+        }
+        extents
+    }
+
+    /// The cluster chain of a live file or directory; stops at a free or bad
+    /// entry and at a loop
+    pub fn chain(&self, first: u32) -> Vec<u32> {
+        let mut clusters = Vec::new();
+        let mut cluster = first;
+        while (2..self.entries.len() as u32).contains(&cluster) && clusters.len() < self.entries.len() {
+            clusters.push(cluster);
+            cluster = self.entries[cluster as usize];
+            if cluster == 0 || cluster >= self.bad_cluster {
+                break;
+            }
+        }
+        clusters
+    }
+
+    /// Lay out `needed` clusters of a deleted file from its first cluster on,
+    /// stepping over clusters that are allocated now. When the first cluster
+    /// is allocated itself the file was overwritten; its old clusters are
+    /// returned as they are.
+    pub fn reassemble(&self, first: u32, needed: u64) -> Reassembly {
+        let end = self.entries.len() as u64;
+        let mut reassembly = Reassembly { runs: Vec::new(), skipped: 0, missing: 0, overwritten: false };
+        if needed == 0 || !(2..end).contains(&(first as u64)) {
+            reassembly.missing = needed;
+            return reassembly;
+        }
+
+        reassembly.overwritten = !self.is_free(first);
+        let mut taken = 0u64;
+        let mut cluster = first as u64;
+        while taken < needed && cluster < end {
+            if !reassembly.overwritten && !self.is_free(cluster as u32) {
+                reassembly.skipped += 1;
+                cluster += 1;
+                continue;
+            }
+            match reassembly.runs.last_mut() {
+                Some(run) if run.cluster_offset as u64 + run.cluster_count == cluster => run.cluster_count += 1,
+                _ => reassembly.runs.push(DataRun { cluster_offset: cluster as i64, cluster_count: 1 }),
+            }
+            taken += 1;
+            cluster += 1;
+        }
+        reassembly.missing = needed - taken;
+        reassembly
+    }
+}
+
+/// A FAT volume ready to be walked
+pub struct FatVolume {
+    pub boot: FatBootSector,
+    pub table: FatTable,
+}
+
+impl FatVolume {
+    pub fn open(disk: &mut DiskReader, boot: FatBootSector) -> Result<FatVolume, String> {
+        let table = FatTable::read(disk, &boot)?;
+        Ok(FatVolume { boot, table })
+    }
+
+    /// Read `size` bytes laid out by `runs`; unreadable clusters become zeros.
+    /// Returns the data and the number of runs that failed.
+    pub fn read_runs(&self, disk: &mut DiskReader, runs: &[DataRun], size: u64) -> (Vec<u8>, usize) {
+        read_cluster_runs(disk, runs, size, self.boot.cluster_size, |cluster| self.boot.cluster_offset(cluster))
+    }
+
+    /// Read a directory's clusters, with the volume offset of each cluster
+    fn read_clusters(&self, disk: &mut DiskReader, clusters: &[u32]) -> (Vec<u8>, Vec<u64>) {
+        read_directory_clusters(disk, clusters, self.boot.cluster_size, |cluster| self.boot.cluster_offset(cluster))
+    }
+
+    /// Clusters of a deleted directory: its first cluster must still open
+    /// with the "." entry pointing at itself; later clusters are read while
+    /// the previous one was full and the next one is still free
+    fn read_deleted_directory(&self, disk: &mut DiskReader, first: u32) -> (Vec<u8>, Vec<u64>) {
+        if !self.boot.is_valid_cluster(first) || !self.table.is_free(first) {
+            return (Vec::new(), Vec::new());
+        }
+        let (mut data, mut offsets) = self.read_clusters(disk, &[first]);
+        let intact = data.len() >= DIRECTORY_ENTRY_SIZE
+            && &data[0..11] == b".          "
+            && entry_cluster(&data[0..DIRECTORY_ENTRY_SIZE], self.boot.fat_type) == first;
+        if !intact {
+            return (Vec::new(), Vec::new());
+        }
+
+        let mut cluster = first;
+        while offsets.len() < MAX_DELETED_DIRECTORY_CLUSTERS {
+            let last = &data[data.len() - self.boot.cluster_size as usize..];
+            let ended = last.chunks_exact(DIRECTORY_ENTRY_SIZE).any(|entry| entry[0] == 0);
+            cluster += 1;
+            if ended || !self.boot.is_valid_cluster(cluster) || !self.table.is_free(cluster) {
+                break;
+            }
+            let (more, more_offsets) = self.read_clusters(disk, &[cluster]);
+            if more_offsets.is_empty() {
+                break;
+            }
+            data.extend_from_slice(&more);
+            offsets.extend(more_offsets);
+        }
+        (data, offsets)
+    }
+}
+
+/// Read `size` bytes laid out by runs of cluster numbers (FAT and exFAT
+/// alike); unreadable clusters become zeros. Returns the data and the number
+/// of runs that failed.
+pub fn read_cluster_runs(
+    disk: &mut DiskReader,
+    runs: &[DataRun],
+    size: u64,
+    cluster_size: u32,
+    cluster_offset: impl Fn(u32) -> u64,
+) -> (Vec<u8>, usize) {
+    let mut data = Vec::new();
+    let mut failed = 0;
+    for run in runs {
+        let remaining = size.saturating_sub(data.len() as u64);
+        if remaining == 0 {
+            break;
+        }
+        let length = (run.cluster_count * cluster_size as u64).min(remaining) as usize;
+        match disk.read_at(cluster_offset(run.cluster_offset as u32), length) {
+            Ok(bytes) if bytes.len() == length => data.extend_from_slice(&bytes),
+            _ => {
+                eprintln!("Warning: Failed to read cluster {}", run.cluster_offset);
+                failed += 1;
+                data.resize(data.len() + length, 0);
+            }
+        }
+    }
+    (data, failed)
+}
+
+/// Read a directory's clusters, with the volume offset of each cluster; stops
+/// at the first unreadable one
+pub fn read_directory_clusters(
+    disk: &mut DiskReader,
+    clusters: &[u32],
+    cluster_size: u32,
+    cluster_offset: impl Fn(u32) -> u64,
+) -> (Vec<u8>, Vec<u64>) {
+    let mut data = Vec::new();
+    let mut offsets = Vec::new();
+    for &cluster in clusters {
+        let offset = cluster_offset(cluster);
+        match disk.read_at(offset, cluster_size as usize) {
+            Ok(bytes) => {
+                data.extend_from_slice(&bytes);
+                offsets.push(offset);
+            }
+            Err(e) => {
+                eprintln!("Warning: Skipping unreadable directory cluster {}: {}", cluster, e);
+                break;
+            }
+        }
+    }
+    (data, offsets)
+}
+
+/// One 8.3 directory entry with the long name in front of it
+#[derive(Debug, Clone)]
+pub struct DirectoryEntry {
+    pub name: String,       // Long name when one survives, else the 8.3 name
+    pub is_deleted: bool,
+    pub is_directory: bool,
+    pub first_cluster: u32,
+    pub size: u32,
+    pub created: Option<NaiveDateTime>,  // 10 ms resolution
+    pub modified: Option<NaiveDateTime>, // 2 s resolution
+    pub offset: usize,      // Byte offset of the 8.3 entry within the directory
+}
+
+fn entry_cluster(entry: &[u8], fat_type: FatType) -> u32 {
+    let low = u16::from_le_bytes([entry[26], entry[27]]) as u32;
+    // FAT12/16 keep extended attribute handles in the high word
+    let high = if fat_type == FatType::Fat32 { u16::from_le_bytes([entry[20], entry[21]]) as u32 } else { 0 };
+    (high << 16) | low
+}
+
+/// Checksum of an 8.3 name, stored in each of its long name entries
+fn short_name_checksum(short_name: &[u8]) -> u8 {
+    short_name.iter().fold(0u8, |sum, &byte| sum.rotate_right(1).wrapping_add(byte))
+}
+
+/// Long name of an 8.3 entry from the long name entries right in front of it
+/// (last part first). Deletion overwrites the first byte of every entry, the
+/// ordinals included, so a deleted entry's parts are matched by checksum
+/// alone; the checksum then gives back the lost first byte of the 8.3 name.
+fn long_name(parts: &[&[u8]], short_name: &[u8; 11], is_deleted: bool) -> Option<(String, u8)> {
+    let checksum = parts.last()?[13];
+    let first_byte = if is_deleted {
+        // The checksum starts from the first byte, so exactly one value fits
+        (0x21..=0xFFu8).find(|&b| b != DELETED_MARKER && {
+            let mut name = *short_name;
+            name[0] = b;
+            short_name_checksum(&name) == checksum
+        })?
+    } else if short_name_checksum(short_name) == checksum {
+        short_name[0]
+    } else {
+        return None;
+    };
+
+    let mut units = Vec::new();
+    'parts: for part in parts.iter().rev().take_while(|part| part[13] == checksum) {
+        for &at in &LONG_NAME_CHARACTERS {
+            match u16::from_le_bytes([part[at], part[at + 1]]) {
+                0x0000 => break 'parts,
+                unit => units.push(unit),
+            }
+        }
+    }
+    let name = String::from_utf16(&units).ok().filter(|name| !name.is_empty())?;
+    Some((name, first_byte))
+}
+
+/// "NAME.EXT" from the padded 8.3 form, lowercased where Windows NT flags it
+fn format_short_name(short_name: &[u8; 11], case_flags: u8) -> String {
+    let decode = |bytes: &[u8], lower: bool| -> String {
+        let text: String = bytes.iter().map(|&b| b as char).collect::<String>().trim_end().to_string();
+        if lower { text.to_lowercase() } else { text }
+    };
+    let base = decode(&short_name[0..8], case_flags & 0x08 != 0);
+    let extension = decode(&short_name[8..11], case_flags & 0x10 != 0);
+    if extension.is_empty() { base } else { format!("{}.{}", base, extension) }
+}
+
+fn fat_date(date: u16) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(1980 + (date >> 9) as i32, ((date >> 5) & 0x0F) as u32, (date & 0x1F) as u32)
+}
+
+/// Date and local time; `hundredths` adds 0-199 hundredths of a second
+fn fat_datetime(date: u16, time: u16, hundredths: u8) -> Option<NaiveDateTime> {
+    let seconds = (time & 0x1F) as u32 * 2 + hundredths as u32 / 100;
+    fat_date(date)?.and_hms_milli_opt((time >> 11) as u32, ((time >> 5) & 0x3F) as u32, seconds, (hundredths as u32 % 100) * 10)
+}
+
+/// Parse a directory's entries, deleted ones included. The flag is true when
+/// the end-of-directory marker was reached.
+pub fn parse_directory(data: &[u8], fat_type: FatType) -> (Vec<DirectoryEntry>, bool) {
+    let mut entries = Vec::new();
+    let mut parts: Vec<&[u8]> = Vec::new();
+    for (index, raw) in data.chunks_exact(DIRECTORY_ENTRY_SIZE).enumerate() {
+        if raw[0] == 0x00 {
+            return (entries, true);
+        }
+        let attributes = raw[11];
+        if attributes & 0x3F == ATTRIBUTE_LONG_NAME {
+            parts.push(raw);
+            continue;
+        }
+        let long_parts = std::mem::take(&mut parts);
+        if attributes & ATTRIBUTE_VOLUME_LABEL != 0 || raw[0] == b'.' {
+            continue;
+        }
+
+        let is_deleted = raw[0] == DELETED_MARKER;
+        let mut short_name: [u8; 11] = raw[0..11].try_into().unwrap();
+        if short_name[0] == 0x05 {
+            short_name[0] = DELETED_MARKER; // A name really starting with 0xE5
+        }
+        let long = long_name(&long_parts, &short_name, is_deleted);
+        if is_deleted {
+            // Without a long name the first character is lost for good
+            short_name[0] = long.as_ref().map_or(b'_', |&(_, first)| first);
+        }
+        let short_name = format_short_name(&short_name, raw[12]);
+
+        let word = |at: usize| u16::from_le_bytes([raw[at], raw[at + 1]]);
+        entries.push(DirectoryEntry {
+            name: long.map_or(short_name, |(name, _)| name),
+            is_deleted,
+            is_directory: attributes & ATTRIBUTE_DIRECTORY != 0,
+            first_cluster: entry_cluster(raw, fat_type),
+            size: u32::from_le_bytes(raw[28..32].try_into().unwrap()),
+            created: fat_datetime(word(16), word(14), raw[13]),
+            modified: fat_datetime(word(24), word(22), 0),
+            offset: index * DIRECTORY_ENTRY_SIZE,
+        });
+    }
+    (entries, false)
+}
+
+/// A deleted file found while walking the directory tree
+#[derive(Debug, Clone)]
+pub struct DeletedEntry {
+    pub entry: DirectoryEntry,
+    pub directory: String,  // Volume-relative path of the directory holding it
+    pub entry_offset: u64,  // Volume byte offset of its 8.3 entry
+}
+
+/// Walk every directory, live and deleted, and collect the deleted files.
+/// Everything inside a deleted directory counts as deleted.
+pub fn scan_deleted(disk: &mut DiskReader, volume: &FatVolume, cancelled: &AtomicBool) -> Vec<DeletedEntry> {
+    let boot = &volume.boot;
+    let mut found = Vec::new();
+    let mut visited: HashSet<u32> = HashSet::new();
+
+    let root = if boot.fat_type == FatType::Fat32 {
+        visited.insert(boot.root_cluster);
+        volume.read_clusters(disk, &volume.table.chain(boot.root_cluster))
+    } else {
+        let offset = (boot.reserved_sectors + boot.fat_count * boot.fat_size) * boot.bytes_per_sector as u64;
+        let data = disk.read_at(offset, boot.root_entries as usize * DIRECTORY_ENTRY_SIZE).unwrap_or_default();
+        (data, vec![offset])
+    };
+
+    // (directory data, volume offset of each of its pieces, path, deleted, depth)
+    let mut pending = vec![(root, String::new(), false, 0usize)];
+    while let Some(((data, offsets), path, deleted, depth)) = pending.pop() {
+        if cancelled.load(Ordering::Relaxed) {
+            break;
+        }
+        // The FAT12/16 root is one piece; everything else comes in clusters
+        let piece = if offsets.len() == 1 { data.len().max(1) } else { boot.cluster_size as usize };
+
+        for entry in parse_directory(&data, boot.fat_type).0 {
+            let entry_path = if path.is_empty() { entry.name.clone() } else { format!("{}\\{}", path, entry.name) };
+            let is_deleted = deleted || entry.is_deleted;
+
+            if entry.is_directory {
+                if depth + 1 >= MAX_PATH_DEPTH || !visited.insert(entry.first_cluster) {
+                    continue;
+                }
+                let contents = if is_deleted {
+                    volume.read_deleted_directory(disk, entry.first_cluster)
+                } else {
+                    volume.read_clusters(disk, &volume.table.chain(entry.first_cluster))
+                };
+                if !contents.1.is_empty() {
+                    pending.push((contents, entry_path, is_deleted, depth + 1));
+                }
+            } else if is_deleted {
+                let entry_offset = offsets[entry.offset / piece] + (entry.offset % piece) as u64;
+                found.push(DeletedEntry {
+                    entry: DirectoryEntry { is_deleted, ..entry },
+                    directory: path.clone(),
+                    entry_offset,
+                });
+            }
+        }
+    }
+
+    found.sort_by_key(|f| f.entry_offset);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn short_entry(name: &[u8; 11], attributes: u8, cluster: u32, size: u32) -> Vec<u8> {
+        let mut entry = vec![0u8; DIRECTORY_ENTRY_SIZE];
+        entry[0..11].copy_from_slice(name);
+        entry[11] = attributes;
+        entry[13] = 150; // 1.5 s past the two-second mark
+        entry[14..16].copy_from_slice(&((10 << 11) | (30 << 5) | 4u16).to_le_bytes());
+        entry[16..18].copy_from_slice(&((44 << 9) | (3 << 5) | 15u16).to_le_bytes());
+        entry[20..22].copy_from_slice(&((cluster >> 16) as u16).to_le_bytes());
+        entry[26..28].copy_from_slice(&(cluster as u16).to_le_bytes());
+        entry[28..32].copy_from_slice(&size.to_le_bytes());
+        entry
+    }
+
+    fn long_entries(name: &str, short_name: &[u8; 11]) -> Vec<u8> {
+        let mut units: Vec<u16> = name.encode_utf16().collect();
+        units.push(0);
+        units.resize(units.len().div_ceil(13) * 13, 0xFFFF);
+        let parts: Vec<&[u16]> = units.chunks(13).collect();
+        let mut entries = Vec::new();
+        for (i, part) in parts.iter().enumerate().rev() {
+            let mut entry = vec![0u8; DIRECTORY_ENTRY_SIZE];
+            entry[0] = (i + 1) as u8 | if i + 1 == parts.len() { 0x40 } else { 0 };
+            entry[11] = ATTRIBUTE_LONG_NAME;
+            entry[13] = short_name_checksum(short_name);
+            for (&at, unit) in LONG_NAME_CHARACTERS.iter().zip(part.iter()) {
+                entry[at..at + 2].copy_from_slice(&unit.to_le_bytes());
+            }
+            entries.extend(entry);
+        }
+        entries
+    }
+
+    #[test]
+    fn test_parse_directory_restores_deleted_long_names() {
+        let mut data = Vec::new();
+        data.extend(long_entries("Holiday photo.jpeg", b"HOLIDA~1JPE"));
+        data.extend(short_entry(b"HOLIDA~1JPE", 0x20, 0x0001_0005, 5000));
+        data.extend(short_entry(b"README  TXT", 0x20, 9, 12));
+        data.extend(short_entry(b"NOTES   TXT", 0x20, 12, 100));
+        // Deleting overwrites the first byte of every entry of the set
+        for entry in [0, 1, 2, 3] {
+            data[entry * DIRECTORY_ENTRY_SIZE] = DELETED_MARKER;
+        }
+        data.extend(vec![0u8; DIRECTORY_ENTRY_SIZE]);
+        data.extend(short_entry(b"AFTEREND   ", 0x20, 20, 1));
+
+        let (entries, ended) = parse_directory(&data, FatType::Fat32);
+        assert!(ended);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].name, "Holiday photo.jpeg");
+        assert!(entries[0].is_deleted);
+        assert_eq!((entries[0].first_cluster, entries[0].size, entries[0].offset), (0x0001_0005, 5000, 64));
+        assert_eq!(entries[0].created.unwrap().to_string(), "2024-03-15 10:30:09.500");
+        assert_eq!(entries[1].name, "_EADME.TXT"); // No long name to restore the first byte from
+        assert!(!entries[2].is_deleted);
+        assert_eq!(parse_directory(&data, FatType::Fat16).0[0].first_cluster, 5);
+    }
+
+    #[test]
+    fn test_reassemble_steps_over_live_clusters() {
+        let boot = FatBootSector {
+            fat_type: FatType::Fat16,
+            bytes_per_sector: 512,
+            sectors_per_cluster: 4,
+            cluster_size: 2048,
+            reserved_sectors: 1,
+            fat_count: 2,
+            fat_size: 1,
+            root_entries: 512,
+            root_cluster: 0,
+            total_sectors: 100,
+            cluster_count: 14,
+            volume_serial: 0,
+        };
+        // Clusters 4-5 and 9 belong to a live file; the rest are free
+        let mut fat = vec![0u8; 32];
+        for (cluster, next) in [(0usize, 0xFFF8u16), (1, 0xFFFF), (4, 5), (5, 9), (9, 0xFFFF)] {
+            fat[cluster * 2..cluster * 2 + 2].copy_from_slice(&next.to_le_bytes());
+        }
+        let table = FatTable::parse(&fat, &boot);
+        assert_eq!(table.chain(4), vec![4, 5, 9]);
+        assert_eq!(table.free_extents(), vec![(2, 2), (6, 3), (10, 6)]);
+
+        let runs = |r: &Reassembly| r.runs.iter().map(|run| (run.cluster_offset, run.cluster_count)).collect::<Vec<_>>();
+        let file = table.reassemble(3, 5);
+        assert_eq!(runs(&file), vec![(3, 1), (6, 3), (10, 1)]);
+        assert_eq!((file.skipped, file.missing, file.overwritten), (3, 0, false));
+        let tail = table.reassemble(12, 6);
+        assert_eq!((runs(&tail), tail.missing), (vec![(12, 4)], 2));
+        assert!(table.reassemble(5, 2).overwritten);
+        assert_eq!(boot.cluster_offset(2), (1 + 2 + 32) * 512);
+    }
+}
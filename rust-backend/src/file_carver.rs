@@ -4226,10 +4226,11 @@ pub fn build_signature_lookup() -> HashMap<u16, Vec<FileSignature>> {
     lookup
 }
 
-/// Carve files from raw sector data
+/// Carve files from raw sector data starting at sector `sector_offset`
 pub fn carve_sector(
     data: &[u8],
     sector_offset: u64,
+    sector_size: u64,
     signatures: &HashMap<u16, Vec<FileSignature>>,
 ) -> Vec<CarvedFile> {
     let mut carved = Vec::new();
@@ -4250,7 +4251,7 @@ pub fn carve_sector(
             // Valid ftyp box size is typically 8-32 bytes
             if box_size >= 8 && box_size <= 64 {
                 let file_start = i - 4;
-                let global_offset = sector_offset * 512 + file_start as u64;
+                let global_offset = sector_offset * sector_size + file_start as u64;
                 
                 if found_positions.contains(&global_offset) {
                     continue;
@@ -4294,7 +4295,7 @@ pub fn carve_sector(
                     // Check if full header matches
                     if data[i..i + sig.header.len()] == *sig.header {
                         // Skip if we already found something at this position
-                        let global_offset = sector_offset * 512 + i as u64;
+                        let global_offset = sector_offset * sector_size + i as u64;
                         if found_positions.contains(&global_offset) {
                             continue;
                         }
@@ -4632,7 +4633,7 @@ mod tests {
     fn test_jpeg_detection() {
         let jpeg_header = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, 0x4A, 0x46, 0x49, 0x46];
         let lookup = build_signature_lookup();
-        let carved = carve_sector(&jpeg_header, 0, &lookup);
+        let carved = carve_sector(&jpeg_header, 0, 512, &lookup);
         assert!(!carved.is_empty());
         assert_eq!(carved[0].extension, "jpg");
     }
@@ -4641,7 +4642,7 @@ mod tests {
     fn test_png_detection() {
         let png_header = vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
         let lookup = build_signature_lookup();
-        let carved = carve_sector(&png_header, 0, &lookup);
+        let carved = carve_sector(&png_header, 0, 512, &lookup);
         assert!(!carved.is_empty());
         assert_eq!(carved[0].extension, "png");
     }
@@ -4650,7 +4651,7 @@ mod tests {
     fn test_pdf_detection() {
         let pdf_header = b"%PDF-1.4".to_vec();
         let lookup = build_signature_lookup();
-        let carved = carve_sector(&pdf_header, 0, &lookup);
+        let carved = carve_sector(&pdf_header, 0, 512, &lookup);
         assert!(!carved.is_empty());
         assert_eq!(carved[0].extension, "pdf");
     }
@@ -6,6 +6,9 @@ use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
 use serde::{Deserialize, Serialize};
 
+#[cfg(windows)]
+use crate::ntfs_parser::parse_boot_sector;
+
 #[cfg(windows)]
 use std::os::windows::io::FromRawHandle;
 
@@ -226,43 +229,24 @@ impl FileSystemDiskReader {
         handle.seek(SeekFrom::Start(0))
             .map_err(|e| format!("Failed to seek to boot sector: {}", e))?;
         
-        // Read boot sector (first 512 bytes)
-        let mut boot_sector = vec![0u8; 512];
+        // Read the boot sector; 4096 bytes keeps the read sector-aligned on 4K-native volumes
+        let mut boot_sector = vec![0u8; 4096];
         handle.read_exact(&mut boot_sector)
             .map_err(|e| format!("Failed to read boot sector: {}", e))?;
         
-        // Check NTFS signature
-        if &boot_sector[3..7] != b"NTFS" {
-            return Err("Not an NTFS volume".to_string());
-        }
-        
-        // Get bytes per sector and sectors per cluster
-        let bytes_per_sector = u16::from_le_bytes([boot_sector[11], boot_sector[12]]) as u64;
-        let sectors_per_cluster = boot_sector[13] as u64;
-        let cluster_size = bytes_per_sector * sectors_per_cluster;
+        let boot = parse_boot_sector(&boot_sector)
+            .ok_or("Not an NTFS volume, or its boot sector is corrupted")?;
+        let cluster_size = boot.cluster_size as u64;
         
-        // Get actual MFT record size from boot sector offset 0x40
-        let mft_size_raw = boot_sector[0x40] as i8;
-        let actual_record_size = if mft_size_raw > 0 {
-            (mft_size_raw as u64) * cluster_size
-        } else {
-            1u64 << ((-mft_size_raw) as u64)
-        };
-        self.mft_record_size = actual_record_size;
+        // Actual MFT record size from boot sector offset 0x40
+        self.mft_record_size = boot.mft_record_size as u64;
         self.cluster_size = cluster_size;
         eprintln!("[MFT] Boot sector: bytes_per_sector={}, sectors_per_cluster={}, cluster_size={}", 
-            bytes_per_sector, sectors_per_cluster, cluster_size);
-        eprintln!("[MFT] MFT record size from boot sector: {} bytes (raw value: {})", 
-            actual_record_size, mft_size_raw);
-        
-        // Get MFT cluster number (offset 0x30, 8 bytes)
-        let mft_cluster = u64::from_le_bytes([
-            boot_sector[0x30], boot_sector[0x31], boot_sector[0x32], boot_sector[0x33],
-            boot_sector[0x34], boot_sector[0x35], boot_sector[0x36], boot_sector[0x37],
-        ]);
+            boot.bytes_per_sector, boot.sectors_per_cluster, cluster_size);
+        eprintln!("[MFT] MFT record size from boot sector: {} bytes", boot.mft_record_size);
         
         // Calculate MFT byte offset
-        let mft_offset = mft_cluster * cluster_size;
+        let mft_offset = boot.mft_cluster * cluster_size;
         
         Ok(mft_offset)
     }
@@ -297,7 +281,13 @@ impl FileSystemDiskReader {
             }
         }
     }
-    
+
+    #[cfg(not(windows))]
+    fn open_mft_file(&mut self) -> Result<(), String> {
+        self.mft_file_open_attempted = true;
+        Err("Only supported on Windows".to_string())
+    }
+
     /// Read a single MFT record using FSCTL_GET_NTFS_FILE_RECORD
     /// This is the most reliable way - Windows handles fragmentation + BitLocker decryption
     #[cfg(windows)]
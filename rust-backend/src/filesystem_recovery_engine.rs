@@ -4,7 +4,8 @@
 
 use crate::bitlocker::{get_bitlocker_status, is_admin, BitLockerStatus};
 use crate::filesystem_disk_reader::{FileSystemDiskReader, UsnDeletedFile};
-use crate::ntfs_parser::{parse_mft_record, MftEntry};
+use crate::ntfs_parser::{parse_attribute_list, parse_mft_record, resolve_attribute_list, sequence_matches, MftEntry};
+use crate::recovery_engine::REUSED_PARENT_PENALTY;
 
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
@@ -45,6 +46,15 @@ pub struct RecoverableFileFS {
     pub data_runs: Option<String>,
 }
 
+/// A directory record seen during the MFT scan, for path resolution
+struct DirectoryInfo {
+    name: String,
+    sequence_number: u16,
+    in_use: bool,
+    parent_record: u64,
+    parent_sequence: u16,
+}
+
 /// Recovery result for a single file
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FileRecoveryResultFS {
@@ -186,8 +196,8 @@ impl FileSystemRecoveryEngine {
         
         // Collect all entries first
         let mut parsed_entries: Vec<MftEntry> = Vec::new();
-        // Build directory map: record_number -> (parent_record, name)
-        let mut dir_map: std::collections::HashMap<u64, (u64, String)> = std::collections::HashMap::new();
+        // Build directory map: record_number -> name, sequence and parent reference
+        let mut dir_map: std::collections::HashMap<u64, DirectoryInfo> = std::collections::HashMap::new();
         
         while record_num < limit as u64 && consecutive_failures < max_consecutive_failures {
             if self.cancelled.load(Ordering::Relaxed) {
@@ -206,7 +216,11 @@ impl FileSystemRecoveryEngine {
                     }
                     
                     // Parse the decrypted MFT record
-                    if let Some(entry) = parse_mft_record(&buffer, record_num) {
+                    if let Some(mut entry) = parse_mft_record(&buffer, record_num) {
+                        if !entry.attribute_list.is_empty() || !entry.attribute_list_runs.is_empty() {
+                            resolve_extension_records(reader, &mut entry);
+                        }
+                        
                         // Log deleted files for debugging
                         if entry.is_deleted && !entry.is_directory {
                             deleted_count_scan += 1;
@@ -220,7 +234,13 @@ impl FileSystemRecoveryEngine {
                         
                         // Add ALL directories to map (even deleted ones) for path resolution
                         if entry.is_directory {
-                            dir_map.insert(entry.record_number, (entry.parent_record, entry.file_name.clone()));
+                            dir_map.insert(entry.record_number, DirectoryInfo {
+                                name: entry.file_name.clone(),
+                                sequence_number: entry.sequence_number,
+                                in_use: entry.is_in_use,
+                                parent_record: entry.parent_record,
+                                parent_sequence: entry.parent_sequence,
+                            });
                         }
                         parsed_entries.push(entry);
                     }
@@ -320,9 +340,10 @@ impl FileSystemRecoveryEngine {
                     }
                     
                     // Build path from parent record using dir_map
-                    let mut full_path = build_full_path(
+                    let (mut full_path, _) = build_full_path(
                         &self.drive_letter, 
                         usn_file.parent_mft_record, 
+                        0,
                         &usn_file.file_name, 
                         &dir_map
                     );
@@ -334,6 +355,7 @@ impl FileSystemRecoveryEngine {
                             &mut reader,
                             &self.drive_letter,
                             usn_file.parent_mft_record,
+                            0,
                             &usn_file.file_name,
                         );
                         if resolved != full_path {
@@ -880,22 +902,52 @@ fn get_path_priority(path: &str) -> u32 {
     99
 }
 
+/// Pull the rest of a file's $DATA run list out of its extension records
+fn resolve_extension_records(
+    reader: &mut crate::filesystem_disk_reader::FileSystemDiskReader,
+    entry: &mut MftEntry,
+) {
+    if entry.attribute_list.is_empty() {
+        let cluster_size = reader.get_cluster_size();
+        let mut content = Vec::new();
+        for run in entry.attribute_list_runs.iter().filter(|r| r.cluster_offset > 0) {
+            match reader.read_clusters(run.cluster_offset as u64, run.cluster_count, cluster_size) {
+                Ok(data) => content.extend_from_slice(&data),
+                Err(_) => return,
+            }
+        }
+        content.truncate(entry.attribute_list_size as usize);
+        entry.attribute_list = parse_attribute_list(&content);
+    }
+    
+    if !resolve_attribute_list(entry, |record_number| reader.read_mft_record(record_number).ok()) {
+        eprintln!("DEBUG [FS]: Could not resolve extension records of record {}", entry.record_number);
+    }
+}
+
 /// Resolve a file path by directly reading parent MFT records
 /// Used as fallback when dir_map doesn't have the parent directory
 fn resolve_path_from_mft(
     reader: &mut crate::filesystem_disk_reader::FileSystemDiskReader,
     drive_letter: &str,
     parent_record: u64,
+    parent_sequence: u16,
     file_name: &str,
 ) -> String {
     let mut path_parts: Vec<String> = vec![file_name.to_string()];
     let mut current = parent_record;
+    let mut expected_sequence = parent_sequence;
+    let mut reused = false;
     let mut depth = 0;
     
     while current != 5 && depth < 50 {
         match reader.read_mft_record(current) {
             Ok(buffer) => {
                 if let Some(entry) = parse_mft_record(&buffer, current) {
+                    if !sequence_matches(expected_sequence, entry.sequence_number, entry.is_in_use) {
+                        reused = true; // Record now holds a different directory
+                        break;
+                    }
                     if !entry.file_name.starts_with('$') && !entry.file_name.is_empty() && entry.file_name != "." {
                         path_parts.push(entry.file_name.clone());
                     }
@@ -903,6 +955,7 @@ fn resolve_path_from_mft(
                         break; // Self-referencing, stop
                     }
                     current = entry.parent_record;
+                    expected_sequence = entry.parent_sequence;
                     depth += 1;
                 } else {
                     break;
@@ -913,28 +966,48 @@ fn resolve_path_from_mft(
     }
     
     path_parts.reverse();
-    format!("{}:\\{}", drive_letter, path_parts.join("\\"))
+    join_path(drive_letter, &path_parts, reused)
+}
+
+/// Join resolved path components; a chain broken by a reused directory
+/// record is placed under `$OrphanFiles` since the real location is unknown
+fn join_path(drive_letter: &str, path_parts: &[String], orphaned: bool) -> String {
+    if orphaned {
+        format!("{}:\\$OrphanFiles\\{}", drive_letter, path_parts.join("\\"))
+    } else {
+        format!("{}:\\{}", drive_letter, path_parts.join("\\"))
+    }
 }
 
-/// Build full path by traversing parent references
+/// Build full path by traversing parent references. `parent_sequence` is the
+/// sequence number from the file's parent reference (0 when unknown). Also
+/// returns whether a directory along the way turned out to be a reused record.
 fn build_full_path(
     drive_letter: &str, 
     parent_record: u64, 
+    parent_sequence: u16,
     file_name: &str, 
-    dir_map: &std::collections::HashMap<u64, (u64, String)>
-) -> String {
+    dir_map: &std::collections::HashMap<u64, DirectoryInfo>
+) -> (String, bool) {
     let mut path_parts: Vec<String> = vec![file_name.to_string()];
     let mut current_parent = parent_record;
+    let mut expected_sequence = parent_sequence;
+    let mut reused = false;
     let mut depth = 0;
     
     // Root directory's parent reference is usually 5 (itself)
     while current_parent != 5 && depth < 100 {
-        if let Some((next_parent, dir_name)) = dir_map.get(&current_parent) {
+        if let Some(dir) = dir_map.get(&current_parent) {
+            if !sequence_matches(expected_sequence, dir.sequence_number, dir.in_use) {
+                reused = true;
+                break;
+            }
             // Skip system directories like "." 
-            if !dir_name.starts_with('$') && !dir_name.is_empty() && dir_name != "." {
-                path_parts.push(dir_name.clone());
+            if !dir.name.starts_with('$') && !dir.name.is_empty() && dir.name != "." {
+                path_parts.push(dir.name.clone());
             }
-            current_parent = *next_parent;
+            current_parent = dir.parent_record;
+            expected_sequence = dir.parent_sequence;
             depth += 1;
         } else {
             break;
@@ -943,7 +1016,7 @@ fn build_full_path(
     
     // Reverse to get path from root to file
     path_parts.reverse();
-    format!("{}:\\{}", drive_letter, path_parts.join("\\"))
+    (join_path(drive_letter, &path_parts, reused), reused)
 }
 
 /// Check if file is a temporary/system file that should be filtered out.
@@ -1009,7 +1082,7 @@ fn is_temp_file(name: &str, path: &str) -> bool {
 fn mft_entry_to_recoverable_with_path(
     drive_letter: &str, 
     entry: &MftEntry,
-    dir_map: &std::collections::HashMap<u64, (u64, String)>
+    dir_map: &std::collections::HashMap<u64, DirectoryInfo>
 ) -> Option<RecoverableFileFS> {
     // Skip entries with empty names (invalid MFT records)
     if entry.file_name.is_empty() {
@@ -1046,7 +1119,7 @@ fn mft_entry_to_recoverable_with_path(
     // For deleted files, clusters may have been reallocated by Windows, so
     // chances are much lower than for active files.  Be realistic so users
     // don't expect perfect recovery.
-    let recovery_chance = if !entry.is_deleted {
+    let recovery_chance: u8 = if !entry.is_deleted {
         95 // Existing files: very high chance (direct copy)
     } else if entry.data_runs.is_empty() {
         // Deleted with no cluster data — very hard to recover
@@ -1090,7 +1163,8 @@ fn mft_entry_to_recoverable_with_path(
     let cluster_offset = entry.data_runs.first().map(|r| r.cluster_offset);
     
     // Build proper full path using directory map
-    let full_path = build_full_path(drive_letter, entry.parent_record, &entry.file_name, dir_map);
+    let (full_path, parent_reused) =
+        build_full_path(drive_letter, entry.parent_record, entry.parent_sequence, &entry.file_name, dir_map);
     
     // Skip temporary/system/cache files — they flood results with junk
     if is_temp_file(&entry.file_name, &full_path) {
@@ -1108,7 +1182,14 @@ fn mft_entry_to_recoverable_with_path(
     let final_is_deleted = entry.is_deleted || is_recycle_bin;
     
     // Adjust recovery chance for Recycle Bin files (higher since data is intact)
-    let final_recovery_chance = if is_recycle_bin && !entry.is_deleted { 95 } else { recovery_chance };
+    let final_recovery_chance = if is_recycle_bin && !entry.is_deleted {
+        95
+    } else if entry.is_deleted && parent_reused {
+        // Its directory was deleted and the record reallocated since
+        recovery_chance.saturating_sub(REUSED_PARENT_PENALTY).max(3)
+    } else {
+        recovery_chance
+    };
     
     Some(RecoverableFileFS {
         id: format!("fs_mft_{}", entry.record_number),
@@ -0,0 +1,461 @@
+//! NTFS $LogFile Module
+//! Parses the log file service (LFS) restart area and RCRD pages of $LogFile
+//! (MFT record 2) and replays the NTFS redo/undo operations that touch MFT
+//! records, rebuilding file records that have since been freed or reused
+//!
+//! Deleting a file logs DeallocateFileRecordSegment with the whole record as
+//! its undo data, so for files deleted shortly before the scan the log still
+//! holds their names, timestamps, data runs and resident content.
+
+use crate::ntfs_parser::apply_fixup;
+use std::collections::{HashMap, HashSet};
+
+const RESTART_SIGNATURE: &[u8] = b"RSTR";
+const RECORD_PAGE_SIGNATURE: &[u8] = b"RCRD";
+const FILE_SIGNATURE: &[u8] = b"FILE";
+
+/// LFS record header preceding the NTFS client data
+const LFS_RECORD_HEADER: usize = 0x30;
+const LFS_CLIENT_RECORD: u32 = 1;
+const MAX_CLIENT_DATA: usize = 1024 * 1024;
+
+// NTFS log operations on MFT records
+pub const OP_INITIALIZE_FILE_RECORD_SEGMENT: u16 = 0x02;
+pub const OP_DEALLOCATE_FILE_RECORD_SEGMENT: u16 = 0x03;
+pub const OP_WRITE_END_OF_FILE_RECORD_SEGMENT: u16 = 0x04;
+pub const OP_CREATE_ATTRIBUTE: u16 = 0x05;
+pub const OP_DELETE_ATTRIBUTE: u16 = 0x06;
+pub const OP_UPDATE_RESIDENT_VALUE: u16 = 0x07;
+pub const OP_UPDATE_MAPPING_PAIRS: u16 = 0x09;
+pub const OP_SET_NEW_ATTRIBUTE_SIZES: u16 = 0x0B;
+
+/// Offset of the allocated/real/initialized sizes in a non-resident attribute
+const NONRESIDENT_SIZES_OFFSET: usize = 0x28;
+
+/// LFS restart area: page geometry and how LSNs map to file offsets
+#[derive(Debug, Clone)]
+pub struct RestartArea {
+    pub current_lsn: u64,
+    pub seq_number_bits: u32,
+    pub file_size: u64,
+    pub system_page_size: usize,
+    pub log_page_size: usize,
+    pub log_page_data_offset: usize,
+    pub major_version: i16,
+}
+
+impl RestartArea {
+    /// File offset of the record an LSN names
+    pub fn lsn_to_offset(&self, lsn: u64) -> u64 {
+        (lsn << self.seq_number_bits) >> (self.seq_number_bits - 3)
+    }
+
+    /// First page of the circular area, counted from the end of the restart pages.
+    /// LFS 1.x keeps two tail-copy pages in front of it.
+    fn first_log_page(&self) -> usize {
+        if self.major_version < 2 { 2 } else { 0 }
+    }
+}
+
+/// One NTFS client record: a redo/undo operation pair
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    pub lsn: u64,
+    pub transaction_id: u32,
+    pub redo_op: u16,
+    pub undo_op: u16,
+    pub target_record: u64,      // MFT record the operation applies to
+    pub record_offset: usize,    // Attribute offset within the record
+    pub attribute_offset: usize, // Offset within that attribute
+    pub redo: Vec<u8>,
+    pub undo: Vec<u8>,
+}
+
+/// An MFT record rebuilt by replaying log operations
+#[derive(Debug, Clone)]
+pub struct ReconstructedRecord {
+    pub record_number: u64,
+    pub lsn: u64,            // Last operation applied
+    pub transaction_id: u32,
+    pub deallocated: bool,   // The log saw the record freed
+    pub image: Vec<u8>,
+}
+
+fn read_u16(data: &[u8], at: usize) -> usize {
+    u16::from_le_bytes([data[at], data[at + 1]]) as usize
+}
+
+fn read_u32(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(data[at..at + 8].try_into().unwrap())
+}
+
+fn valid_page_size(size: usize) -> bool {
+    size.is_power_of_two() && (512..=65536).contains(&size)
+}
+
+fn parse_restart_page(data: &[u8]) -> Option<RestartArea> {
+    if data.len() < 0x20 || &data[0..4] != RESTART_SIGNATURE {
+        return None;
+    }
+    let system_page_size = read_u32(data, 0x10) as usize;
+    let log_page_size = read_u32(data, 0x14) as usize;
+    if !valid_page_size(system_page_size) || !valid_page_size(log_page_size) || data.len() < system_page_size {
+        return None;
+    }
+
+    let mut page = data[..system_page_size].to_vec();
+    apply_fixup(&mut page, read_u16(data, 4), read_u16(data, 6));
+    let area = read_u16(&page, 0x18);
+    if area + 0x30 > page.len() {
+        return None;
+    }
+
+    let restart = RestartArea {
+        current_lsn: read_u64(&page, area),
+        seq_number_bits: read_u32(&page, area + 0x10),
+        file_size: read_u64(&page, area + 0x18),
+        system_page_size,
+        log_page_size,
+        log_page_data_offset: read_u16(&page, area + 0x26),
+        major_version: i16::from_le_bytes([page[0x1C], page[0x1D]]),
+    };
+    ((3..=60).contains(&restart.seq_number_bits) && restart.log_page_data_offset < log_page_size).then_some(restart)
+}
+
+/// Parse the two restart pages and keep the more recent one
+pub fn parse_restart_area(log: &[u8]) -> Option<RestartArea> {
+    let first = parse_restart_page(log);
+    let second_offset = first.as_ref().map_or(4096, |r| r.system_page_size);
+    let second = log.get(second_offset..).and_then(parse_restart_page);
+
+    match (first, second) {
+        (Some(a), Some(b)) => Some(if b.current_lsn > a.current_lsn { b } else { a }),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Client data of a record, following it across page boundaries into the
+/// next pages of the circular area
+fn gather_client_data(pages: &[Option<Vec<u8>>], restart: &RestartArea, mut index: usize, mut start: usize, length: usize) -> Option<Vec<u8>> {
+    let mut data = Vec::with_capacity(length);
+
+    for _ in 0..pages.len() {
+        let page = pages.get(index)?.as_ref()?;
+        let take = (length - data.len()).min(page.len().saturating_sub(start));
+        data.extend_from_slice(&page[start..start + take]);
+        if data.len() == length {
+            return Some(data);
+        }
+        index = if index + 1 < pages.len() { index + 1 } else { restart.first_log_page() };
+        start = restart.log_page_data_offset;
+    }
+
+    None
+}
+
+fn parse_client_record(lsn: u64, transaction_id: u32, data: &[u8], cluster_size: u32, record_size: usize) -> Option<LogRecord> {
+    if data.len() < 0x20 {
+        return None;
+    }
+    let slice = |offset: usize, length: usize| data.get(offset..offset + length).map(<[u8]>::to_vec).unwrap_or_default();
+
+    // Target = cluster (VCN of $MFT) + 512-byte block within it
+    let target = read_u64(data, 0x18) * cluster_size as u64 + read_u16(data, 0x14) as u64 * 512;
+
+    Some(LogRecord {
+        lsn,
+        transaction_id,
+        redo_op: read_u16(data, 0) as u16,
+        undo_op: read_u16(data, 2) as u16,
+        target_record: target / record_size as u64,
+        record_offset: read_u16(data, 0x10),
+        attribute_offset: read_u16(data, 0x12),
+        redo: slice(read_u16(data, 4), read_u16(data, 6)),
+        undo: slice(read_u16(data, 8), read_u16(data, 10)),
+    })
+}
+
+/// Decode every client record in the RCRD pages, oldest LSN first. A header
+/// is only trusted where its own LSN says it lives, which skips both
+/// continuation data and the stale copies in the LFS 1.x tail pages.
+pub fn parse_log_records(log: &[u8], restart: &RestartArea, cluster_size: u32, record_size: usize) -> Vec<LogRecord> {
+    let page_size = restart.log_page_size;
+    let log_start = 2 * restart.system_page_size;
+    let log_end = (restart.file_size as usize).min(log.len());
+    let pages: Vec<Option<Vec<u8>>> = log.get(log_start..log_end).unwrap_or_default()
+        .chunks_exact(page_size)
+        .map(|page| {
+            (&page[0..4] == RECORD_PAGE_SIGNATURE).then(|| {
+                let mut page = page.to_vec();
+                let (usa_offset, usa_count) = (read_u16(&page, 4), read_u16(&page, 6));
+                apply_fixup(&mut page, usa_offset, usa_count);
+                page
+            })
+        })
+        .collect();
+
+    let mut records = Vec::new();
+    let mut seen = HashSet::new();
+    for (index, page) in pages.iter().enumerate() {
+        let Some(page) = page else { continue };
+        let page_offset = (log_start + index * page_size) as u64;
+        let mut offset = restart.log_page_data_offset.next_multiple_of(8);
+
+        while offset + LFS_RECORD_HEADER <= page_size {
+            let lsn = read_u64(page, offset);
+            let length = read_u32(page, offset + 0x18) as usize;
+            if lsn == 0 || restart.lsn_to_offset(lsn) != page_offset + offset as u64 || length > MAX_CLIENT_DATA {
+                offset += 8;
+                continue;
+            }
+
+            let record_type = read_u32(page, offset + 0x20);
+            if record_type == LFS_CLIENT_RECORD && seen.insert(lsn) {
+                let transaction_id = read_u32(page, offset + 0x24);
+                if let Some(record) = gather_client_data(&pages, restart, index, offset + LFS_RECORD_HEADER, length)
+                    .and_then(|data| parse_client_record(lsn, transaction_id, &data, cluster_size, record_size))
+                {
+                    records.push(record);
+                }
+            }
+            offset += (LFS_RECORD_HEADER + length).next_multiple_of(8);
+        }
+    }
+
+    records.sort_by_key(|r| r.lsn);
+    records
+}
+
+fn overwrite(image: &mut [u8], at: usize, bytes: &[u8]) -> bool {
+    match image.get_mut(at..at + bytes.len()) {
+        Some(target) => {
+            target.copy_from_slice(bytes);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Apply one redo operation to a record image
+fn apply_redo(image: &mut Vec<u8>, op: &LogRecord) -> bool {
+    let record_size = image.len();
+    let at = op.record_offset;
+
+    match op.redo_op {
+        OP_CREATE_ATTRIBUTE if at <= record_size => {
+            image.splice(at..at, op.redo.iter().copied());
+            image.truncate(record_size);
+            true
+        }
+        OP_DELETE_ATTRIBUTE if at + 8 <= record_size => {
+            let length = read_u32(image, at + 4) as usize;
+            if length == 0 || at + length > record_size {
+                return false;
+            }
+            image.drain(at..at + length);
+            image.resize(record_size, 0);
+            true
+        }
+        OP_WRITE_END_OF_FILE_RECORD_SEGMENT | OP_UPDATE_RESIDENT_VALUE | OP_UPDATE_MAPPING_PAIRS => {
+            overwrite(image, at + op.attribute_offset, &op.redo)
+        }
+        OP_SET_NEW_ATTRIBUTE_SIZES => overwrite(image, at + NONRESIDENT_SIZES_OFFSET, &op.redo),
+        _ => false,
+    }
+}
+
+fn record_image(bytes: &[u8], record_size: usize) -> Vec<u8> {
+    let mut image = bytes[..bytes.len().min(record_size)].to_vec();
+    image.resize(record_size, 0);
+    image
+}
+
+/// Replay MFT operations in LSN order. Each InitializeFileRecordSegment starts
+/// a new image; a deallocation's undo data is the record as it was deleted.
+/// Freed images are kept even when the record is initialized again later.
+pub fn reconstruct_records(records: &[LogRecord], record_size: usize) -> Vec<ReconstructedRecord> {
+    let mut current: HashMap<u64, ReconstructedRecord> = HashMap::new();
+    let mut freed = Vec::new();
+
+    for op in records {
+        let rebuilt = |image: &[u8], deallocated: bool| ReconstructedRecord {
+            record_number: op.target_record,
+            lsn: op.lsn,
+            transaction_id: op.transaction_id,
+            deallocated,
+            image: record_image(image, record_size),
+        };
+
+        match op.redo_op {
+            OP_INITIALIZE_FILE_RECORD_SEGMENT => {
+                if let Some(old) = current.insert(op.target_record, rebuilt(&op.redo, false)) {
+                    freed.extend(old.deallocated.then_some(old));
+                }
+            }
+            OP_DEALLOCATE_FILE_RECORD_SEGMENT if op.undo_op == OP_INITIALIZE_FILE_RECORD_SEGMENT && op.undo.starts_with(FILE_SIGNATURE) => {
+                if let Some(old) = current.insert(op.target_record, rebuilt(&op.undo, true)) {
+                    freed.extend(old.deallocated.then_some(old));
+                }
+            }
+            OP_DEALLOCATE_FILE_RECORD_SEGMENT => {
+                if let Some(record) = current.get_mut(&op.target_record) {
+                    record.deallocated = true;
+                    record.lsn = op.lsn;
+                    record.transaction_id = op.transaction_id;
+                }
+            }
+            _ => {
+                if let Some(record) = current.get_mut(&op.target_record).filter(|r| !r.deallocated) {
+                    if apply_redo(&mut record.image, op) {
+                        record.lsn = op.lsn;
+                        record.transaction_id = op.transaction_id;
+                    }
+                }
+            }
+        }
+    }
+
+    freed.extend(current.into_values());
+    freed.retain(|r| r.image.starts_with(FILE_SIGNATURE));
+    freed.sort_by_key(|r| (r.record_number, r.lsn));
+    freed
+}
+
+/// Replay a whole $LogFile and return the MFT records it can rebuild
+pub fn scan_logfile(log: &[u8], cluster_size: u32, record_size: usize) -> Result<Vec<ReconstructedRecord>, String> {
+    let restart = parse_restart_area(log).ok_or("$LogFile has no valid restart area")?;
+    let records = parse_log_records(log, &restart, cluster_size, record_size);
+    eprintln!("$LogFile: {} client records up to LSN {}", records.len(), restart.current_lsn);
+    Ok(reconstruct_records(&records, record_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: usize = 4096;
+    const DATA_OFFSET: usize = 0x40;
+    const SEQ_BITS: u32 = 44;
+    const OP_NOOP: u16 = 0x00;
+
+    struct Op {
+        redo_op: u16,
+        undo_op: u16,
+        record: u64,
+        record_offset: u16,
+        attribute_offset: u16,
+        redo: Vec<u8>,
+        undo: Vec<u8>,
+    }
+
+    fn op(redo_op: u16, record: u64, record_offset: u16, attribute_offset: u16, redo: &[u8]) -> Op {
+        Op { redo_op, undo_op: OP_NOOP, record, record_offset, attribute_offset, redo: redo.to_vec(), undo: Vec::new() }
+    }
+
+    /// LFS 2.0 log: two restart pages, then RCRD pages written as one stream
+    fn build_log(ops: &[Op]) -> Vec<u8> {
+        let mut log = vec![0u8; 2 * PAGE + 4 * PAGE];
+        let log_size = log.len() as u64;
+        for restart in [0, PAGE] {
+            let page = &mut log[restart..restart + PAGE];
+            page[0..4].copy_from_slice(RESTART_SIGNATURE);
+            page[0x10..0x14].copy_from_slice(&(PAGE as u32).to_le_bytes());
+            page[0x14..0x18].copy_from_slice(&(PAGE as u32).to_le_bytes());
+            page[0x18..0x1A].copy_from_slice(&0x30u16.to_le_bytes());
+            page[0x1C..0x1E].copy_from_slice(&2i16.to_le_bytes());
+            page[0x30 + 0x10..0x30 + 0x14].copy_from_slice(&SEQ_BITS.to_le_bytes());
+            page[0x30 + 0x18..0x30 + 0x20].copy_from_slice(&log_size.to_le_bytes());
+            page[0x30 + 0x26..0x30 + 0x28].copy_from_slice(&(DATA_OFFSET as u16).to_le_bytes());
+        }
+        for page in log[2 * PAGE..].chunks_exact_mut(PAGE) {
+            page[0..4].copy_from_slice(RECORD_PAGE_SIGNATURE);
+        }
+
+        // Writes `bytes` at `pos`, continuing in the next page's data area
+        fn write(log: &mut [u8], pos: &mut usize, bytes: &[u8]) {
+            for &b in bytes {
+                if pos.is_multiple_of(PAGE) {
+                    *pos += DATA_OFFSET;
+                }
+                log[*pos] = b;
+                *pos += 1;
+            }
+        }
+
+        let mut pos = 2 * PAGE + DATA_OFFSET;
+        for (i, op) in ops.iter().enumerate() {
+            let mut client = vec![0u8; 0x28];
+            client[0..2].copy_from_slice(&op.redo_op.to_le_bytes());
+            client[2..4].copy_from_slice(&op.undo_op.to_le_bytes());
+            client[4..6].copy_from_slice(&0x28u16.to_le_bytes());
+            client[6..8].copy_from_slice(&(op.redo.len() as u16).to_le_bytes());
+            client[8..10].copy_from_slice(&((0x28 + op.redo.len()) as u16).to_le_bytes());
+            client[10..12].copy_from_slice(&(op.undo.len() as u16).to_le_bytes());
+            client[0x10..0x12].copy_from_slice(&op.record_offset.to_le_bytes());
+            client[0x12..0x14].copy_from_slice(&op.attribute_offset.to_le_bytes());
+            // 1 KiB records in 4 KiB clusters
+            client[0x14..0x16].copy_from_slice(&((op.record % 4 * 2) as u16).to_le_bytes());
+            client[0x18..0x20].copy_from_slice(&(op.record / 4).to_le_bytes());
+            client.extend_from_slice(&op.redo);
+            client.extend_from_slice(&op.undo);
+
+            if PAGE - pos % PAGE < LFS_RECORD_HEADER {
+                pos = pos.next_multiple_of(PAGE) + DATA_OFFSET;
+            }
+            let lsn = (1u64 << (64 - SEQ_BITS)) | (pos as u64 >> 3);
+            let mut header = vec![0u8; LFS_RECORD_HEADER];
+            header[0..8].copy_from_slice(&lsn.to_le_bytes());
+            header[0x18..0x1C].copy_from_slice(&(client.len() as u32).to_le_bytes());
+            header[0x20..0x24].copy_from_slice(&LFS_CLIENT_RECORD.to_le_bytes());
+            header[0x24..0x28].copy_from_slice(&(i as u32 + 1).to_le_bytes());
+            write(&mut log, &mut pos, &header);
+            write(&mut log, &mut pos, &client);
+            pos = pos.next_multiple_of(8);
+        }
+        log
+    }
+
+    /// FILE record with a resident unnamed $DATA attribute at 0x38
+    fn file_record(content: &[u8]) -> Vec<u8> {
+        let mut record = vec![0u8; 1024];
+        record[0..4].copy_from_slice(FILE_SIGNATURE);
+        record[0x14..0x16].copy_from_slice(&0x38u16.to_le_bytes());
+        record[0x38..0x3C].copy_from_slice(&0x80u32.to_le_bytes());
+        record[0x3C..0x40].copy_from_slice(&0x28u32.to_le_bytes());
+        record[0x48..0x4C].copy_from_slice(&(content.len() as u32).to_le_bytes());
+        record[0x4C..0x4E].copy_from_slice(&0x18u16.to_le_bytes());
+        record[0x50..0x50 + content.len()].copy_from_slice(content);
+        record[0x60..0x64].copy_from_slice(&0xFFFF_FFFFu32.to_le_bytes());
+        record
+    }
+
+    #[test]
+    fn test_replay_keeps_deallocated_record_across_pages() {
+        let ops = [
+            op(OP_INITIALIZE_FILE_RECORD_SEGMENT, 7, 0, 0, &file_record(b"draft v1")),
+            // Pushes the next records over a page boundary
+            op(OP_NOOP, 0, 0, 0, &[0xAA; 2900]),
+            op(OP_UPDATE_RESIDENT_VALUE, 7, 0x38, 0x18, b"draft v2"),
+            op(OP_DEALLOCATE_FILE_RECORD_SEGMENT, 7, 0, 0, &[]),
+            op(OP_INITIALIZE_FILE_RECORD_SEGMENT, 7, 0, 0, &file_record(b"other")),
+        ];
+        let log = build_log(&ops);
+
+        let restart = parse_restart_area(&log).unwrap();
+        let records = parse_log_records(&log, &restart, 4096, 1024);
+        assert_eq!(records.len(), ops.len());
+        assert_eq!(records[2].redo, b"draft v2");
+        assert_eq!(records[2].target_record, 7);
+
+        let rebuilt = reconstruct_records(&records, 1024);
+        assert_eq!(rebuilt.len(), 2);
+        let deleted = rebuilt.iter().find(|r| r.deallocated).unwrap();
+        assert_eq!(deleted.lsn, records[3].lsn);
+        assert_eq!(&deleted.image[0x50..0x58], b"draft v2");
+        assert!(!rebuilt.iter().find(|r| !r.deallocated).unwrap().image.windows(5).any(|w| w == b"draft"));
+    }
+}
@@ -10,15 +10,28 @@
 //! Requires Administrator privileges for raw disk access.
 
 mod bitlocker;
+mod cluster_map;
+mod directory_tree;
 mod disk_reader;
+mod ewf;
+mod exfat;
+mod fat;
 mod file_carver;
 mod filesystem_disk_reader;
 mod filesystem_recovery_engine;
+mod logfile;
+mod mft_rebuild;
+mod ntfs_compression;
+mod ntfs_index;
 mod ntfs_parser;
+mod partition;
 mod recovery_engine;
+mod usn_journal;
+mod virtual_disk;
 mod vss;
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 use std::fs;
 use std::path::Path;
@@ -32,7 +45,11 @@ use std::os::windows::ffi::OsStrExt;
 use crate::bitlocker::{
     get_bitlocker_status, is_admin, lock_drive, unlock_with_password, unlock_with_recovery_key,
 };
-use crate::recovery_engine::{perform_scan, recover_file as recover_deleted_file};
+use crate::disk_reader::ScanSource;
+use crate::recovery_engine::{
+    perform_scan, perform_scan_source, recover_file as recover_deleted_file, recover_file_from_source,
+    recover_tree, recover_tree_from_source,
+};
 use crate::filesystem_recovery_engine::FileSystemRecoveryEngine;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -61,6 +78,7 @@ struct DriveInfo {
     is_bitlocker: bool,
     is_locked: bool,
     filesystem: String,
+    partition: Option<partition::VolumeLocation>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -71,6 +89,7 @@ struct AdminStatus {
 
 fn get_drives() -> Vec<DriveInfo> {
     let mut drives = Vec::new();
+    let mut layouts = HashMap::new();
     
     // Scan for Windows drive letters A-Z
     for letter in b'A'..=b'Z' {
@@ -86,6 +105,9 @@ fn get_drives() -> Vec<DriveInfo> {
             // Check BitLocker status
             let bl_status = get_bitlocker_status(&drive_letter);
             
+            // Physical disk and partition layout behind the letter
+            let location = partition::locate_volume(&drive_letter, &mut layouts);
+            
             drives.push(DriveInfo {
                 letter: drive_letter,
                 label,
@@ -94,6 +116,7 @@ fn get_drives() -> Vec<DriveInfo> {
                 is_bitlocker: bl_status.is_encrypted,
                 is_locked: bl_status.is_locked,
                 filesystem,
+                partition: location,
             });
         }
     }
@@ -132,6 +155,7 @@ fn perform_scan_filesystem(drive_letter: &str, mode: &str) -> recovery_engine::R
             mft_records_scanned: 0,
             orphan_records_found: 0,
             requires_admin: true,
+            directory_tree: None,
         };
     }
     
@@ -154,6 +178,7 @@ fn perform_scan_filesystem(drive_letter: &str, mode: &str) -> recovery_engine::R
             mft_records_scanned: 0,
             orphan_records_found: 0,
             requires_admin: false,
+            directory_tree: None,
         };
     }
     
@@ -200,6 +225,18 @@ fn perform_scan_filesystem(drive_letter: &str, mode: &str) -> recovery_engine::R
                     recoverable_bytes: fs_file.size,
                     difficulty: "easy".to_string(),
                     age_estimate: "unknown".to_string(),
+                    stream_name: None,
+                    resident_data: None,
+                    compression: None,
+                    stored_size: None,
+                    initialized_size: None,
+                    mft_reference: None,
+                    lsn: None,
+                    cross_links: None,
+                    timestamps: None,
+                    hard_links: None,
+                    exfat_timestamps: None,
+                    usn_reasons: None,
                 }
             }).collect();
             
@@ -224,6 +261,7 @@ fn perform_scan_filesystem(drive_letter: &str, mode: &str) -> recovery_engine::R
                 mft_records_scanned: fs_result.mft_records_scanned,
                 orphan_records_found: 0,
                 requires_admin: true,
+                directory_tree: None,
             }
         }
         Err(e) => recovery_engine::RecoveryScanResult {
@@ -242,6 +280,7 @@ fn perform_scan_filesystem(drive_letter: &str, mode: &str) -> recovery_engine::R
             mft_records_scanned: 0,
             orphan_records_found: 0,
             requires_admin: true,
+            directory_tree: None,
         }
     }
 }
@@ -437,6 +476,121 @@ fn recover_file_copy(source: &str, destination: &str) -> Result<(), String> {
     }
 }
 
+/// Split `--image <path>` / `--disk <n|path>` / `--offset <n>` / `--partition <index>`
+/// out of a command's arguments.
+/// Returns the image/disk source (if one was given) and the remaining positional arguments.
+fn parse_source_args(args: &[String]) -> Result<(Option<ScanSource>, Vec<String>), String> {
+    let mut image: Option<String> = None;
+    let mut device: Option<String> = None;
+    let mut offset: Option<u64> = None;
+    let mut partition: Option<usize> = None;
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+    
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--image" => {
+                image = Some(iter.next().ok_or("--image requires a file path")?.clone());
+            }
+            "--disk" => {
+                let value = iter.next().ok_or("--disk requires a disk number or device path")?;
+                device = Some(match value.parse::<u32>() {
+                    Ok(number) => format!("\\\\.\\PhysicalDrive{}", number),
+                    Err(_) => value.clone(),
+                });
+            }
+            "--offset" => {
+                let value = iter.next().ok_or("--offset requires a value")?;
+                offset = Some(parse_offset(value)?);
+            }
+            "--partition" => {
+                let value = iter.next().ok_or("--partition requires an index")?;
+                partition = Some(value.parse().map_err(|_| format!("Invalid partition index: {}", value))?);
+            }
+            _ => positional.push(arg.clone()),
+        }
+    }
+    
+    let source = match (image, device) {
+        (Some(_), Some(_)) => return Err("--image and --disk cannot be combined".to_string()),
+        (Some(path), None) => ScanSource::Image { path, offset: 0 },
+        (None, Some(path)) => ScanSource::Device { path, offset: 0 },
+        (None, None) if offset.is_some() || partition.is_some() => {
+            return Err("--offset and --partition can only be used together with --image or --disk".to_string());
+        }
+        (None, None) => return Ok((None, positional)),
+    };
+    
+    let source = match (offset, partition) {
+        (Some(_), Some(_)) => return Err("--offset and --partition cannot be combined".to_string()),
+        (Some(offset), None) => source.at_offset(offset),
+        (None, Some(index)) => partition::select_partition(&source, index)?,
+        (None, None) => source,
+    };
+    Ok((Some(source), positional))
+}
+
+/// Parse a partition offset: plain bytes ("1048576"), hex ("0x100000")
+/// or 512-byte sectors as printed by partition tools ("2048s")
+fn parse_offset(value: &str) -> Result<u64, String> {
+    let parsed = if let Some(sectors) = value.strip_suffix('s') {
+        sectors.parse::<u64>().ok().and_then(|s| s.checked_mul(512))
+    } else if let Some(hex) = value.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16).ok()
+    } else {
+        value.parse::<u64>().ok()
+    };
+    parsed.ok_or_else(|| format!("Invalid offset: {}", value))
+}
+
+/// Parse source arguments or exit with a usage error
+fn source_args_or_exit(args: &[String]) -> (Option<ScanSource>, Vec<String>) {
+    match parse_source_args(args) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Describe a disk image: format, media size, (for EWF) case metadata and stored
+/// acquisition hashes, optionally re-hashing the media to verify them, and (for VM
+/// disks) the parent chain
+fn image_info(path: &str, verify: bool) -> Result<serde_json::Value, String> {
+    let mut magic = [0u8; 8];
+    let magic_len = fs::File::open(path)
+        .and_then(|mut f| std::io::Read::read(&mut f, &mut magic))
+        .map_err(|e| format!("Failed to open image {}: {}", path, e))?;
+    
+    if !ewf::is_ewf(&magic[..magic_len]) {
+        if let Some(disk) = virtual_disk::VirtualDisk::open(path)? {
+            return Ok(serde_json::json!({
+                "success": true,
+                "format": disk.info().format,
+                "media_size": disk.size(),
+                "virtual_disk": disk.info(),
+            }));
+        }
+        let size = fs::metadata(path).map_err(|e| e.to_string())?.len();
+        return Ok(serde_json::json!({
+            "success": true,
+            "format": "raw",
+            "media_size": size,
+        }));
+    }
+    
+    let mut image = ewf::EwfImage::open(path)?;
+    let verification = if verify { Some(image.verify()?) } else { None };
+    
+    Ok(serde_json::json!({
+        "success": true,
+        "format": "ewf",
+        "ewf": image.info(),
+        "verification": verification,
+    }))
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     
@@ -455,19 +609,31 @@ fn main() {
             println!("{}", json);
         }
         "scan" => {
-            if args.len() < 3 {
+            let (image, positional) = source_args_or_exit(&args[2..]);
+            if image.is_none() && positional.is_empty() {
                 eprintln!("Usage: data_recovery_backend scan <drive> [mode]");
+                eprintln!("       data_recovery_backend scan --image <path> [--offset <bytes> | --partition <index>] [mode]");
+                eprintln!("       data_recovery_backend scan --disk <n> --partition <index> [mode]");
                 eprintln!("  drive: Drive letter (e.g., C)");
                 eprintln!("  mode: Optional - 'quick' (last 24h) or 'deep' (all) - default: quick");
                 std::process::exit(1);
             }
-            let drive = &args[2];
-            let mode = args.get(3).map(|s| s.as_str()).unwrap_or("quick");
             
-            eprintln!("DEBUG [Main]: Starting scan - drive: {}, mode: {}", drive, mode);
-            
-            // Use filesystem scanner for deep recovery (works with BitLocker)
-            let result = perform_scan_filesystem(drive, mode);
+            let result = if let Some(source) = image {
+                let mode = positional.first().map(|s| s.as_str()).unwrap_or("quick");
+                eprintln!("DEBUG [Main]: Starting image scan - source: {:?}, mode: {}", source, mode);
+                
+                // Images and physical disks are read with the raw-disk engine (no Windows volume APIs involved)
+                perform_scan_source(&source, mode)
+            } else {
+                let drive = &positional[0];
+                let mode = positional.get(1).map(|s| s.as_str()).unwrap_or("quick");
+                
+                eprintln!("DEBUG [Main]: Starting scan - drive: {}, mode: {}", drive, mode);
+                
+                // Use filesystem scanner for deep recovery (works with BitLocker)
+                perform_scan_filesystem(drive, mode)
+            };
             let json = serde_json::to_string(&result).unwrap();
             println!("{}", json);
             
@@ -561,13 +727,30 @@ fn main() {
         
         // Professional Recovery Commands
         "deep-scan" => {
-            if args.len() < 3 {
+            let (image, positional) = source_args_or_exit(&args[2..]);
+            if image.is_none() && positional.is_empty() {
                 eprintln!("Usage: data_recovery_backend deep-scan <drive> [mode]");
-                eprintln!("Modes: quick, deep (default: quick)");
+                eprintln!("       data_recovery_backend deep-scan --image <path> [--offset <bytes> | --partition <index>] [mode]");
+                eprintln!("       data_recovery_backend deep-scan --disk <n> --partition <index> [mode]");
+                eprintln!("Modes: quick, deep, deep-free, mft-rebuild (default: quick)");
                 std::process::exit(1);
             }
-            let drive = &args[2];
-            let mode = args.get(3).map(|s| s.as_str()).unwrap_or("quick");
+            
+            if let Some(source) = image {
+                let mode = positional.first().map(|s| s.as_str()).unwrap_or("quick");
+                eprintln!("[AUTO-SELECT] Disk image / physical disk source - using Raw Disk backend");
+                let result = perform_scan_source(&source, mode);
+                let json = serde_json::to_string(&result).unwrap();
+                println!("{}", json);
+                
+                if !result.success {
+                    std::process::exit(1);
+                }
+                return;
+            }
+            
+            let drive = &positional[0];
+            let mode = positional.get(1).map(|s| s.as_str()).unwrap_or("quick");
             
             // SMART BACKEND ROUTING:
             // Check if drive is encrypted and auto-select backend
@@ -597,15 +780,48 @@ fn main() {
         }
         
         "recover-deleted" => {
-            if args.len() < 5 {
-                eprintln!("Usage: data_recovery_backend recover-deleted <drive> <file_json> <destination>");
+            let (image, mut positional) = source_args_or_exit(&args[2..]);
+            let with_streams = positional.iter().any(|a| a == "--streams");
+            let with_hard_links = positional.iter().any(|a| a == "--hard-links");
+            positional.retain(|a| a != "--streams" && a != "--hard-links");
+            let needed = if image.is_some() { 2 } else { 3 };
+            if positional.len() < needed {
+                eprintln!("Usage: data_recovery_backend recover-deleted <drive> <file_json> <destination> [--streams] [--hard-links]");
+                eprintln!("       data_recovery_backend recover-deleted --image <path> [--offset <bytes> | --partition <index>] <file_json> <destination>");
+                eprintln!("       data_recovery_backend recover-deleted --disk <n> --partition <index> <file_json> <destination>");
+                std::process::exit(1);
+            }
+            
+            let result = match image {
+                Some(source) => recover_file_from_source(&source, &positional[0], &positional[1], with_streams, with_hard_links),
+                None => recover_deleted_file(&positional[0], &positional[1], &positional[2], with_streams, with_hard_links),
+            };
+            let json = serde_json::to_string(&result).unwrap();
+            println!("{}", json);
+            
+            if !result.success {
+                std::process::exit(1);
+            }
+        }
+        
+        "recover-tree" => {
+            let (image, positional) = source_args_or_exit(&args[2..]);
+            let needed = if image.is_some() { 2 } else { 3 };
+            if positional.len() < needed {
+                eprintln!("Usage: data_recovery_backend recover-tree <drive> <record> <destination>");
+                eprintln!("       data_recovery_backend recover-tree --image <path> [--offset <bytes> | --partition <index>] <record> <destination>");
                 std::process::exit(1);
             }
-            let drive = &args[2];
-            let file_json = &args[3];
-            let destination = &args[4];
             
-            let result = recover_deleted_file(drive, file_json, destination);
+            let (record, destination) = (&positional[needed - 2], &positional[needed - 1]);
+            let Ok(record) = record.parse::<u64>() else {
+                eprintln!("Invalid MFT record number: {}", record);
+                std::process::exit(1);
+            };
+            let result = match image {
+                Some(source) => recover_tree_from_source(&source, record, destination),
+                None => recover_tree(&positional[0], record, destination),
+            };
             let json = serde_json::to_string(&result).unwrap();
             println!("{}", json);
             
@@ -614,6 +830,58 @@ fn main() {
             }
         }
         
+        "partitions" => {
+            let (source, _) = source_args_or_exit(&args[2..]);
+            let Some(source) = source else {
+                eprintln!("Usage: data_recovery_backend partitions --image <path>");
+                eprintln!("       data_recovery_backend partitions --disk <n>");
+                std::process::exit(1);
+            };
+            
+            match source.open().and_then(|mut disk| partition::read_partition_table(&mut disk)) {
+                Ok(table) => println!("{}", serde_json::to_string(&table).unwrap()),
+                Err(e) => {
+                    println!("{}", serde_json::json!({ "success": false, "message": e }));
+                    std::process::exit(1);
+                }
+            }
+        }
+        
+        "find-partitions" => {
+            let (source, _) = source_args_or_exit(&args[2..]);
+            let Some(source) = source else {
+                eprintln!("Usage: data_recovery_backend find-partitions --image <path>");
+                eprintln!("       data_recovery_backend find-partitions --disk <n>");
+                std::process::exit(1);
+            };
+            
+            let result = partition::find_partitions(&source);
+            println!("{}", serde_json::to_string(&result).unwrap());
+            if !result.success {
+                std::process::exit(1);
+            }
+        }
+        
+        "image-info" => {
+            let (image, positional) = source_args_or_exit(&args[2..]);
+            let path = match image {
+                Some(ScanSource::Image { path, .. }) => path,
+                _ => {
+                    eprintln!("Usage: data_recovery_backend image-info --image <path> [--verify]");
+                    std::process::exit(1);
+                }
+            };
+            let verify = positional.iter().any(|a| a == "--verify");
+            
+            match image_info(&path, verify) {
+                Ok(info) => println!("{}", info),
+                Err(e) => {
+                    println!("{}", serde_json::json!({ "success": false, "message": e }));
+                    std::process::exit(1);
+                }
+            }
+        }
+        
         "file-signatures" => {
             let stats = file_carver::get_signature_stats();
             let json = serde_json::to_string(&stats).unwrap();
@@ -751,7 +1019,7 @@ RecoverPro Backend v2.0.0
 
 BASIC COMMANDS:
   drives                          List all available drives
-  scan <path>                     Scan directory for existing files
+  scan <drive> [mode]             Scan a drive for deleted files
   recover <source> <destination>  Copy a file (legacy recovery)
 
 ADMIN & BITLOCKER:
@@ -765,11 +1033,33 @@ ADMIN & BITLOCKER:
 
 PROFESSIONAL RECOVERY:
   deep-scan <drive> [mode]        Scan for deleted files
-                                  Modes: quick (MFT only), deep (MFT + carving)
-  recover-deleted <drive> <file_json> <destination>
-                                  Recover a deleted file
+                                  Modes: quick (MFT only), deep (MFT + carving),
+                                  deep-free (MFT + carving of unallocated clusters only),
+                                  mft-rebuild (MFT located by sweeping for FILE records)
+  recover-deleted <drive> <file_json> <destination> [--streams] [--hard-links]
+                                  Recover a deleted file (or one alternate data stream);
+                                  --streams also restores all of its streams to <destination>.streams,
+                                  --hard-links recreates each of its original paths under <destination>.links
+  recover-tree <drive> <record> <destination>
+                                  Restore a directory (MFT record from the scan's directory_tree) with
+                                  all recoverable files below it, keeping their relative paths
   file-signatures                 List supported file signatures
 
+DISK IMAGES & PHYSICAL DISKS:
+  scan, deep-scan, recover-deleted and recover-tree accept a disk image or physical disk instead of <drive>:
+    --image <path>                Raw image (.dd/.img/.raw), EWF image (.E01, segments are chained)
+                                  or VM disk (.vhd/.vhdx/.vmdk/.qcow2/.vdi, parents are followed)
+    --disk <n|path>               Physical disk (\\\\.\\PhysicalDrive<n>), requires Administrator
+    --offset <bytes>              Volume start inside the image (e.g. 1048576, 0x100000, 2048s)
+    --partition <index>           Volume = partition <index> from the MBR/GPT table
+  partitions --image <path> | --disk <n>
+                                  List MBR/GPT partitions (type, GUIDs, names, filesystem)
+  find-partitions --image <path> | --disk <n>
+                                  Search for lost NTFS/FAT/exFAT volumes (use the offset with --offset)
+  image-info --image <path> [--verify]
+                                  Show image format, case metadata, stored MD5/SHA1 and VM disk chain;
+                                  --verify re-hashes the media and compares
+
 VSS (VOLUME SHADOW COPY):
   vss-check                       Check if VSS is available
   vss-enumerate <drive>           List all snapshots for a drive
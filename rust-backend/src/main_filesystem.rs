@@ -6,14 +6,27 @@
 //! Requires Administrator privileges for $MFT access.
 
 mod bitlocker;
+mod cluster_map;
+mod directory_tree;
 mod disk_reader;
+mod ewf;
+mod exfat;
+mod fat;
 mod file_carver;
 mod filesystem_disk_reader;
 mod filesystem_recovery_engine;
+mod logfile;
+mod mft_rebuild;
+mod ntfs_compression;
+mod ntfs_index;
 mod ntfs_parser;
+mod partition;
 mod recovery_engine;
+mod usn_journal;
+mod virtual_disk;
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 use std::path::Path;
 
@@ -32,6 +45,7 @@ struct DriveInfo {
     is_bitlocker: bool,
     is_locked: bool,
     filesystem: String,
+    partition: Option<partition::VolumeLocation>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -42,6 +56,7 @@ struct AdminStatus {
 
 fn get_drives() -> Vec<DriveInfo> {
     let mut drives = Vec::new();
+    let mut layouts = HashMap::new();
     
     for letter in b'A'..=b'Z' {
         let drive_letter = format!("{}:", letter as char);
@@ -53,6 +68,8 @@ fn get_drives() -> Vec<DriveInfo> {
             let filesystem = get_filesystem(&drive_letter);
             let bl_status = get_bitlocker_status(&drive_letter);
             
+            let location = partition::locate_volume(&drive_letter, &mut layouts);
+            
             drives.push(DriveInfo {
                 letter: drive_letter,
                 label,
@@ -61,6 +78,7 @@ fn get_drives() -> Vec<DriveInfo> {
                 is_bitlocker: bl_status.is_encrypted,
                 is_locked: bl_status.is_locked,
                 filesystem,
+                partition: location,
             });
         }
     }
@@ -209,6 +227,7 @@ fn perform_scan_filesystem(drive_letter: &str, mode: &str) -> RecoveryScanResult
             mft_records_scanned: 0,
             orphan_records_found: 0,
             requires_admin: true,
+            directory_tree: None,
         };
     }
     
@@ -231,6 +250,7 @@ fn perform_scan_filesystem(drive_letter: &str, mode: &str) -> RecoveryScanResult
             mft_records_scanned: 0,
             orphan_records_found: 0,
             requires_admin: false,
+            directory_tree: None,
         };
     }
     
@@ -271,6 +291,18 @@ fn perform_scan_filesystem(drive_letter: &str, mode: &str) -> RecoveryScanResult
                     recoverable_bytes: fs_file.size,
                     difficulty: "easy".to_string(),
                     age_estimate: "unknown".to_string(),
+                    stream_name: None,
+                    resident_data: None,
+                    compression: None,
+                    stored_size: None,
+                    initialized_size: None,
+                    mft_reference: None,
+                    lsn: None,
+                    cross_links: None,
+                    timestamps: None,
+                    hard_links: None,
+                    exfat_timestamps: None,
+                    usn_reasons: None,
                 }
             }).collect();
             
@@ -290,6 +322,7 @@ fn perform_scan_filesystem(drive_letter: &str, mode: &str) -> RecoveryScanResult
                 mft_records_scanned: fs_result.mft_records_scanned,
                 orphan_records_found: 0,
                 requires_admin: true,
+                directory_tree: None,
             }
         }
         Err(e) => RecoveryScanResult {
@@ -308,6 +341,7 @@ fn perform_scan_filesystem(drive_letter: &str, mode: &str) -> RecoveryScanResult
             mft_records_scanned: 0,
             orphan_records_found: 0,
             requires_admin: true,
+            directory_tree: None,
         }
     }
 }
@@ -0,0 +1,203 @@
+//! MFT Rebuild Module
+//! Recovers the $MFT's layout when its own run list is lost, by sweeping the
+//! volume for intact FILE records and grouping them into extents
+//!
+//! Records of one MFT extent sit at a fixed distance from where their record
+//! number places them in the $MFT, so every sighting votes for an (extent
+//! start) hypothesis. Stale MFT copies and $MFTMirr produce small competing
+//! groups; the best-supported group wins each cluster of the rebuilt $MFT.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::disk_reader::DiskReader;
+use crate::ntfs_parser::DataRun;
+
+const FILE_SIGNATURE: &[u8] = b"FILE";
+const SCAN_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// An intact FILE record found on the volume
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecordSighting {
+    pub record_number: u64,
+    pub offset: u64, // Byte offset on the volume
+}
+
+/// Record number of an intact FILE record: every sector must end in the
+/// update sequence number, and the header must be NTFS 3.1 (which stores it)
+fn intact_record_number(record: &[u8]) -> Option<u64> {
+    if record.len() < 48 || &record[0..4] != FILE_SIGNATURE {
+        return None;
+    }
+    let update_seq_offset = u16::from_le_bytes([record[4], record[5]]) as usize;
+    let update_seq_size = u16::from_le_bytes([record[6], record[7]]) as usize;
+    if update_seq_offset < 0x30 || update_seq_size != record.len() / 512 + 1
+        || update_seq_offset + update_seq_size * 2 > record.len()
+    {
+        return None;
+    }
+
+    let usn = &record[update_seq_offset..update_seq_offset + 2];
+    if (1..update_seq_size).any(|i| &record[i * 512 - 2..i * 512] != usn) {
+        return None;
+    }
+    Some(u32::from_le_bytes(record[0x2C..0x30].try_into().unwrap()) as u64)
+}
+
+/// Sweep the volume for intact FILE records. Records start at MFT extent
+/// boundaries (clusters) plus a multiple of the record size.
+pub fn scan_file_records(
+    disk: &mut DiskReader,
+    record_size: u32,
+    cluster_size: u32,
+    cancelled: &AtomicBool,
+) -> Result<Vec<RecordSighting>, String> {
+    let record_size = record_size as usize;
+    let stride = record_size.min(cluster_size as usize);
+    let volume_size = disk.size();
+    let mut sightings = Vec::new();
+
+    let mut chunk_start = 0u64;
+    while chunk_start < volume_size {
+        if cancelled.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let length = (SCAN_CHUNK_SIZE as u64).min(volume_size - chunk_start) as usize;
+        let chunk = match disk.read_at(chunk_start, length) {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                eprintln!("Warning: Skipping unreadable range at {}: {}", chunk_start, e);
+                chunk_start += length as u64;
+                continue;
+            }
+        };
+
+        for at in (0..chunk.len()).step_by(stride) {
+            if chunk.get(at..at + 4) != Some(FILE_SIGNATURE) {
+                continue;
+            }
+            let offset = chunk_start + at as u64;
+            // A record straddling the chunk end is read on its own
+            let record = match chunk.get(at..at + record_size) {
+                Some(record) => record.to_vec(),
+                None => disk.read_at(offset, record_size).unwrap_or_default(),
+            };
+            if let Some(record_number) = intact_record_number(&record) {
+                sightings.push(RecordSighting { record_number, offset });
+            }
+        }
+
+        chunk_start += length as u64;
+    }
+
+    Ok(sightings)
+}
+
+/// Rebuild the $MFT's run list and size from record sightings. Clusters no
+/// group vouches for become sparse runs, which read back as zeros.
+pub fn rebuild_mft_runs(sightings: &[RecordSighting], record_size: u32, cluster_size: u32) -> Option<(Vec<DataRun>, u64)> {
+    let (record_size, cluster_size) = (record_size as u64, cluster_size as u64);
+    let clusters_per_record = (record_size / cluster_size).max(1);
+
+    // MFT cluster (VCN) -> extent hypotheses (LCN - VCN) seen there, and each hypothesis' support
+    let mut candidates: HashMap<u64, Vec<i64>> = HashMap::new();
+    let mut support: HashMap<i64, u64> = HashMap::new();
+    let mut max_record = None;
+    for sighting in sightings {
+        let mft_offset = sighting.record_number * record_size;
+        // The record must sit where its number puts it within a cluster
+        if mft_offset % cluster_size != sighting.offset % cluster_size {
+            continue;
+        }
+
+        let delta = (sighting.offset / cluster_size) as i64 - (mft_offset / cluster_size) as i64;
+        for vcn in mft_offset / cluster_size..mft_offset / cluster_size + clusters_per_record {
+            candidates.entry(vcn).or_default().push(delta);
+        }
+        *support.entry(delta).or_default() += 1;
+        max_record = max_record.max(Some(sighting.record_number));
+    }
+    let max_record = max_record?;
+
+    let clusters = ((max_record + 1) * record_size).div_ceil(cluster_size);
+    let mut chosen: Vec<Option<i64>> = (0..clusters)
+        .map(|vcn| candidates.get(&vcn).and_then(|deltas| deltas.iter().copied().max_by_key(|d| support[d])))
+        .collect();
+
+    // Unused records inside an extent hold no FILE record; bridge gaps whose
+    // two sides agree on the extent
+    let mut last: Option<(usize, i64)> = None;
+    for vcn in 0..chosen.len() {
+        if let Some(delta) = chosen[vcn] {
+            if let Some((previous, previous_delta)) = last {
+                if previous_delta == delta {
+                    chosen[previous + 1..vcn].fill(Some(delta));
+                }
+            }
+            last = Some((vcn, delta));
+        }
+    }
+
+    let mut runs: Vec<DataRun> = Vec::new();
+    let mut previous: Option<Option<i64>> = None;
+    for (vcn, delta) in chosen.into_iter().enumerate() {
+        match runs.last_mut() {
+            Some(run) if previous == Some(delta) => run.cluster_count += 1,
+            _ => runs.push(DataRun {
+                cluster_offset: delta.map_or(0, |d| vcn as i64 + d),
+                cluster_count: 1,
+            }),
+        }
+        previous = Some(delta);
+    }
+
+    Some((runs, (max_record + 1) * record_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(record_number: u32) -> Vec<u8> {
+        let mut record = vec![0u8; 1024];
+        record[0..4].copy_from_slice(FILE_SIGNATURE);
+        record[4..6].copy_from_slice(&0x30u16.to_le_bytes());
+        record[6..8].copy_from_slice(&3u16.to_le_bytes());
+        record[0x2C..0x30].copy_from_slice(&record_number.to_le_bytes());
+        record[0x30..0x32].copy_from_slice(&7u16.to_le_bytes());
+        record[510..512].copy_from_slice(&7u16.to_le_bytes());
+        record[1022..1024].copy_from_slice(&7u16.to_le_bytes());
+        record
+    }
+
+    #[test]
+    fn test_intact_record_number_checks_update_sequence() {
+        assert_eq!(intact_record_number(&record(42)), Some(42));
+        let mut torn = record(42);
+        torn[1022] = 0; // Second sector written by a different update
+        assert_eq!(intact_record_number(&torn), None);
+    }
+
+    #[test]
+    fn test_rebuild_mft_runs_from_two_extents_and_mirror() {
+        let cluster_size = 4096u64;
+        let sightings_at = |first: u64, count: u64, lcn: u64| -> Vec<RecordSighting> {
+            (0..count)
+                .map(|i| RecordSighting { record_number: first + i, offset: lcn * cluster_size + i * 1024 })
+                .collect()
+        };
+
+        // Records 0-15 at cluster 100, records 24-39 at cluster 500; 16-23 were
+        // never used and hold no FILE record; $MFTMirr holds 0-3 at cluster 8
+        let mut sightings = sightings_at(0, 12, 100);
+        sightings.extend(sightings_at(14, 2, 103).into_iter().map(|s| RecordSighting { offset: s.offset + 2 * 1024, ..s }));
+        sightings.extend(sightings_at(24, 16, 500));
+        sightings.extend(sightings_at(0, 4, 8));
+        let (runs, size) = rebuild_mft_runs(&sightings, 1024, 4096).unwrap();
+
+        let runs: Vec<(i64, u64)> = runs.iter().map(|r| (r.cluster_offset, r.cluster_count)).collect();
+        assert_eq!(runs, vec![(100, 4), (0, 2), (500, 4)]);
+        assert_eq!(size, 40 * 1024);
+    }
+}
@@ -0,0 +1,762 @@
+//! NTFS Compression Module
+//! Reads files stored with NTFS native (LZNT1) compression and with
+//! Windows Overlay Filter (WOF / CompactOS) system compression
+//!
+//! Compressed attributes are split into compression units of 16 clusters.
+//! Each unit is stored either raw (all 16 clusters allocated), as a sparse
+//! hole (no clusters allocated) or LZNT1-compressed in its first clusters
+//! followed by a sparse run padding the unit out to 16 clusters.
+//!
+//! WOF-compressed files carry an IO_REPARSE_TAG_WOF reparse point and keep
+//! their content in the `WofCompressedData` stream: a chunk offset table
+//! followed by independently compressed XPRESS Huffman or LZX chunks.
+
+use crate::disk_reader::{read_clusters, DiskReader};
+use crate::ntfs_parser::DataRun;
+
+/// Clusters per compression unit (NTFS always uses 2^4)
+pub const COMPRESSION_UNIT_CLUSTERS: u64 = 16;
+
+/// Uncompressed size of an LZNT1 chunk
+const LZNT1_CHUNK_SIZE: usize = 4096;
+
+/// Decompress an LZNT1 buffer (a sequence of chunks with 2-byte headers).
+/// Output stops at `output_size` bytes; chunks that decode short are zero
+/// padded to 4 KB because the next chunk always starts on a 4 KB boundary.
+pub fn lznt1_decompress(input: &[u8], output_size: usize) -> Result<Vec<u8>, String> {
+    let mut output = Vec::with_capacity(output_size);
+    let mut position = 0;
+
+    while position + 2 <= input.len() && output.len() < output_size {
+        let header = u16::from_le_bytes([input[position], input[position + 1]]);
+        if header == 0 {
+            break; // End of compressed data
+        }
+        position += 2;
+
+        let chunk_length = (header & 0x0FFF) as usize + 1;
+        if position + chunk_length > input.len() {
+            return Err(format!("LZNT1 chunk at offset {} runs past the end of the unit", position - 2));
+        }
+        let chunk = &input[position..position + chunk_length];
+        position += chunk_length;
+
+        let chunk_start = output.len();
+        if header & 0x8000 == 0 {
+            output.extend_from_slice(chunk);
+        } else {
+            decompress_chunk(chunk, &mut output, chunk_start)?;
+        }
+
+        // A short chunk followed by more data means the rest of the 4 KB was zeros
+        let chunk_end = chunk_start + LZNT1_CHUNK_SIZE;
+        if output.len() < chunk_end && position + 2 <= input.len() && input[position] | input[position + 1] != 0 {
+            output.resize(chunk_end, 0);
+        }
+    }
+
+    output.truncate(output_size);
+    Ok(output)
+}
+
+fn decompress_chunk(chunk: &[u8], output: &mut Vec<u8>, chunk_start: usize) -> Result<(), String> {
+    let mut position = 0;
+
+    while position < chunk.len() {
+        let flags = chunk[position];
+        position += 1;
+
+        for bit in 0..8 {
+            if position >= chunk.len() {
+                break;
+            }
+            if flags & (1 << bit) == 0 {
+                output.push(chunk[position]);
+                position += 1;
+                continue;
+            }
+
+            if position + 2 > chunk.len() {
+                return Err("Truncated LZNT1 back-reference".to_string());
+            }
+            let token = u16::from_le_bytes([chunk[position], chunk[position + 1]]) as usize;
+            position += 2;
+
+            // The offset/length split moves as the chunk fills up
+            let chunk_position = output.len() - chunk_start;
+            if chunk_position == 0 {
+                return Err("LZNT1 back-reference at the start of a chunk".to_string());
+            }
+            let mut length_bits = 12;
+            let mut p = chunk_position - 1;
+            while p >= 0x10 {
+                length_bits -= 1;
+                p >>= 1;
+            }
+            let offset = (token >> length_bits) + 1;
+            let length = (token & ((1 << length_bits) - 1)) + 3;
+
+            if offset > chunk_position {
+                return Err(format!("LZNT1 back-reference {} bytes before the chunk start", offset - chunk_position));
+            }
+            // Overlapping copies are byte-by-byte by design (run-length encoding)
+            let from = output.len() - offset;
+            for i in 0..length {
+                output.push(output[from + i]);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Read a compressed attribute and return its uncompressed content, plus the
+/// number of compression units that could not be read or decompressed
+/// (those are zero-filled).
+pub fn read_compressed_runs(
+    disk: &mut DiskReader,
+    runs: &[DataRun],
+    cluster_size: u32,
+    size: u64,
+) -> (Vec<u8>, u64) {
+    let unit_size = (COMPRESSION_UNIT_CLUSTERS * cluster_size as u64) as usize;
+    let mut output = Vec::with_capacity(size as usize);
+    let mut damaged_units = 0;
+
+    // (first VCN, LCN or 0 for sparse, cluster count)
+    let mut extents = Vec::with_capacity(runs.len());
+    let mut vcn = 0u64;
+    for run in runs {
+        extents.push((vcn, run.cluster_offset.max(0) as u64, run.cluster_count));
+        vcn += run.cluster_count;
+    }
+
+    let mut extent_index = 0;
+    let mut unit_vcn = 0u64;
+    while (output.len() as u64) < size {
+        let unit_end = unit_vcn + COMPRESSION_UNIT_CLUSTERS;
+
+        // Allocated pieces of this unit, in VCN order
+        let mut pieces: Vec<(u64, u64)> = Vec::new();
+        let mut index = extent_index;
+        while index < extents.len() && extents[index].0 < unit_end {
+            let (start, lcn, count) = extents[index];
+            let from = start.max(unit_vcn);
+            let to = (start + count).min(unit_end);
+            if from < to && lcn > 0 {
+                pieces.push((lcn + (from - start), to - from));
+            }
+            if start + count <= unit_end {
+                extent_index = index + 1;
+            }
+            index += 1;
+        }
+        let allocated: u64 = pieces.iter().map(|p| p.1).sum();
+
+        let mut stored = Vec::with_capacity((allocated * cluster_size as u64) as usize);
+        let mut read_failed = false;
+        for (lcn, count) in &pieces {
+            match read_clusters(disk, *lcn, *count, cluster_size) {
+                Ok(data) => stored.extend_from_slice(&data),
+                Err(e) => {
+                    eprintln!("Warning: Failed to read compressed clusters at {}: {}", lcn, e);
+                    read_failed = true;
+                    break;
+                }
+            }
+        }
+
+        let unit = if read_failed {
+            damaged_units += 1;
+            vec![0u8; unit_size]
+        } else if allocated == 0 {
+            vec![0u8; unit_size] // Sparse unit
+        } else if allocated >= COMPRESSION_UNIT_CLUSTERS {
+            stored // Stored uncompressed (did not shrink)
+        } else {
+            match lznt1_decompress(&stored, unit_size) {
+                Ok(mut data) => {
+                    data.resize(unit_size, 0);
+                    data
+                }
+                Err(e) => {
+                    eprintln!("Warning: Compression unit at VCN {} is damaged: {}", unit_vcn, e);
+                    damaged_units += 1;
+                    vec![0u8; unit_size]
+                }
+            }
+        };
+
+        let take = (size - output.len() as u64).min(unit.len() as u64) as usize;
+        output.extend_from_slice(&unit[..take]);
+        unit_vcn = unit_end;
+    }
+
+    (output, damaged_units)
+}
+
+// ============================================================================
+// CANONICAL HUFFMAN DECODING (shared by XPRESS and LZX)
+// ============================================================================
+
+/// Canonical Huffman code: shorter codes first, ties broken by symbol value
+struct HuffmanDecoder {
+    counts: [u16; 17],
+    symbols: Vec<u16>,
+    max_length: u32,
+}
+
+impl HuffmanDecoder {
+    fn new(lengths: &[u8], max_length: u32) -> Result<Self, String> {
+        let mut counts = [0u16; 17];
+        for &length in lengths {
+            if length as u32 > max_length {
+                return Err(format!("Huffman code length {} exceeds {}", length, max_length));
+            }
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        // Reject over-subscribed codes; incomplete ones are legal
+        let mut available = 1i64;
+        for &count in &counts[1..=max_length as usize] {
+            available = (available << 1) - count as i64;
+            if available < 0 {
+                return Err("Over-subscribed Huffman code".to_string());
+            }
+        }
+
+        let mut symbols: Vec<u16> = (0..lengths.len() as u16).filter(|&s| lengths[s as usize] > 0).collect();
+        symbols.sort_by_key(|&s| lengths[s as usize]);
+        Ok(HuffmanDecoder { counts, symbols, max_length })
+    }
+
+    /// Decode from the top `max_length` bits of `bits`: (symbol, code length)
+    fn decode(&self, bits: u32) -> Option<(u16, u32)> {
+        let mut first = 0u32;
+        let mut index = 0u32;
+        for length in 1..=self.max_length {
+            let code = bits >> (self.max_length - length);
+            let count = self.counts[length as usize] as u32;
+            if code >= first && code - first < count {
+                return Some((self.symbols[(index + code - first) as usize], length));
+            }
+            index += count;
+            first = (first + count) << 1;
+        }
+        None
+    }
+}
+
+// ============================================================================
+// XPRESS HUFFMAN (MS-XCA "LZ77+Huffman")
+// ============================================================================
+
+/// Decompress one XPRESS Huffman block (WOF chunks are at most 16 KB, so a
+/// single 256-byte code length table covers the whole chunk)
+pub fn xpress_huffman_decompress(input: &[u8], output_size: usize) -> Result<Vec<u8>, String> {
+    if input.len() < 260 {
+        return Err("XPRESS chunk is shorter than its Huffman table".to_string());
+    }
+    let mut lengths = [0u8; 512];
+    for (i, &byte) in input[..256].iter().enumerate() {
+        lengths[2 * i] = byte & 0x0F;
+        lengths[2 * i + 1] = byte >> 4;
+    }
+    let decoder = HuffmanDecoder::new(&lengths, 15)?;
+
+    let read16 = |at: usize| -> u32 {
+        match input.get(at..at + 2) {
+            Some(b) => u16::from_le_bytes([b[0], b[1]]) as u32,
+            None => 0,
+        }
+    };
+
+    let mut output = Vec::with_capacity(output_size);
+    let mut position = 256;
+    let mut next_bits = (read16(position) << 16) | read16(position + 2);
+    position += 4;
+    let mut extra_bits: i32 = 16;
+
+    // Consume `count` bits, pulling in the next 16-bit word when the buffer runs low
+    macro_rules! consume {
+        ($count:expr) => {
+            let count = $count;
+            if count > 0 {
+                next_bits <<= count;
+                extra_bits -= count as i32;
+                if extra_bits < 0 {
+                    next_bits |= read16(position) << (-extra_bits);
+                    position += 2;
+                    extra_bits += 16;
+                }
+            }
+        };
+    }
+
+    while output.len() < output_size {
+        let (symbol, length) = decoder
+            .decode(next_bits >> 17)
+            .ok_or("Invalid XPRESS Huffman code")?;
+        consume!(length);
+
+        if symbol < 256 {
+            output.push(symbol as u8);
+            continue;
+        }
+
+        let symbol = symbol as usize - 256;
+        let mut match_length = symbol & 15;
+        let offset_bits = (symbol >> 4) as u32;
+        if match_length == 15 {
+            match_length = *input.get(position).ok_or("Truncated XPRESS match length")? as usize;
+            position += 1;
+            if match_length == 255 {
+                match_length = read16(position) as usize;
+                position += 2;
+                if match_length == 0 {
+                    match_length = input
+                        .get(position..position + 4)
+                        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+                        .ok_or("Truncated XPRESS match length")?;
+                    position += 4;
+                }
+                if match_length < 15 {
+                    return Err("Invalid XPRESS match length".to_string());
+                }
+                match_length -= 15;
+            }
+            match_length += 15;
+        }
+        match_length += 3;
+
+        let offset = if offset_bits == 0 {
+            1
+        } else {
+            ((next_bits >> (32 - offset_bits)) + (1 << offset_bits)) as usize
+        };
+        consume!(offset_bits);
+
+        if offset > output.len() {
+            return Err(format!("XPRESS match offset {} before the start of the chunk", offset));
+        }
+        let from = output.len() - offset;
+        for i in 0..match_length.min(output_size - output.len()) {
+            output.push(output[from + i]);
+        }
+    }
+
+    Ok(output)
+}
+
+// ============================================================================
+// LZX (WIM / WOF variant: 32 KB window, E8 translation always on)
+// ============================================================================
+
+const LZX_NUM_CHARS: usize = 256;
+const LZX_NUM_OFFSET_SLOTS: usize = 30;
+const LZX_MAIN_SYMBOLS: usize = LZX_NUM_CHARS + 8 * LZX_NUM_OFFSET_SLOTS;
+const LZX_LENGTH_SYMBOLS: usize = 249;
+const LZX_PRECODE_SYMBOLS: usize = 20;
+const LZX_DEFAULT_BLOCK_SIZE: usize = 32768;
+const LZX_E8_FILE_SIZE: i32 = 12_000_000;
+
+/// 16-bit little-endian words, bits consumed most significant first
+struct LzxBitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u64,
+    bits: u32,
+}
+
+impl LzxBitReader<'_> {
+    fn ensure(&mut self, count: u32) {
+        while self.bits < count {
+            // Past the end the stream reads as zeros
+            let word = match self.data.get(self.position..self.position + 2) {
+                Some(b) => u16::from_le_bytes([b[0], b[1]]) as u64,
+                None => 0,
+            };
+            self.position += 2;
+            self.buffer = (self.buffer << 16) | word;
+            self.bits += 16;
+        }
+    }
+
+    fn peek(&mut self, count: u32) -> u32 {
+        self.ensure(count);
+        ((self.buffer >> (self.bits - count)) & ((1u64 << count) - 1)) as u32
+    }
+
+    fn read(&mut self, count: u32) -> u32 {
+        if count == 0 {
+            return 0;
+        }
+        let value = self.peek(count);
+        self.bits -= count;
+        value
+    }
+
+    fn decode(&mut self, code: &HuffmanDecoder) -> Result<u16, String> {
+        let bits = self.peek(16);
+        let (symbol, length) = code.decode(bits).ok_or("Invalid LZX Huffman code")?;
+        self.bits -= length;
+        Ok(symbol)
+    }
+
+    /// Drop buffered bits; if already aligned the next 16 bits are skipped
+    fn align(&mut self) {
+        self.ensure(1);
+        self.bits = 0;
+        self.buffer = 0;
+    }
+
+    fn read_bytes(&mut self, count: usize) -> Result<&[u8], String> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + count)
+            .ok_or("Truncated LZX uncompressed block")?;
+        self.position += count;
+        Ok(bytes)
+    }
+}
+
+/// Read code lengths delta-coded against the previous block's lengths
+fn lzx_read_lengths(reader: &mut LzxBitReader, lengths: &mut [u8]) -> Result<(), String> {
+    let mut precode_lengths = [0u8; LZX_PRECODE_SYMBOLS];
+    for length in precode_lengths.iter_mut() {
+        *length = reader.read(4) as u8;
+    }
+    let precode = HuffmanDecoder::new(&precode_lengths, 16)?;
+
+    let delta = |old: u8, presym: u16| ((old as i32 - presym as i32 + 17) % 17) as u8;
+    let mut i = 0;
+    while i < lengths.len() {
+        let presym = reader.decode(&precode)?;
+        let (run, value) = match presym {
+            0..=16 => (1, delta(lengths[i], presym)),
+            17 => (4 + reader.read(4) as usize, 0),
+            18 => (20 + reader.read(5) as usize, 0),
+            _ => {
+                let run = 4 + reader.read(1) as usize;
+                let presym = reader.decode(&precode)?;
+                if presym > 16 {
+                    return Err("Invalid LZX length run".to_string());
+                }
+                (run, delta(lengths[i], presym))
+            }
+        };
+        let end = (i + run).min(lengths.len());
+        lengths[i..end].fill(value);
+        i = end;
+    }
+    Ok(())
+}
+
+/// Undo the encoder's x86 CALL (E8) target translation
+fn lzx_undo_e8_translation(data: &mut [u8]) {
+    if data.len() <= 10 {
+        return;
+    }
+    let tail = data.len() - 10;
+    let mut i = 0;
+    while i < tail {
+        if data[i] != 0xE8 {
+            i += 1;
+            continue;
+        }
+        let position = i as i32;
+        let absolute = i32::from_le_bytes([data[i + 1], data[i + 2], data[i + 3], data[i + 4]]);
+        let relative = if absolute >= 0 {
+            (absolute < LZX_E8_FILE_SIZE).then(|| absolute - position)
+        } else {
+            (absolute >= -position).then(|| absolute + LZX_E8_FILE_SIZE)
+        };
+        if let Some(relative) = relative {
+            data[i + 1..i + 5].copy_from_slice(&relative.to_le_bytes());
+        }
+        i += 5;
+    }
+}
+
+/// Decompress one independently compressed LZX chunk
+pub fn lzx_decompress(input: &[u8], output_size: usize) -> Result<Vec<u8>, String> {
+    // Offset slot bases and extra bit counts
+    let mut extra_bits = [0u32; LZX_NUM_OFFSET_SLOTS];
+    let mut slot_base = [0u32; LZX_NUM_OFFSET_SLOTS];
+    for slot in 0..LZX_NUM_OFFSET_SLOTS {
+        extra_bits[slot] = if slot < 4 { 0 } else { (slot as u32 / 2 - 1).min(17) };
+        if slot > 0 {
+            slot_base[slot] = slot_base[slot - 1] + (1 << extra_bits[slot - 1]);
+        }
+    }
+
+    let mut reader = LzxBitReader { data: input, position: 0, buffer: 0, bits: 0 };
+    let mut output = Vec::with_capacity(output_size);
+    let mut main_lengths = [0u8; LZX_MAIN_SYMBOLS];
+    let mut length_lengths = [0u8; LZX_LENGTH_SYMBOLS];
+    let mut recent_offsets = [1usize; 3];
+
+    while output.len() < output_size {
+        let block_type = reader.read(3);
+        let block_size = if reader.read(1) == 1 {
+            LZX_DEFAULT_BLOCK_SIZE
+        } else {
+            reader.read(16) as usize
+        };
+        if block_size == 0 {
+            return Err("Empty LZX block".to_string());
+        }
+        let block_end = (output.len() + block_size).min(output_size);
+
+        match block_type {
+            // Verbatim and aligned-offset blocks
+            1 | 2 => {
+                let aligned = if block_type == 2 {
+                    let mut lengths = [0u8; 8];
+                    for length in lengths.iter_mut() {
+                        *length = reader.read(3) as u8;
+                    }
+                    Some(HuffmanDecoder::new(&lengths, 16)?)
+                } else {
+                    None
+                };
+                lzx_read_lengths(&mut reader, &mut main_lengths[..LZX_NUM_CHARS])?;
+                lzx_read_lengths(&mut reader, &mut main_lengths[LZX_NUM_CHARS..])?;
+                lzx_read_lengths(&mut reader, &mut length_lengths)?;
+                let main_code = HuffmanDecoder::new(&main_lengths, 16)?;
+                let length_code = HuffmanDecoder::new(&length_lengths, 16)?;
+
+                while output.len() < block_end {
+                    let symbol = reader.decode(&main_code)? as usize;
+                    if symbol < LZX_NUM_CHARS {
+                        output.push(symbol as u8);
+                        continue;
+                    }
+
+                    let symbol = symbol - LZX_NUM_CHARS;
+                    let mut length = symbol & 7;
+                    if length == 7 {
+                        length += reader.decode(&length_code)? as usize;
+                    }
+                    length += 2;
+
+                    let slot = symbol >> 3;
+                    let offset = if slot < 3 {
+                        // Repeat offset: swap it to the front
+                        recent_offsets.swap(0, slot);
+                        recent_offsets[0]
+                    } else {
+                        let bits = extra_bits[slot];
+                        let mut formatted = slot_base[slot] as usize;
+                        match aligned {
+                            Some(ref aligned) if bits >= 3 => {
+                                formatted += (reader.read(bits - 3) as usize) << 3;
+                                formatted += reader.decode(aligned)? as usize;
+                            }
+                            _ => formatted += reader.read(bits) as usize,
+                        }
+                        let offset = formatted - 2;
+                        recent_offsets[2] = recent_offsets[1];
+                        recent_offsets[1] = recent_offsets[0];
+                        recent_offsets[0] = offset;
+                        offset
+                    };
+
+                    if offset == 0 || offset > output.len() {
+                        return Err(format!("LZX match offset {} outside the chunk", offset));
+                    }
+                    let from = output.len() - offset;
+                    for i in 0..length.min(block_end - output.len()) {
+                        output.push(output[from + i]);
+                    }
+                }
+            }
+            // Uncompressed block: byte aligned, carries the recent offsets
+            3 => {
+                reader.align();
+                for offset in recent_offsets.iter_mut() {
+                    let bytes = reader.read_bytes(4)?;
+                    *offset = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+                }
+                let count = block_end - output.len();
+                output.extend_from_slice(reader.read_bytes(count)?);
+                if block_size % 2 == 1 {
+                    reader.position += 1;
+                }
+            }
+            _ => return Err(format!("Invalid LZX block type {}", block_type)),
+        }
+    }
+
+    lzx_undo_e8_translation(&mut output);
+    Ok(output)
+}
+
+// ============================================================================
+// WOF (WofCompressedData)
+// ============================================================================
+
+/// Stream holding the compressed content of a WOF file
+pub const WOF_STREAM_NAME: &str = "WofCompressedData";
+
+/// Name used in scan results for a WOF compression format
+pub fn wof_format_name(format: u32) -> Option<&'static str> {
+    match format {
+        0 => Some("wof_xpress4k"),
+        1 => Some("wof_lzx"),
+        2 => Some("wof_xpress8k"),
+        3 => Some("wof_xpress16k"),
+        _ => None,
+    }
+}
+
+/// Inverse of `wof_format_name`
+pub fn wof_format_from_name(name: &str) -> Option<u32> {
+    (0..4).find(|&format| wof_format_name(format) == Some(name))
+}
+
+/// Uncompressed size of one chunk for a WOF compression format
+pub fn wof_chunk_size(format: u32) -> Option<u64> {
+    match format {
+        0 => Some(4096),
+        1 => Some(32768),
+        2 => Some(8192),
+        3 => Some(16384),
+        _ => None,
+    }
+}
+
+/// Decompress a `WofCompressedData` stream into `size` bytes. Returns the data
+/// and the number of damaged chunks (zero-filled).
+pub fn decompress_wof(stored: &[u8], format: u32, size: u64) -> Result<(Vec<u8>, u64), String> {
+    let chunk_size = wof_chunk_size(format).ok_or(format!("Unknown WOF compression format {}", format))?;
+    let chunk_count = size.div_ceil(chunk_size) as usize;
+    if chunk_count == 0 {
+        return Ok((Vec::new(), 0));
+    }
+
+    // Offsets of chunks 1..n relative to the end of the table (chunk 0 starts at 0)
+    let entry_size = if size > u32::MAX as u64 { 8 } else { 4 };
+    let table_size = (chunk_count - 1) * entry_size;
+    if stored.len() < table_size {
+        return Err("WofCompressedData is shorter than its chunk table".to_string());
+    }
+    let mut offsets = vec![0u64];
+    for entry in stored[..table_size].chunks_exact(entry_size) {
+        offsets.push(if entry_size == 8 {
+            u64::from_le_bytes(entry.try_into().unwrap())
+        } else {
+            u32::from_le_bytes(entry.try_into().unwrap()) as u64
+        });
+    }
+    let data = &stored[table_size..];
+    offsets.push(data.len() as u64);
+
+    let mut output = Vec::with_capacity(size as usize);
+    let mut damaged_chunks = 0;
+    for chunk in 0..chunk_count {
+        let expected = chunk_size.min(size - chunk as u64 * chunk_size) as usize;
+        let (start, end) = (offsets[chunk] as usize, offsets[chunk + 1] as usize);
+
+        let decoded = match data.get(start..end) {
+            None => Err(format!("Chunk {} lies outside the stream", chunk)),
+            Some(compressed) if compressed.len() == expected => Ok(compressed.to_vec()),
+            Some(compressed) if format == 1 => lzx_decompress(compressed, expected),
+            Some(compressed) => xpress_huffman_decompress(compressed, expected),
+        };
+        match decoded {
+            Ok(mut bytes) => {
+                bytes.resize(expected, 0);
+                output.extend_from_slice(&bytes);
+            }
+            Err(e) => {
+                eprintln!("Warning: WOF chunk {} is damaged: {}", chunk, e);
+                damaged_chunks += 1;
+                output.resize(output.len() + expected, 0);
+            }
+        }
+    }
+
+    Ok((output, damaged_chunks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lznt1_back_reference_and_raw_chunk() {
+        // "abc" as literals, then a 15-byte copy from 3 bytes back
+        let compressed = [0x05, 0xB0, 0x08, b'a', b'b', b'c', 0x0C, 0x20];
+        assert_eq!(lznt1_decompress(&compressed, 4096).unwrap(), b"abcabcabcabcabcabc");
+
+        // A full uncompressed chunk followed by a compressed one
+        let mut input = vec![0xFF, 0x3F];
+        input.extend(std::iter::repeat_n(b'x', 4096));
+        input.extend_from_slice(&compressed);
+        let output = lznt1_decompress(&input, 8192).unwrap();
+        assert_eq!(output.len(), 4096 + 18);
+        assert!(output[..4096].iter().all(|&b| b == b'x'));
+        assert_eq!(&output[4096..], b"abcabcabcabcabcabc");
+    }
+
+    #[test]
+    fn test_lznt1_rejects_reference_before_chunk() {
+        let compressed = [0x03, 0xB0, 0x02, b'a', 0x00, 0x20];
+        assert!(lznt1_decompress(&compressed, 4096).is_err());
+    }
+
+    /// XPRESS chunk: 'a' = 0, 'b' = 10, symbol 256 (length 3, offset 1) = 11
+    fn xpress_chunk() -> Vec<u8> {
+        let mut chunk = vec![0u8; 256];
+        chunk[0x61 / 2] = 0x10;
+        chunk[0x62 / 2] = 0x02;
+        chunk[256 / 2] = 0x02;
+        // Bits 0 10 11 0: a, b, match, a
+        chunk.extend_from_slice(&[0x00, 0x58, 0x00, 0x00]);
+        chunk
+    }
+
+    #[test]
+    fn test_xpress_huffman_literals_and_match() {
+        assert_eq!(xpress_huffman_decompress(&xpress_chunk(), 6).unwrap(), b"abbbba");
+        assert!(xpress_huffman_decompress(&[0u8; 100], 6).is_err());
+    }
+
+    #[test]
+    fn test_lzx_uncompressed_block_undoes_e8_translation() {
+        // Block type 3, explicit size 16: 011 0 0000000000010000, then 12 bits of padding
+        let mut chunk = vec![0x01, 0x60, 0x00, 0x00];
+        for _ in 0..3 {
+            chunk.extend_from_slice(&1u32.to_le_bytes());
+        }
+        let mut data = vec![0xE8, 5, 0, 0, 0, 0xE8, 100, 0, 0, 0];
+        data.extend_from_slice(b"tail..");
+        chunk.extend_from_slice(&data);
+
+        let output = lzx_decompress(&chunk, 16).unwrap();
+        // Absolute targets become relative to each call's position
+        assert_eq!(&output[..5], &[0xE8, 5, 0, 0, 0]);
+        assert_eq!(&output[5..10], &[0xE8, 95, 0, 0, 0]);
+        assert_eq!(&output[10..], b"tail..");
+    }
+
+    #[test]
+    fn test_wof_chunk_table_with_raw_and_damaged_chunks() {
+        // Three XPRESS4K chunks: stored raw, garbage, and a compressed 6-byte tail
+        let mut stored = Vec::new();
+        stored.extend_from_slice(&4096u32.to_le_bytes());
+        stored.extend_from_slice(&(4096 + 8u32).to_le_bytes());
+        stored.extend(std::iter::repeat_n(b'r', 4096));
+        stored.extend_from_slice(&[0xFF; 8]);
+        stored.extend_from_slice(&xpress_chunk());
+
+        let (data, damaged) = decompress_wof(&stored, 0, 2 * 4096 + 6).unwrap();
+        assert_eq!(data.len(), 2 * 4096 + 6);
+        assert!(data[..4096].iter().all(|&b| b == b'r'));
+        assert!(data[4096..8192].iter().all(|&b| b == 0));
+        assert_eq!(&data[8192..], b"abbbba");
+        assert_eq!(damaged, 1);
+    }
+}
@@ -0,0 +1,333 @@
+//! NTFS Index Module
+//! Parses $I30 directory indexes ($INDEX_ROOT / $INDEX_ALLOCATION) and
+//! carves stale entries of deleted files from INDX node slack
+//!
+//! Every index entry carries a copy of the file's $FILE_NAME attribute: name,
+//! parent reference, timestamps and sizes. Removing an entry shifts the rest
+//! of the node down over it, so stale copies linger past the node's used
+//! length until that space is written again - often long after the file's
+//! MFT record has been reused.
+
+use crate::disk_reader::{read_runs_range, DiskReader};
+use crate::ntfs_parser::{apply_fixup, record_attributes};
+
+pub const ATTRIBUTE_INDEX_ROOT: u32 = 0x90;
+pub const ATTRIBUTE_INDEX_ALLOCATION: u32 = 0xA0;
+pub const ATTRIBUTE_BITMAP: u32 = 0xB0;
+const DIRECTORY_INDEX_NAME: &str = "$I30";
+const INDEX_RECORD_SIGNATURE: &[u8] = b"INDX";
+
+// Index entry flags
+const INDEX_ENTRY_LAST: u16 = 0x02;
+
+/// Offsets in an INDX record / $INDEX_ROOT of the node header
+const INDX_NODE_HEADER: usize = 0x18;
+const ROOT_NODE_HEADER: usize = 0x10;
+
+/// $FILE_NAME key bytes before the name
+const FILE_NAME_KEY_HEADER: usize = 66;
+const FILE_NAMESPACE_DOS: u8 = 2;
+const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x10000000;
+
+/// Timestamps accepted for carved entries (FILETIME 1980-01-01 .. 2100-01-01)
+const FILETIME_MIN: i64 = 119_600_064_000_000_000;
+const FILETIME_MAX: i64 = 157_469_184_000_000_000;
+
+/// Upper bound on the $INDEX_ALLOCATION read for a single directory
+const MAX_INDEX_ALLOCATION: u64 = 64 * 1024 * 1024;
+
+/// One $I30 entry: a directory's copy of a file's $FILE_NAME
+#[derive(Debug, Clone)]
+pub struct IndexEntry {
+    pub file_reference: u64,   // MFT record number (low 48 bits) + sequence number
+    pub parent_reference: u64,
+    pub name: String,
+    pub namespace: u8,
+    pub created_time: i64,     // Raw FILETIMEs
+    pub modified_time: i64,
+    pub mft_modified_time: i64,
+    pub accessed_time: i64,
+    pub allocated_size: u64,
+    pub real_size: u64,
+    pub file_attributes: u32,
+}
+
+impl IndexEntry {
+    pub fn record_number(&self) -> u64 {
+        self.file_reference & 0x0000_FFFF_FFFF_FFFF
+    }
+
+    pub fn sequence_number(&self) -> u16 {
+        (self.file_reference >> 48) as u16
+    }
+
+    pub fn is_directory(&self) -> bool {
+        self.file_attributes & FILE_ATTRIBUTE_DIRECTORY != 0
+    }
+}
+
+/// Live entries of a directory index plus stale entries carved from slack
+#[derive(Debug, Default)]
+pub struct DirectoryIndex {
+    pub entries: Vec<IndexEntry>,
+    pub slack_entries: Vec<IndexEntry>,
+}
+
+fn read_u16(data: &[u8], at: usize) -> usize {
+    u16::from_le_bytes([data[at], data[at + 1]]) as usize
+}
+
+fn read_u32(data: &[u8], at: usize) -> usize {
+    u32::from_le_bytes(data[at..at + 4].try_into().unwrap()) as usize
+}
+
+fn read_u64(data: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(data[at..at + 8].try_into().unwrap())
+}
+
+/// Parse an index entry at `offset` (header + $FILE_NAME key)
+fn parse_entry(node: &[u8], offset: usize) -> Option<IndexEntry> {
+    let header = node.get(offset..offset + 16)?;
+    let key_length = read_u16(header, 10);
+    let key = node.get(offset + 16..offset + 16 + key_length)?;
+    if key.len() < FILE_NAME_KEY_HEADER {
+        return None;
+    }
+
+    let name_length = key[64] as usize;
+    let namespace = key[65];
+    if name_length == 0 || namespace > 3 || key.len() < FILE_NAME_KEY_HEADER + name_length * 2 {
+        return None;
+    }
+    let units: Vec<u16> = key[FILE_NAME_KEY_HEADER..FILE_NAME_KEY_HEADER + name_length * 2]
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    let name = String::from_utf16(&units).ok()?;
+
+    Some(IndexEntry {
+        file_reference: read_u64(header, 0),
+        parent_reference: read_u64(key, 0),
+        name,
+        namespace,
+        created_time: read_u64(key, 8) as i64,
+        modified_time: read_u64(key, 16) as i64,
+        mft_modified_time: read_u64(key, 24) as i64,
+        accessed_time: read_u64(key, 32) as i64,
+        allocated_size: read_u64(key, 40),
+        real_size: read_u64(key, 48),
+        file_attributes: read_u32(key, 56) as u32,
+    })
+}
+
+/// Walk the live entries of one index node between `start` and `end`
+fn node_entries(node: &[u8], start: usize, end: usize) -> Vec<IndexEntry> {
+    let mut entries = Vec::new();
+    let end = end.min(node.len());
+    let mut offset = start;
+
+    while offset + 16 <= end {
+        let length = read_u16(node, offset + 8);
+        let flags = read_u16(node, offset + 12) as u16;
+        if length < 16 || offset + length > end || flags & INDEX_ENTRY_LAST != 0 {
+            break; // The terminating entry carries no key
+        }
+        if let Some(entry) = parse_entry(&node[..offset + length], offset) {
+            entries.push(entry);
+        }
+        offset += length;
+    }
+
+    entries
+}
+
+/// Whether a carved entry looks like a genuine entry of `directory`
+fn plausible_slack_entry(entry: &IndexEntry, directory: u64) -> bool {
+    let times = [entry.created_time, entry.modified_time, entry.mft_modified_time, entry.accessed_time];
+    entry.parent_reference & 0x0000_FFFF_FFFF_FFFF == directory
+        && times.iter().all(|t| (FILETIME_MIN..FILETIME_MAX).contains(t))
+        && entry.real_size <= entry.allocated_size.max(entry.real_size.next_multiple_of(8))
+        && !entry.name.chars().any(|c| c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+}
+
+/// Carve stale entries from the unused space of a node, starting at `start`.
+/// Entries are 8-byte aligned; every candidate must name `directory` as its
+/// parent, which keeps random slack bytes from passing as entries.
+fn carve_slack(node: &[u8], start: usize, directory: u64) -> Vec<IndexEntry> {
+    let mut entries = Vec::new();
+    let mut offset = start.next_multiple_of(8);
+
+    while offset + 16 + FILE_NAME_KEY_HEADER <= node.len() {
+        let length = read_u16(node, offset + 8);
+        let key_length = read_u16(node, offset + 10);
+        let well_formed = length.is_multiple_of(8) && length >= 16 + key_length && offset + length <= node.len();
+
+        match parse_entry(node, offset).filter(|e| well_formed && plausible_slack_entry(e, directory)) {
+            Some(entry) => {
+                entries.push(entry);
+                offset += length;
+            }
+            None => offset += 8,
+        }
+    }
+
+    entries
+}
+
+/// Parse one INDX record of $INDEX_ALLOCATION. Returns the live entries and
+/// the entries carved from slack; nodes marked free in the $I30 bitmap are
+/// slack from their first entry on.
+pub fn parse_index_record(buffer: &[u8], directory: u64, in_use: bool) -> Option<(Vec<IndexEntry>, Vec<IndexEntry>)> {
+    if buffer.len() < INDX_NODE_HEADER + 16 || &buffer[0..4] != INDEX_RECORD_SIGNATURE {
+        return None;
+    }
+    let mut node = buffer.to_vec();
+    apply_fixup(&mut node, read_u16(buffer, 4), read_u16(buffer, 6));
+
+    // Node header offsets are relative to the header itself
+    let entries_start = INDX_NODE_HEADER + read_u32(&node, INDX_NODE_HEADER);
+    let used_end = (INDX_NODE_HEADER + read_u32(&node, INDX_NODE_HEADER + 4)).min(node.len());
+
+    if in_use {
+        Some((node_entries(&node, entries_start, used_end), carve_slack(&node, used_end, directory)))
+    } else {
+        Some((Vec::new(), carve_slack(&node, entries_start, directory)))
+    }
+}
+
+/// Read the $I30 index of a directory from its fixed-up MFT record.
+/// `index_record_size` (from the boot sector) applies when $INDEX_ROOT is unreadable.
+pub fn read_directory_index(disk: &mut DiskReader, record: &[u8], directory: u64, cluster_size: u32, index_record_size: u32) -> DirectoryIndex {
+    let attributes = record_attributes(record);
+    let i30 = |attr_type: u32| attributes.iter().find(|a| a.attr_type == attr_type && a.name == DIRECTORY_INDEX_NAME);
+    let mut index = DirectoryIndex::default();
+
+    // $INDEX_ROOT: index parameters, then the root node
+    let mut record_size = index_record_size as usize;
+    if let Some(root) = i30(ATTRIBUTE_INDEX_ROOT).and_then(|a| a.resident_content()) {
+        if root.len() >= ROOT_NODE_HEADER + 16 {
+            let size = read_u32(root, 8);
+            if size.is_power_of_two() && (512..=65536).contains(&size) {
+                record_size = size;
+            }
+            let start = ROOT_NODE_HEADER + read_u32(root, ROOT_NODE_HEADER);
+            let end = ROOT_NODE_HEADER + read_u32(root, ROOT_NODE_HEADER + 4);
+            index.entries = node_entries(root, start, end);
+        }
+    }
+
+    let Some(allocation) = i30(ATTRIBUTE_INDEX_ALLOCATION).and_then(|a| a.segment()) else {
+        return index;
+    };
+
+    // Which INDX records are allocated; without a bitmap treat all as live
+    let bitmap = i30(ATTRIBUTE_BITMAP).and_then(|a| match a.resident_content() {
+        Some(content) => Some(content.to_vec()),
+        None => a.segment().and_then(|s| read_runs_range(disk, &s.runs, cluster_size, 0, s.sizes.real as usize).ok()),
+    });
+
+    let nodes = allocation.sizes.real.min(MAX_INDEX_ALLOCATION) / record_size as u64;
+    let mut slack = Vec::new();
+    for node in 0..nodes {
+        let in_use = bitmap.as_ref().is_none_or(|b| b.get(node as usize / 8).is_some_and(|&bits| bits & (1 << (node % 8)) != 0));
+        let buffer = match read_runs_range(disk, &allocation.runs, cluster_size, node * record_size as u64, record_size) {
+            Ok(buffer) => buffer,
+            Err(e) => {
+                eprintln!("Warning: Failed to read INDX record {} of directory {}: {}", node, directory, e);
+                continue;
+            }
+        };
+        if let Some((live, stale)) = parse_index_record(&buffer, directory, in_use) {
+            index.entries.extend(live);
+            slack.extend(stale);
+        }
+    }
+
+    // Stale copies of entries that are still live are just shifted leftovers;
+    // of a Win32/DOS name pair keep the long name
+    slack.sort_by(|a, b| (a.file_reference, &a.name).cmp(&(b.file_reference, &b.name)));
+    slack.dedup_by(|a, b| a.file_reference == b.file_reference && a.name == b.name);
+    let long_names: Vec<u64> = slack.iter().filter(|e| e.namespace != FILE_NAMESPACE_DOS).map(|e| e.file_reference).collect();
+    slack.retain(|s| s.namespace != FILE_NAMESPACE_DOS || !long_names.contains(&s.file_reference));
+    slack.retain(|s| !index.entries.iter().any(|e| e.record_number() == s.record_number() && e.name == s.name));
+    index.slack_entries = slack;
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FT_2020: i64 = 132_224_352_000_000_000;
+
+    /// Index entry for `name` (record `record`, sequence 1) in directory 5
+    fn entry(name: &str, record: u64, flags: u16) -> Vec<u8> {
+        let units: Vec<u8> = name.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        let key_length = FILE_NAME_KEY_HEADER + units.len();
+        let length = (16 + key_length).next_multiple_of(8);
+        let mut e = vec![0u8; length];
+        e[0..8].copy_from_slice(&(record | 1 << 48).to_le_bytes());
+        e[8..10].copy_from_slice(&(length as u16).to_le_bytes());
+        e[10..12].copy_from_slice(&(key_length as u16).to_le_bytes());
+        e[12..14].copy_from_slice(&flags.to_le_bytes());
+        let key = &mut e[16..];
+        key[0..8].copy_from_slice(&(5u64 | 5 << 48).to_le_bytes());
+        for i in 0..4 {
+            key[8 + i * 8..16 + i * 8].copy_from_slice(&FT_2020.to_le_bytes());
+        }
+        key[40..48].copy_from_slice(&4096u64.to_le_bytes());
+        key[48..56].copy_from_slice(&1234u64.to_le_bytes());
+        key[64] = name.encode_utf16().count() as u8;
+        key[65] = 1;
+        key[FILE_NAME_KEY_HEADER..FILE_NAME_KEY_HEADER + units.len()].copy_from_slice(&units);
+        e
+    }
+
+    fn indx(live: &[Vec<u8>], stale: &[Vec<u8>]) -> Vec<u8> {
+        let mut node = vec![0u8; 4096];
+        node[0..4].copy_from_slice(INDEX_RECORD_SIGNATURE);
+        node[4..6].copy_from_slice(&0x28u16.to_le_bytes());
+        node[6..8].copy_from_slice(&9u16.to_le_bytes());
+        let mut offset = 0x40;
+        for e in live.iter().chain(std::iter::once(&entry_end())) {
+            node[offset..offset + e.len()].copy_from_slice(e);
+            offset += e.len();
+        }
+        node[INDX_NODE_HEADER..INDX_NODE_HEADER + 4].copy_from_slice(&((0x40 - INDX_NODE_HEADER) as u32).to_le_bytes());
+        node[INDX_NODE_HEADER + 4..INDX_NODE_HEADER + 8].copy_from_slice(&((offset - INDX_NODE_HEADER) as u32).to_le_bytes());
+        for e in stale {
+            node[offset..offset + e.len()].copy_from_slice(e);
+            offset += e.len();
+        }
+        node
+    }
+
+    fn entry_end() -> Vec<u8> {
+        let mut e = vec![0u8; 16];
+        e[8] = 16;
+        e[12] = INDEX_ENTRY_LAST as u8;
+        e
+    }
+
+    #[test]
+    fn test_index_record_live_and_slack_entries() {
+        let mut garbage = entry("noise.bin", 77, 0);
+        garbage[16..24].copy_from_slice(&(9u64).to_le_bytes()); // Parent is another directory
+        let node = indx(&[entry("keep.txt", 40, 0)], &[entry("gone.docx", 41, 0), garbage]);
+
+        let (live, slack) = parse_index_record(&node, 5, true).unwrap();
+        assert_eq!(live.len(), 1);
+        assert_eq!(live[0].name, "keep.txt");
+        assert_eq!(slack.len(), 1);
+        assert_eq!(slack[0].name, "gone.docx");
+        assert_eq!(slack[0].record_number(), 41);
+        assert_eq!(slack[0].sequence_number(), 1);
+        assert_eq!(slack[0].real_size, 1234);
+
+        // A node the bitmap marks free is slack from the first entry on
+        let (live, slack) = parse_index_record(&node, 5, false).unwrap();
+        assert!(live.is_empty());
+        let names: Vec<&str> = slack.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["keep.txt", "gone.docx"]);
+    }
+}
@@ -12,8 +12,21 @@ const ATTRIBUTE_END: u32 = 0xFFFFFFFF;
 
 // Attribute Types
 const ATTRIBUTE_STANDARD_INFORMATION: u32 = 0x10;
+pub const ATTRIBUTE_ATTRIBUTE_LIST: u32 = 0x20;
 const ATTRIBUTE_FILE_NAME: u32 = 0x30;
-const ATTRIBUTE_DATA: u32 = 0x80;
+pub const ATTRIBUTE_DATA: u32 = 0x80;
+const ATTRIBUTE_REPARSE_POINT: u32 = 0xC0;
+
+// Attribute header flags
+const ATTRIBUTE_FLAG_COMPRESSED: u16 = 0x0001;
+
+// Reparse tags
+/// IO_REPARSE_TAG_WOF: file content lives in a compressed WofCompressedData stream
+pub const REPARSE_TAG_WOF: u32 = 0x80000017;
+const WOF_PROVIDER_FILE: u32 = 2;
+
+// File references: 48-bit record number, 16-bit sequence number
+const FILE_REFERENCE_RECORD_MASK: u64 = 0x0000_FFFF_FFFF_FFFF;
 
 // File Attribute Flags
 const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x10000000;
@@ -21,18 +34,167 @@ const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x10000000;
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MftEntry {
     pub record_number: u64,
+    pub sequence_number: u16, // Bumped each time the record is freed
     pub file_name: String,
     pub parent_record: u64,
-    pub file_size: u64,
-    pub allocated_size: u64,
+    pub parent_sequence: u16, // Parent directory's sequence number when the name was created
+    pub file_size: u64,        // Real size (end of file)
+    pub allocated_size: u64,   // Clusters reserved for $DATA, in bytes
+    pub initialized_size: u64, // Valid data length: bytes past it read as zeros
     pub created_time: i64,
     pub modified_time: i64,
     pub accessed_time: i64,
+    pub si_times: MacbTimes,   // $STANDARD_INFORMATION times at full precision
+    pub fn_times: MacbTimes,   // Times of the $FILE_NAME the name was taken from
+    pub file_names: Vec<FileNameAttr>, // Every $FILE_NAME: hard links and DOS short names
     pub is_deleted: bool,
     pub is_directory: bool,
     pub is_in_use: bool,
     pub data_runs: Vec<DataRun>,
+    pub resident_data: Option<Vec<u8>>, // Unnamed $DATA content stored in the record itself
+    pub is_torn: bool,                  // A sector failed the update sequence check
     pub extension: String,
+    pub attribute_list: Vec<AttributeListEntry>, // Resident $ATTRIBUTE_LIST entries
+    pub attribute_list_runs: Vec<DataRun>,       // Non-resident $ATTRIBUTE_LIST location
+    pub attribute_list_size: u64,
+    pub streams: Vec<DataStream>, // Named $DATA attributes (alternate data streams)
+    pub is_compressed: bool,      // $DATA uses NTFS (LZNT1) compression
+    pub reparse_tag: u32,         // $REPARSE_POINT tag, 0 if none
+    pub wof_format: Option<u32>,  // WOF file provider compression format
+}
+
+/// MACB timestamps of a $STANDARD_INFORMATION or $FILE_NAME attribute, kept as raw
+/// FILETIMEs (100 ns ticks since 1601) and serialized as ISO-8601 strings
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(default)]
+pub struct MacbTimes {
+    #[serde(with = "filetime_iso8601")]
+    pub modified: i64,
+    #[serde(with = "filetime_iso8601")]
+    pub accessed: i64,
+    #[serde(with = "filetime_iso8601")]
+    pub mft_modified: i64, // Last change to the MFT record itself
+    #[serde(with = "filetime_iso8601")]
+    pub created: i64,
+}
+
+impl MacbTimes {
+    /// Read four FILETIMEs in on-disk order: created, modified, MFT modified, accessed
+    fn from_bytes(data: &[u8]) -> Option<Self> {
+        let time = |i: usize| data.get(i * 8..i * 8 + 8).map(|b| i64::from_le_bytes(b.try_into().unwrap()));
+        Some(MacbTimes {
+            created: time(0)?,
+            modified: time(1)?,
+            mft_modified: time(2)?,
+            accessed: time(3)?,
+        })
+    }
+    
+    fn named(&self) -> [(&'static str, i64); 4] {
+        [
+            ("modified", self.modified),
+            ("accessed", self.accessed),
+            ("mft_modified", self.mft_modified),
+            ("created", self.created),
+        ]
+    }
+}
+
+/// $FILE_NAME namespace: the naming rules a name was created under
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum FileNamespace {
+    Posix,       // Case-sensitive, any character but '/' and NUL
+    Win32,
+    Dos,         // 8.3 short name paired with a Win32 name
+    Win32AndDos, // Long name that is also a valid 8.3 name
+}
+
+impl FileNamespace {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => FileNamespace::Posix,
+            2 => FileNamespace::Dos,
+            3 => FileNamespace::Win32AndDos,
+            _ => FileNamespace::Win32,
+        }
+    }
+    
+    /// Order of preference for the displayed name: Win32 first, DOS short names last
+    fn display_rank(self) -> u8 {
+        match self {
+            FileNamespace::Win32 | FileNamespace::Win32AndDos => 0,
+            FileNamespace::Posix => 1,
+            FileNamespace::Dos => 2,
+        }
+    }
+}
+
+/// One $FILE_NAME attribute: the file's name within one directory
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FileNameAttr {
+    pub name: String,
+    pub namespace: FileNamespace,
+    pub parent_record: u64,
+    pub parent_sequence: u16,
+    pub real_size: u64,
+    pub allocated_size: u64,
+    pub times: MacbTimes,
+}
+
+/// All eight timestamps of a file, with any signs that they were tampered with
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct FileTimestamps {
+    pub standard_information: MacbTimes,
+    pub file_name: MacbTimes,
+    pub timestomp_indicators: Vec<String>, // e.g. "si_created_before_fn", "si_zero_subseconds"
+}
+
+impl FileTimestamps {
+    pub fn new(standard_information: MacbTimes, file_name: MacbTimes) -> Self {
+        let mut timestomp_indicators = Vec::new();
+        
+        // Windows copies $FILE_NAME times from $STANDARD_INFORMATION, so $SI never
+        // legitimately predates them; user-mode tools can only rewrite $SI
+        for ((field, si), (_, fn_time)) in standard_information.named().into_iter().zip(file_name.named()) {
+            if si > 0 && fn_time > 0 && si < fn_time {
+                timestomp_indicators.push(format!("si_{}_before_fn", field));
+            }
+        }
+        // Many timestomping tools set whole seconds only
+        if standard_information.named().iter().any(|&(_, si)| si > 0 && si % 10_000_000 == 0) {
+            timestomp_indicators.push("si_zero_subseconds".to_string());
+        }
+        
+        FileTimestamps { standard_information, file_name, timestomp_indicators }
+    }
+}
+
+impl MftEntry {
+    pub fn timestamps(&self) -> FileTimestamps {
+        FileTimestamps::new(self.si_times, self.fn_times)
+    }
+    
+    /// Each distinct (directory, name) the file is linked under; DOS short names
+    /// only alias a long name in the same directory and are left out
+    pub fn hard_links(&self) -> Vec<&FileNameAttr> {
+        let mut links: Vec<&FileNameAttr> = Vec::new();
+        for name in self.file_names.iter().filter(|n| n.namespace != FileNamespace::Dos) {
+            if !links.iter().any(|l| l.parent_record == name.parent_record && l.name == name.name) {
+                links.push(name);
+            }
+        }
+        links
+    }
+}
+
+/// A named $DATA attribute: Zone.Identifier, resource forks, app payloads
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DataStream {
+    pub name: String,
+    pub size: u64,
+    pub initialized_size: u64,
+    pub resident_data: Option<Vec<u8>>,
+    pub data_runs: Vec<DataRun>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -41,16 +203,178 @@ pub struct DataRun {
     pub cluster_count: u64,
 }
 
+/// One $ATTRIBUTE_LIST entry: which MFT record holds (part of) an attribute
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AttributeListEntry {
+    pub attr_type: u32,
+    pub name: String,
+    pub starting_vcn: u64,
+    pub record_number: u64,
+    pub sequence_number: u16,
+}
+
+/// The slice of a non-resident attribute's run list held by a single MFT record
+#[derive(Debug, Clone)]
+pub struct AttributeSegment {
+    pub starting_vcn: u64,
+    pub last_vcn: u64,
+    pub sizes: AttributeSizes, // Only meaningful in the segment starting at VCN 0
+    pub runs: Vec<DataRun>,
+}
+
+/// Sizes from a $DATA attribute header (all equal for resident data)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AttributeSizes {
+    pub allocated: u64,
+    pub real: u64,
+    pub initialized: u64,
+}
+
+/// An attribute as laid out in a (fixed-up) MFT record
+pub struct RawAttribute<'a> {
+    pub attr_type: u32,
+    pub name: String,
+    pub non_resident: bool,
+    pub flags: u16,
+    pub data: &'a [u8], // Whole attribute, header included
+}
+
+impl RawAttribute<'_> {
+    /// Resident attribute value
+    pub fn resident_content(&self) -> Option<&[u8]> {
+        if self.non_resident || self.data.len() < 24 {
+            return None;
+        }
+        let length = u32::from_le_bytes([self.data[16], self.data[17], self.data[18], self.data[19]]) as usize;
+        let offset = u16::from_le_bytes([self.data[20], self.data[21]]) as usize;
+        self.data.get(offset..offset.checked_add(length)?)
+    }
+    
+    /// Run list segment of a non-resident attribute
+    pub fn segment(&self) -> Option<AttributeSegment> {
+        if !self.non_resident || self.data.len() < 64 {
+            return None;
+        }
+        let read_u64 = |at: usize| u64::from_le_bytes(self.data[at..at + 8].try_into().unwrap());
+        let runs_offset = u16::from_le_bytes([self.data[32], self.data[33]]) as usize;
+        Some(AttributeSegment {
+            starting_vcn: read_u64(16),
+            last_vcn: read_u64(24),
+            sizes: AttributeSizes {
+                allocated: read_u64(40),
+                real: read_u64(48),
+                initialized: read_u64(56),
+            },
+            runs: parse_data_runs(self.data.get(runs_offset..)?),
+        })
+    }
+}
+
+/// $Bitmap (MFT record 6): one bit per cluster, set while it is allocated.
+/// Clusters past the end of the bitmap count as allocated.
+#[derive(Debug, Default)]
+pub struct ClusterBitmap {
+    bits: Vec<u8>,
+}
+
+impl ClusterBitmap {
+    pub fn new(bits: Vec<u8>) -> Self {
+        ClusterBitmap { bits }
+    }
+    
+    pub fn total_clusters(&self) -> u64 {
+        self.bits.len() as u64 * 8
+    }
+    
+    pub fn is_allocated(&self, lcn: u64) -> bool {
+        self.bits.get((lcn / 8) as usize).is_none_or(|byte| byte & (1 << (lcn % 8)) != 0)
+    }
+    
+    /// Number of allocated clusters in `lcn .. lcn + count`
+    pub fn allocated_count(&self, lcn: u64, count: u64) -> u64 {
+        let end = lcn.saturating_add(count);
+        let mut allocated = end.saturating_sub(self.total_clusters().max(lcn));
+        let mut cluster = lcn;
+        let end = end.min(self.total_clusters());
+        while cluster < end {
+            if cluster.is_multiple_of(8) && end - cluster >= 8 {
+                allocated += self.bits[(cluster / 8) as usize].count_ones() as u64;
+                cluster += 8;
+            } else {
+                allocated += self.is_allocated(cluster) as u64;
+                cluster += 1;
+            }
+        }
+        allocated
+    }
+    
+    /// Unallocated extents as (first cluster, cluster count)
+    pub fn free_extents(&self) -> Vec<(u64, u64)> {
+        let mut extents = Vec::new();
+        let mut start = None;
+        let mut lcn = 0;
+        while lcn < self.total_clusters() {
+            // Whole bytes of allocated clusters are skipped at once
+            if start.is_none() && lcn.is_multiple_of(8) && self.bits[(lcn / 8) as usize] == 0xFF {
+                lcn += 8;
+                continue;
+            }
+            match (self.is_allocated(lcn), start) {
+                (false, None) => start = Some(lcn),
+                (true, Some(first)) => {
+                    extents.push((first, lcn - first));
+                    start = None;
+                }
+                _ => {}
+            }
+            lcn += 1;
+        }
+        if let Some(first) = start {
+            extents.push((first, self.total_clusters() - first));
+        }
+        extents
+    }
+}
+
 #[derive(Debug)]
 pub struct NtfsBootSector {
     pub bytes_per_sector: u16,
-    pub sectors_per_cluster: u8,
+    pub sectors_per_cluster: u32,
+    pub total_sectors: u64,       // Sectors in the volume; the backup boot sector sits just past them
     pub mft_cluster: u64,
+    pub mft_mirror_cluster: u64,  // $MFTMirr: copies of records 0-3
     pub mft_record_size: u32,
+    pub index_record_size: u32,   // INDX buffer size of directory indexes
+    pub volume_serial: u64,
     pub cluster_size: u32,
 }
 
-/// Parse NTFS boot sector to get MFT location
+/// Largest NTFS cluster size (Windows 10 1709+)
+const MAX_CLUSTER_SIZE: u32 = 2 * 1024 * 1024;
+
+/// Sectors per cluster byte: a count up to 128, or 2^(256 - value) sectors
+/// for the 0xF4-0xFF encoding used by clusters of 128 KiB and up
+fn decode_sectors_per_cluster(raw: u8) -> Option<u32> {
+    let sectors = match raw {
+        1..=0x80 => raw as u32,
+        0xF4..=0xFF => 1u32 << (256 - raw as u32),
+        _ => return None,
+    };
+    sectors.is_power_of_two().then_some(sectors)
+}
+
+/// Clusters per MFT/index record byte: clusters when positive, otherwise 2^-value bytes
+fn decode_record_size(raw: i8, cluster_size: u32) -> Option<u32> {
+    let size = match raw {
+        1..=i8::MAX => (raw as u32).checked_mul(cluster_size)?,
+        -31..=-1 => 1u32 << -(raw as i32),
+        _ => return None,
+    };
+    (size.is_power_of_two() && (256..=65536).contains(&size)).then_some(size)
+}
+
+/// Parse NTFS boot sector to get MFT location. Fields outside what NTFS can
+/// produce (a corrupted or foreign sector) reject the whole sector.
 pub fn parse_boot_sector(data: &[u8]) -> Option<NtfsBootSector> {
     if data.len() < 512 {
         return None;
@@ -66,32 +390,52 @@ pub fn parse_boot_sector(data: &[u8]) -> Option<NtfsBootSector> {
     // Bytes per sector at offset 0x0B
     cursor.seek(SeekFrom::Start(0x0B)).ok()?;
     let bytes_per_sector = cursor.read_u16::<LittleEndian>().ok()?;
+    if !bytes_per_sector.is_power_of_two() || !(512..=4096).contains(&bytes_per_sector) {
+        return None;
+    }
     
     // Sectors per cluster at offset 0x0D
     cursor.seek(SeekFrom::Start(0x0D)).ok()?;
-    let sectors_per_cluster = cursor.read_u8().ok()?;
+    let sectors_per_cluster = decode_sectors_per_cluster(cursor.read_u8().ok()?)?;
+    let cluster_size = (bytes_per_sector as u32).checked_mul(sectors_per_cluster)
+        .filter(|&size| size <= MAX_CLUSTER_SIZE)?;
     
-    // MFT cluster at offset 0x30
+    // Total sectors at offset 0x28
+    cursor.seek(SeekFrom::Start(0x28)).ok()?;
+    let total_sectors = cursor.read_u64::<LittleEndian>().ok()?;
+    let total_clusters = total_sectors / sectors_per_cluster as u64;
+    if total_clusters == 0 {
+        return None;
+    }
+    
+    // MFT cluster at offset 0x30, $MFTMirr cluster at 0x38; both inside the
+    // volume and past the boot sector
     cursor.seek(SeekFrom::Start(0x30)).ok()?;
     let mft_cluster = cursor.read_u64::<LittleEndian>().ok()?;
+    let mft_mirror_cluster = cursor.read_u64::<LittleEndian>().ok()?;
+    if !(1..total_clusters).contains(&mft_cluster) || !(1..total_clusters).contains(&mft_mirror_cluster) {
+        return None;
+    }
     
-    // MFT record size at offset 0x40
+    // MFT record size at offset 0x40, index record size at 0x44
     cursor.seek(SeekFrom::Start(0x40)).ok()?;
-    let mft_size_raw = cursor.read_i8().ok()?;
+    let mft_record_size = decode_record_size(cursor.read_i8().ok()?, cluster_size)?;
+    cursor.seek(SeekFrom::Start(0x44)).ok()?;
+    let index_record_size = decode_record_size(cursor.read_i8().ok()?, cluster_size)?;
     
-    let mft_record_size = if mft_size_raw > 0 {
-        (mft_size_raw as u32) * (sectors_per_cluster as u32) * (bytes_per_sector as u32)
-    } else {
-        1u32 << ((-mft_size_raw) as u32)
-    };
-    
-    let cluster_size = (bytes_per_sector as u32) * (sectors_per_cluster as u32);
+    // Volume serial number at offset 0x48
+    cursor.seek(SeekFrom::Start(0x48)).ok()?;
+    let volume_serial = cursor.read_u64::<LittleEndian>().ok()?;
     
     Some(NtfsBootSector {
         bytes_per_sector,
         sectors_per_cluster,
+        total_sectors,
         mft_cluster,
+        mft_mirror_cluster,
         mft_record_size,
+        index_record_size,
+        volume_serial,
         cluster_size,
     })
 }
@@ -121,100 +465,120 @@ pub fn parse_mft_record(data: &[u8], record_number: u64) -> Option<MftEntry> {
     
     // Apply fixup array
     let mut fixed_data = data.to_vec();
-    apply_fixup(&mut fixed_data, update_seq_offset as usize, update_seq_size as usize);
+    let is_torn = !apply_fixup(&mut fixed_data, update_seq_offset as usize, update_seq_size as usize);
     cursor = Cursor::new(&fixed_data);
     
+    // Sequence number at offset 0x10
+    cursor.seek(SeekFrom::Start(0x10)).ok()?;
+    let sequence_number = cursor.read_u16::<LittleEndian>().ok()?;
+    
     // Flags at offset 0x16
     cursor.seek(SeekFrom::Start(0x16)).ok()?;
     let flags = cursor.read_u16::<LittleEndian>().ok()?;
     let is_in_use = (flags & 0x01) != 0;
     let is_directory = (flags & 0x02) != 0;
     
-    // First attribute offset at 0x14
-    cursor.seek(SeekFrom::Start(0x14)).ok()?;
-    let first_attr_offset = cursor.read_u16::<LittleEndian>().ok()?;
-    
     let mut file_name = String::new();
     let mut parent_record = 0u64;
+    let mut parent_sequence = 0u16;
     let mut file_size = 0u64;
     let mut allocated_size = 0u64;
-    let mut created_time = 0i64;
-    let mut modified_time = 0i64;
-    let mut accessed_time = 0i64;
+    let mut data_sizes: Option<AttributeSizes> = None;
+    let mut si_times = MacbTimes::default();
+    let mut fn_times = MacbTimes::default();
+    let mut file_names: Vec<FileNameAttr> = Vec::new();
     let mut data_runs = Vec::new();
+    let mut resident_data = None;
+    let mut attribute_list = Vec::new();
+    let mut attribute_list_runs = Vec::new();
+    let mut attribute_list_size = 0u64;
+    let mut streams: Vec<DataStream> = Vec::new();
+    let mut is_compressed = false;
+    let mut reparse_tag = 0u32;
+    let mut wof_format = None;
     
-    // Parse attributes
-    let mut attr_offset = first_attr_offset as usize;
-    
-    while attr_offset < fixed_data.len() - 4 {
-        let attr_type = u32::from_le_bytes([
-            fixed_data[attr_offset],
-            fixed_data[attr_offset + 1],
-            fixed_data[attr_offset + 2],
-            fixed_data[attr_offset + 3],
-        ]);
-        
-        if attr_type == ATTRIBUTE_END || attr_type == 0 {
-            break;
-        }
-        
-        let attr_length = u32::from_le_bytes([
-            fixed_data[attr_offset + 4],
-            fixed_data[attr_offset + 5],
-            fixed_data[attr_offset + 6],
-            fixed_data[attr_offset + 7],
-        ]) as usize;
-        
-        if attr_length == 0 || attr_offset + attr_length > fixed_data.len() {
-            break;
-        }
-        
-        match attr_type {
+    // Parse attributes; headers too short to hold their own fields end the walk
+    for attr in record_attributes(&fixed_data) {
+        match attr.attr_type {
             ATTRIBUTE_STANDARD_INFORMATION => {
-                if let Some(times) = parse_standard_info(&fixed_data[attr_offset..attr_offset + attr_length]) {
-                    created_time = times.0;
-                    modified_time = times.1;
-                    accessed_time = times.2;
+                if let Some(times) = parse_standard_info(attr.data) {
+                    si_times = times;
+                }
+            }
+            ATTRIBUTE_ATTRIBUTE_LIST => {
+                if let Some(content) = attr.resident_content() {
+                    attribute_list = parse_attribute_list(content);
+                } else if let Some(segment) = attr.segment() {
+                    attribute_list_runs = segment.runs;
+                    attribute_list_size = segment.sizes.real;
                 }
             }
             ATTRIBUTE_FILE_NAME => {
-                if let Some((name, parent, size, alloc)) = parse_file_name_attr(&fixed_data[attr_offset..attr_offset + attr_length]) {
-                    if file_name.is_empty() || name.len() > file_name.len() {
-                        file_name = name;
-                        parent_record = parent;
-                        if size > 0 {
-                            file_size = size;
-                        }
-                        if alloc > 0 {
-                            allocated_size = alloc;
-                        }
-                    }
+                if let Some(name) = parse_file_name_attr(attr.data) {
+                    file_names.push(name);
                 }
             }
             ATTRIBUTE_DATA => {
-                if let Some((size, runs)) = parse_data_attr(&fixed_data[attr_offset..attr_offset + attr_length]) {
-                    if size > file_size {
-                        file_size = size;
+                if attr.data[9] > 0 {
+                    // Named stream: keep it separate from the file's main data (an
+                    // unreadable name leaves it out altogether)
+                    if let (false, Some((sizes, runs))) = (attr.name.is_empty(), parse_data_attr(attr.data)) {
+                        streams.push(DataStream {
+                            name: attr.name.clone(),
+                            size: sizes.real,
+                            initialized_size: sizes.initialized,
+                            resident_data: attr.resident_content().map(<[u8]>::to_vec),
+                            data_runs: runs,
+                        });
+                    }
+                } else if let Some((sizes, runs)) = parse_data_attr(attr.data) {
+                    if attr.flags & ATTRIBUTE_FLAG_COMPRESSED != 0 {
+                        is_compressed = true;
                     }
-                    // Only update data_runs if we found some, or if this is the unnamed data stream
-                    // Check if this is the unnamed stream (attribute name length = 0 at offset 9)
-                    if attr_offset + 9 < fixed_data.len() {
-                        let name_length = fixed_data[attr_offset + 9];
-                        if name_length == 0 || !runs.is_empty() {
-                            // This is the main data stream or we found data runs
-                            if runs.len() > data_runs.len() {
-                                data_runs = runs;
-                            }
-                        }
+                    if sizes.real > file_size {
+                        file_size = sizes.real;
                     }
+                    data_sizes = Some(sizes);
+                    if !attr.non_resident {
+                        resident_data = attr.resident_content().map(<[u8]>::to_vec);
+                    }
+                    if runs.len() > data_runs.len() {
+                        data_runs = runs;
+                    }
+                }
+            }
+            ATTRIBUTE_REPARSE_POINT => {
+                if let Some((tag, format)) = attr.resident_content().and_then(parse_reparse_point) {
+                    reparse_tag = tag;
+                    wof_format = format;
                 }
             }
             _ => {}
         }
-        
-        attr_offset += attr_length;
     }
     
+    // Show the Win32 name when there is one, not a DOS alias or POSIX link
+    let primary = file_names.iter().enumerate().min_by_key(|(i, name)| (name.namespace.display_rank(), *i));
+    if let Some((_, name)) = primary {
+        file_name = name.name.clone();
+        fn_times = name.times;
+        parent_record = name.parent_record;
+        parent_sequence = name.parent_sequence;
+        file_size = file_size.max(name.real_size);
+        if name.allocated_size > 0 {
+            allocated_size = name.allocated_size;
+        }
+    }
+    
+    // The $DATA header is authoritative; $FILE_NAME sizes are only updated lazily
+    let initialized_size = match data_sizes {
+        Some(sizes) => {
+            allocated_size = sizes.allocated;
+            sizes.initialized.min(file_size)
+        }
+        None => file_size,
+    };
+    
     // Extract extension
     let extension = file_name
         .rsplit('.')
@@ -225,28 +589,282 @@ pub fn parse_mft_record(data: &[u8], record_number: u64) -> Option<MftEntry> {
     
     Some(MftEntry {
         record_number,
+        sequence_number,
         file_name,
         parent_record,
+        parent_sequence,
         file_size,
         allocated_size,
-        created_time,
-        modified_time,
-        accessed_time,
+        initialized_size,
+        created_time: filetime_to_unix(si_times.created),
+        modified_time: filetime_to_unix(si_times.modified),
+        accessed_time: filetime_to_unix(si_times.accessed),
+        si_times,
+        fn_times,
+        file_names,
         is_deleted: !is_in_use,
         is_directory,
         is_in_use,
         data_runs,
+        resident_data,
+        is_torn,
         extension,
+        attribute_list,
+        attribute_list_runs,
+        attribute_list_size,
+        streams,
+        is_compressed,
+        reparse_tag,
+        wof_format,
     })
 }
 
-/// Apply NTFS fixup array to correct sector boundaries
-fn apply_fixup(data: &mut [u8], offset: usize, count: usize) {
+/// Whether a record with `sequence` (and `in_use` state) is still the one a
+/// file reference carrying `expected` pointed at. Freeing a record bumps its
+/// sequence number, so a deleted directory may be one ahead of the reference;
+/// an in-use record that is ahead has been reused. 0 means "not recorded".
+pub fn sequence_matches(expected: u16, sequence: u16, in_use: bool) -> bool {
+    expected == 0 || sequence == expected || (!in_use && sequence == expected.wrapping_add(1))
+}
+
+/// Parse $REPARSE_POINT content: the tag, plus the compression format when it
+/// is a WOF reparse point handled by the file provider (CompactOS)
+fn parse_reparse_point(content: &[u8]) -> Option<(u32, Option<u32>)> {
+    let tag = u32::from_le_bytes(content.get(0..4)?.try_into().ok()?);
+    if tag != REPARSE_TAG_WOF {
+        return Some((tag, None));
+    }
+    // WOF_EXTERNAL_INFO { version, provider } then FILE_PROVIDER_EXTERNAL_INFO_V1 { version, format }
+    let field = |at: usize| content.get(at..at + 4).map(|b| u32::from_le_bytes(b.try_into().unwrap()));
+    let format = match (field(12), field(20)) {
+        (Some(WOF_PROVIDER_FILE), Some(format)) => Some(format),
+        _ => None,
+    };
+    Some((tag, format))
+}
+
+/// Segments of the unnamed $DATA attribute held in one fixed-up record
+pub fn data_segments(record: &[u8]) -> Vec<AttributeSegment> {
+    record_attributes(record)
+        .iter()
+        .filter(|a| a.attr_type == ATTRIBUTE_DATA && a.name.is_empty())
+        .filter_map(|a| a.segment())
+        .collect()
+}
+
+/// Complete `entry.data_runs` for a file whose $DATA is split across
+/// extension records. `read_record` returns the raw bytes of an MFT record.
+/// Non-resident attribute lists must be loaded into `entry.attribute_list`
+/// by the caller first. Returns false when nothing could be resolved.
+pub fn resolve_attribute_list<F>(entry: &mut MftEntry, mut read_record: F) -> bool
+where
+    F: FnMut(u64) -> Option<Vec<u8>>,
+{
+    let mut records: Vec<u64> = entry
+        .attribute_list
+        .iter()
+        .filter(|e| e.attr_type == ATTRIBUTE_DATA)
+        .map(|e| e.record_number)
+        .collect();
+    records.sort_unstable();
+    records.dedup();
+    
+    let mut segments = Vec::new();
+    let mut named_segments: Vec<(String, AttributeSegment)> = Vec::new();
+    for record_number in records {
+        let Some(record) = read_record(record_number).as_deref().and_then(fixup_record) else {
+            continue;
+        };
+        // Extension records point back at their base record; anything else was reused
+        let base = u64::from_le_bytes(record[0x20..0x28].try_into().unwrap()) & FILE_REFERENCE_RECORD_MASK;
+        if record_number != entry.record_number && base != entry.record_number {
+            continue;
+        }
+        for attr in record_attributes(&record).iter().filter(|a| a.attr_type == ATTRIBUTE_DATA) {
+            match (attr.name.is_empty(), attr.segment()) {
+                (true, Some(segment)) => segments.push(segment),
+                (false, Some(segment)) => named_segments.push((attr.name.clone(), segment)),
+                _ => {}
+            }
+        }
+    }
+    
+    // Named streams whose run lists span several records
+    let mut names: Vec<String> = named_segments.iter().map(|(name, _)| name.clone()).collect();
+    names.sort();
+    names.dedup();
+    for name in names {
+        let parts: Vec<AttributeSegment> = named_segments
+            .iter()
+            .filter(|(n, _)| *n == name)
+            .map(|(_, segment)| segment.clone())
+            .collect();
+        let sizes = parts.iter().find(|s| s.starting_vcn == 0).map(|s| s.sizes).unwrap_or_default();
+        let data_runs = stitch_segments(parts);
+        match entry.streams.iter_mut().find(|s| s.name == name) {
+            Some(stream) => stream.data_runs = data_runs,
+            None => entry.streams.push(DataStream {
+                name,
+                size: sizes.real,
+                initialized_size: sizes.initialized,
+                resident_data: None,
+                data_runs,
+            }),
+        }
+    }
+    
+    if segments.is_empty() {
+        return false;
+    }
+    if let Some(first) = segments.iter().find(|s| s.starting_vcn == 0) {
+        if first.sizes.real > 0 {
+            entry.file_size = first.sizes.real;
+            entry.allocated_size = first.sizes.allocated;
+            entry.initialized_size = first.sizes.initialized.min(first.sizes.real);
+        }
+    }
+    entry.data_runs = stitch_segments(segments);
+    true
+}
+
+/// Validate a raw FILE record and return a copy with its fixup array applied
+pub fn fixup_record(data: &[u8]) -> Option<Vec<u8>> {
+    if data.len() < 48 || &data[0..4] != MFT_SIGNATURE {
+        return None;
+    }
+    let update_seq_offset = u16::from_le_bytes([data[4], data[5]]) as usize;
+    let update_seq_size = u16::from_le_bytes([data[6], data[7]]) as usize;
+    let mut fixed_data = data.to_vec();
+    apply_fixup(&mut fixed_data, update_seq_offset, update_seq_size);
+    Some(fixed_data)
+}
+
+/// Walk the attributes of a fixed-up MFT record
+pub fn record_attributes(record: &[u8]) -> Vec<RawAttribute<'_>> {
+    let mut attributes = Vec::new();
+    if record.len() < 24 {
+        return attributes;
+    }
+    let mut offset = u16::from_le_bytes([record[0x14], record[0x15]]) as usize;
+    
+    while offset + 16 <= record.len() {
+        let attr_type = u32::from_le_bytes(record[offset..offset + 4].try_into().unwrap());
+        if attr_type == ATTRIBUTE_END || attr_type == 0 {
+            break;
+        }
+        let attr_length = u32::from_le_bytes(record[offset + 4..offset + 8].try_into().unwrap()) as usize;
+        if attr_length < 16 || offset + attr_length > record.len() {
+            break;
+        }
+        let data = &record[offset..offset + attr_length];
+        let name_length = data[9] as usize;
+        let name_offset = u16::from_le_bytes([data[10], data[11]]) as usize;
+        let name = data
+            .get(name_offset..name_offset + name_length * 2)
+            .map(utf16_name)
+            .unwrap_or_default();
+        
+        attributes.push(RawAttribute {
+            attr_type,
+            name,
+            non_resident: data[8] != 0,
+            flags: u16::from_le_bytes([data[12], data[13]]),
+            data,
+        });
+        offset += attr_length;
+    }
+    
+    attributes
+}
+
+/// Parse the value of an $ATTRIBUTE_LIST attribute
+pub fn parse_attribute_list(content: &[u8]) -> Vec<AttributeListEntry> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    
+    while offset + 26 <= content.len() {
+        let entry = &content[offset..];
+        let entry_length = u16::from_le_bytes([entry[4], entry[5]]) as usize;
+        if entry_length < 26 || offset + entry_length > content.len() {
+            break;
+        }
+        let name_length = entry[6] as usize;
+        let name_offset = entry[7] as usize;
+        let reference = u64::from_le_bytes(entry[16..24].try_into().unwrap());
+        
+        entries.push(AttributeListEntry {
+            attr_type: u32::from_le_bytes(entry[0..4].try_into().unwrap()),
+            name: entry
+                .get(name_offset..name_offset + name_length * 2)
+                .map(utf16_name)
+                .unwrap_or_default(),
+            starting_vcn: u64::from_le_bytes(entry[8..16].try_into().unwrap()),
+            record_number: reference & FILE_REFERENCE_RECORD_MASK,
+            sequence_number: (reference >> 48) as u16,
+        });
+        offset += entry_length;
+    }
+    
+    entries
+}
+
+/// Join run-list segments into one run list in VCN order. Gaps between
+/// segments (lost extension records) become sparse runs so later VCNs keep
+/// their position in the file. Each segment is held to the VCN range in its
+/// header: runs past its last VCN are cut, and a short run list is padded.
+pub fn stitch_segments(mut segments: Vec<AttributeSegment>) -> Vec<DataRun> {
+    segments.sort_by_key(|s| s.starting_vcn);
+    let mut runs = Vec::new();
+    let mut next_vcn = 0u64;
+    
+    for segment in segments {
+        if segment.starting_vcn < next_vcn {
+            continue; // Duplicate or overlapping segment
+        }
+        if segment.starting_vcn > next_vcn {
+            runs.push(DataRun {
+                cluster_offset: 0,
+                cluster_count: segment.starting_vcn - next_vcn,
+            });
+        }
+        // An empty attribute has a last VCN of -1; trust its runs instead
+        let end_vcn = match segment.last_vcn.checked_add(1) {
+            Some(end) if end > segment.starting_vcn => end,
+            _ => segment.starting_vcn + segment.runs.iter().map(|r| r.cluster_count).sum::<u64>(),
+        };
+        
+        let mut vcn = segment.starting_vcn;
+        for mut run in segment.runs {
+            if vcn >= end_vcn {
+                break;
+            }
+            run.cluster_count = run.cluster_count.min(end_vcn - vcn);
+            vcn += run.cluster_count;
+            runs.push(run);
+        }
+        if vcn < end_vcn {
+            runs.push(DataRun { cluster_offset: 0, cluster_count: end_vcn - vcn });
+        }
+        next_vcn = end_vcn;
+    }
+    
+    runs
+}
+
+fn utf16_name(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// Apply NTFS fixup array to correct sector boundaries. Returns false when a
+/// sector does not end in the update sequence number (a torn write).
+pub fn apply_fixup(data: &mut [u8], offset: usize, count: usize) -> bool {
     if offset + 2 + count * 2 > data.len() {
-        return;
+        return false;
     }
     
     let signature = u16::from_le_bytes([data[offset], data[offset + 1]]);
+    let mut intact = true;
     
     for i in 1..count {
         let fixup_value = u16::from_le_bytes([
@@ -261,12 +879,56 @@ fn apply_fixup(data: &mut [u8], offset: usize, count: usize) {
             if current == signature {
                 data[sector_end] = fixup_value as u8;
                 data[sector_end + 1] = (fixup_value >> 8) as u8;
+            } else {
+                intact = false;
             }
         }
     }
+    intact
 }
 
-fn parse_standard_info(data: &[u8]) -> Option<(i64, i64, i64)> {
+/// Convert Windows FILETIME to Unix timestamp
+pub fn filetime_to_unix(ft: i64) -> i64 {
+    if ft <= 0 {
+        return 0;
+    }
+    (ft / 10_000_000) - 11_644_473_600
+}
+
+/// Render a FILETIME as ISO-8601 UTC at its full 100 ns precision
+pub fn filetime_to_iso8601(ft: i64) -> Option<String> {
+    if ft <= 0 {
+        return None;
+    }
+    let seconds = ft / 10_000_000 - 11_644_473_600;
+    chrono::DateTime::from_timestamp(seconds, 0)
+        .map(|dt| format!("{}.{:07}Z", dt.format("%Y-%m-%dT%H:%M:%S"), ft % 10_000_000))
+}
+
+/// Parse an ISO-8601 / RFC 3339 timestamp back into a FILETIME
+pub fn iso8601_to_filetime(text: &str) -> Option<i64> {
+    let dt = chrono::DateTime::parse_from_rfc3339(text).ok()?;
+    Some((dt.timestamp() + 11_644_473_600) * 10_000_000 + (dt.timestamp_subsec_nanos() / 100) as i64)
+}
+
+/// Serializes a FILETIME field as an ISO-8601 string, or null when unset
+mod filetime_iso8601 {
+    use serde::{Deserialize, Deserializer, Serializer};
+    
+    pub fn serialize<S: Serializer>(ft: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+        match super::filetime_to_iso8601(*ft) {
+            Some(text) => serializer.serialize_str(&text),
+            None => serializer.serialize_none(),
+        }
+    }
+    
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        let text = Option::<String>::deserialize(deserializer)?;
+        Ok(text.as_deref().and_then(super::iso8601_to_filetime).unwrap_or(0))
+    }
+}
+
+fn parse_standard_info(data: &[u8]) -> Option<MacbTimes> {
     if data.len() < 72 {
         return None;
     }
@@ -278,48 +940,10 @@ fn parse_standard_info(data: &[u8]) -> Option<(i64, i64, i64)> {
     }
     
     let content_offset = u16::from_le_bytes([data[20], data[21]]) as usize;
-    
-    if content_offset + 32 > data.len() {
-        return None;
-    }
-    
-    let created = i64::from_le_bytes([
-        data[content_offset], data[content_offset + 1],
-        data[content_offset + 2], data[content_offset + 3],
-        data[content_offset + 4], data[content_offset + 5],
-        data[content_offset + 6], data[content_offset + 7],
-    ]);
-    
-    let modified = i64::from_le_bytes([
-        data[content_offset + 8], data[content_offset + 9],
-        data[content_offset + 10], data[content_offset + 11],
-        data[content_offset + 12], data[content_offset + 13],
-        data[content_offset + 14], data[content_offset + 15],
-    ]);
-    
-    let accessed = i64::from_le_bytes([
-        data[content_offset + 24], data[content_offset + 25],
-        data[content_offset + 26], data[content_offset + 27],
-        data[content_offset + 28], data[content_offset + 29],
-        data[content_offset + 30], data[content_offset + 31],
-    ]);
-    
-    // Convert Windows FILETIME to Unix timestamp
-    fn filetime_to_unix(ft: i64) -> i64 {
-        if ft <= 0 {
-            return 0;
-        }
-        (ft / 10_000_000) - 11_644_473_600
-    }
-    
-    Some((
-        filetime_to_unix(created),
-        filetime_to_unix(modified),
-        filetime_to_unix(accessed),
-    ))
+    MacbTimes::from_bytes(data.get(content_offset..content_offset + 32)?)
 }
 
-fn parse_file_name_attr(data: &[u8]) -> Option<(String, u64, u64, u64)> {
+fn parse_file_name_attr(data: &[u8]) -> Option<FileNameAttr> {
     if data.len() < 90 {
         return None;
     }
@@ -339,10 +963,10 @@ fn parse_file_name_attr(data: &[u8]) -> Option<(String, u64, u64, u64)> {
     
     let content = &data[content_offset..];
     
-    // Parent directory reference (first 6 bytes of 8)
+    // Parent directory reference: 48-bit record number + 16-bit sequence number
     let parent_ref = u64::from_le_bytes([
         content[0], content[1], content[2], content[3],
-        content[4], content[5], 0, 0,
+        content[4], content[5], content[6], content[7],
     ]);
     
     // Allocated size
@@ -359,12 +983,7 @@ fn parse_file_name_attr(data: &[u8]) -> Option<(String, u64, u64, u64)> {
     
     // Filename length
     let name_length = content[64] as usize;
-    let name_type = content[65];
-    
-    // Skip DOS names (type 2)
-    if name_type == 2 {
-        return None;
-    }
+    let namespace = FileNamespace::from_u8(content[65]);
     
     if 66 + name_length * 2 > content.len() {
         return None;
@@ -382,10 +1001,20 @@ fn parse_file_name_attr(data: &[u8]) -> Option<(String, u64, u64, u64)> {
     
     let file_name = String::from_utf16_lossy(&name_chars);
     
-    Some((file_name, parent_ref, real_size, allocated_size))
+    let times = MacbTimes::from_bytes(&content[8..40])?;
+    
+    Some(FileNameAttr {
+        name: file_name,
+        namespace,
+        parent_record: parent_ref & FILE_REFERENCE_RECORD_MASK,
+        parent_sequence: (parent_ref >> 48) as u16,
+        real_size,
+        allocated_size,
+        times,
+    })
 }
 
-fn parse_data_attr(data: &[u8]) -> Option<(u64, Vec<DataRun>)> {
+fn parse_data_attr(data: &[u8]) -> Option<(AttributeSizes, Vec<DataRun>)> {
     if data.len() < 24 {
         return None;
     }
@@ -394,8 +1023,9 @@ fn parse_data_attr(data: &[u8]) -> Option<(u64, Vec<DataRun>)> {
     
     if non_resident == 0 {
         // Resident data
-        let content_length = u32::from_le_bytes([data[16], data[17], data[18], data[19]]);
-        return Some((content_length as u64, Vec::new()));
+        let content_length = u32::from_le_bytes([data[16], data[17], data[18], data[19]]) as u64;
+        let sizes = AttributeSizes { allocated: content_length, real: content_length, initialized: content_length };
+        return Some((sizes, Vec::new()));
     }
     
     // Non-resident data
@@ -403,18 +1033,20 @@ fn parse_data_attr(data: &[u8]) -> Option<(u64, Vec<DataRun>)> {
         return None;
     }
     
-    // Real size at offset 48
-    let real_size = u64::from_le_bytes([
-        data[48], data[49], data[50], data[51],
-        data[52], data[53], data[54], data[55],
-    ]);
+    // Allocated, real and initialized sizes at offsets 40, 48 and 56
+    let read_u64 = |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap());
+    let sizes = AttributeSizes {
+        allocated: read_u64(40),
+        real: read_u64(48),
+        initialized: read_u64(56),
+    };
     
     // Data runs offset at offset 32
     let runs_offset = u16::from_le_bytes([data[32], data[33]]) as usize;
     
     let data_runs = parse_data_runs(&data[runs_offset..]);
     
-    Some((real_size, data_runs))
+    Some((sizes, data_runs))
 }
 
 pub fn parse_data_runs(data: &[u8]) -> Vec<DataRun> {
@@ -507,6 +1139,78 @@ pub fn scan_mft_for_deleted(mft_data: &[u8], boot_sector: &NtfsBootSector) -> Ve
 mod tests {
     use super::*;
     
+    fn attribute(attr_type: u32, name: &str, non_resident: bool, body: &[u8]) -> Vec<u8> {
+        let header = if non_resident { 64 } else { 24 };
+        let name_units: Vec<u8> = name.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        let body_offset = (header + name_units.len() + 7) & !7;
+        let length = (body_offset + body.len() + 7) & !7;
+        let mut attr = vec![0u8; length];
+        attr[0..4].copy_from_slice(&attr_type.to_le_bytes());
+        attr[4..8].copy_from_slice(&(length as u32).to_le_bytes());
+        attr[8] = non_resident as u8;
+        attr[9] = name.encode_utf16().count() as u8;
+        attr[10..12].copy_from_slice(&(header as u16).to_le_bytes());
+        attr[header..header + name_units.len()].copy_from_slice(&name_units);
+        if non_resident {
+            attr[32..34].copy_from_slice(&(body_offset as u16).to_le_bytes());
+        } else {
+            attr[16..20].copy_from_slice(&(body.len() as u32).to_le_bytes());
+            attr[20..22].copy_from_slice(&(body_offset as u16).to_le_bytes());
+        }
+        attr[body_offset..body_offset + body.len()].copy_from_slice(body);
+        attr
+    }
+    
+    /// Attribute whose header is cut off after `length` bytes
+    fn short_attribute(attr_type: u32, length: u32) -> Vec<u8> {
+        let mut attr = vec![0u8; length as usize];
+        attr[0..4].copy_from_slice(&attr_type.to_le_bytes());
+        attr[4..8].copy_from_slice(&length.to_le_bytes());
+        attr
+    }
+    
+    /// Non-resident attribute with a single run of `clusters` clusters at `lcn`
+    fn non_resident(attr_type: u32, name: &str, lcn: u8, clusters: u8, real_size: u64) -> Vec<u8> {
+        let mut attr = attribute(attr_type, name, true, &[0x11, clusters, lcn, 0]);
+        attr[24..32].copy_from_slice(&(clusters as u64 - 1).to_le_bytes());
+        attr[40..48].copy_from_slice(&(clusters as u64 * 4096).to_le_bytes());
+        attr[48..56].copy_from_slice(&real_size.to_le_bytes());
+        attr[56..64].copy_from_slice(&real_size.to_le_bytes());
+        attr
+    }
+    
+    fn file_record(attributes: &[Vec<u8>], in_use: bool) -> Vec<u8> {
+        let mut record = vec![0u8; MFT_RECORD_SIZE];
+        record[0..4].copy_from_slice(MFT_SIGNATURE);
+        record[4..6].copy_from_slice(&48u16.to_le_bytes());
+        record[6..8].copy_from_slice(&3u16.to_le_bytes());
+        record[0x14..0x16].copy_from_slice(&56u16.to_le_bytes());
+        record[0x16] = in_use as u8;
+        let mut offset = 56;
+        for attr in attributes {
+            record[offset..offset + attr.len()].copy_from_slice(attr);
+            offset += attr.len();
+        }
+        record[offset..offset + 4].copy_from_slice(&ATTRIBUTE_END.to_le_bytes());
+        // Update sequence: stamp the last two bytes of each 512-byte sector
+        record[48..50].copy_from_slice(&[1, 0]);
+        for i in 1..3 {
+            let end = i * 512 - 2;
+            record.copy_within(end..end + 2, 48 + i * 2);
+            record[end..end + 2].copy_from_slice(&[1, 0]);
+        }
+        record
+    }
+    
+    fn file_name(name: &str) -> Vec<u8> {
+        let mut content = vec![0u8; 66];
+        content[0] = 5;
+        content[64] = name.encode_utf16().count() as u8;
+        content[65] = 1;
+        content.extend(name.encode_utf16().flat_map(|u| u.to_le_bytes()));
+        attribute(ATTRIBUTE_FILE_NAME, "", false, &content)
+    }
+    
     #[test]
     fn test_filetime_conversion() {
         // Windows FILETIME for 2020-01-01 00:00:00 UTC
@@ -514,4 +1218,251 @@ mod tests {
         let unix = (ft / 10_000_000) - 11_644_473_600;
         assert!(unix > 0);
     }
+    
+    #[test]
+    fn test_named_streams_are_kept_apart() {
+        let record = file_record(&[
+            file_name("report.docx"),
+            non_resident(ATTRIBUTE_DATA, "", 40, 2, 6000),
+            attribute(ATTRIBUTE_DATA, "Zone.Identifier", false, b"[ZoneTransfer]\r\nZoneId=3\r\n"),
+            non_resident(ATTRIBUTE_DATA, "payload", 90, 4, 15000),
+        ], false);
+        let entry = parse_mft_record(&record, 42).unwrap();
+        
+        assert_eq!(entry.file_name, "report.docx");
+        assert_eq!(entry.file_size, 6000);
+        assert_eq!(entry.data_runs.len(), 1);
+        assert_eq!(entry.data_runs[0].cluster_offset, 40);
+        
+        assert_eq!(entry.streams.len(), 2);
+        assert_eq!(entry.streams[0].name, "Zone.Identifier");
+        assert_eq!(entry.streams[0].resident_data.as_deref(), Some(&b"[ZoneTransfer]\r\nZoneId=3\r\n"[..]));
+        assert_eq!(entry.streams[1].name, "payload");
+        assert_eq!(entry.streams[1].size, 15000);
+        assert_eq!(entry.streams[1].data_runs[0].cluster_offset, 90);
+        
+        // A damaged $DATA header too short for its own fields ends the walk
+        let record = file_record(&[file_name("report.docx"), short_attribute(ATTRIBUTE_DATA, 8)], false);
+        let entry = parse_mft_record(&record, 42).unwrap();
+        assert_eq!((entry.file_name.as_str(), entry.streams.len()), ("report.docx", 0));
+    }
+    
+    #[test]
+    fn test_initialized_size_from_data_header() {
+        // Preallocated file: 6000 bytes long, only the first 2500 ever written
+        let mut data = non_resident(ATTRIBUTE_DATA, "", 40, 2, 6000);
+        data[56..64].copy_from_slice(&2500u64.to_le_bytes());
+        let entry = parse_mft_record(&file_record(&[file_name("prealloc.bin"), data], false), 7).unwrap();
+        
+        assert_eq!(entry.file_size, 6000);
+        assert_eq!(entry.allocated_size, 8192);
+        assert_eq!(entry.initialized_size, 2500);
+        
+        // Resident data is always fully initialized
+        let resident = attribute(ATTRIBUTE_DATA, "", false, b"hello");
+        let entry = parse_mft_record(&file_record(&[file_name("small.txt"), resident], false), 8).unwrap();
+        assert_eq!(entry.initialized_size, 5);
+    }
+    
+    #[test]
+    fn test_resident_data_and_torn_records() {
+        let resident = attribute(ATTRIBUTE_DATA, "", false, b"hello");
+        let mut record = file_record(&[file_name("small.txt"), resident], false);
+        let entry = parse_mft_record(&record, 8).unwrap();
+        assert_eq!(entry.resident_data.as_deref(), Some(&b"hello"[..]));
+        assert!(entry.data_runs.is_empty() && !entry.is_torn);
+        
+        // Second sector written by a later update than the first
+        record[1022] = 2;
+        assert!(parse_mft_record(&record, 8).unwrap().is_torn);
+        
+        let entry = parse_mft_record(&file_record(&[file_name("big.bin"), non_resident(ATTRIBUTE_DATA, "", 40, 2, 6000)], false), 9).unwrap();
+        assert_eq!(entry.resident_data, None);
+        
+        // WOF reparse point, then one whose header is cut short
+        let mut reparse = vec![0u8; 24];
+        reparse[0..4].copy_from_slice(&REPARSE_TAG_WOF.to_le_bytes());
+        reparse[12..16].copy_from_slice(&WOF_PROVIDER_FILE.to_le_bytes());
+        reparse[20..24].copy_from_slice(&1u32.to_le_bytes());
+        let entry = parse_mft_record(&file_record(&[file_name("app.exe"), attribute(ATTRIBUTE_REPARSE_POINT, "", false, &reparse)], false), 10).unwrap();
+        assert_eq!((entry.reparse_tag, entry.wof_format), (REPARSE_TAG_WOF, Some(1)));
+        for length in [8, 16] {
+            let entry = parse_mft_record(&file_record(&[file_name("app.exe"), short_attribute(ATTRIBUTE_REPARSE_POINT, length)], false), 10).unwrap();
+            assert_eq!((entry.reparse_tag, entry.wof_format), (0, None));
+        }
+    }
+    
+    #[test]
+    fn test_sequence_numbers_and_parent_reference() {
+        // Parent reference 0x0007_0000_0000_0123: record 0x123, sequence 7
+        let mut name = file_name("a.txt");
+        let content = u16::from_le_bytes([name[20], name[21]]) as usize;
+        name[content..content + 8].copy_from_slice(&0x0007_0000_0000_0123u64.to_le_bytes());
+        let mut record = file_record(&[name], false);
+        record[0x10..0x12].copy_from_slice(&12u16.to_le_bytes());
+        let entry = parse_mft_record(&record, 64).unwrap();
+        
+        assert_eq!(entry.sequence_number, 12);
+        assert_eq!(entry.parent_record, 0x123);
+        assert_eq!(entry.parent_sequence, 7);
+        
+        // Same directory, still live or deleted since (freeing bumps the sequence)
+        assert!(sequence_matches(7, 7, true));
+        assert!(sequence_matches(7, 8, false));
+        // Record reallocated to another directory
+        assert!(!sequence_matches(7, 8, true));
+        assert!(!sequence_matches(7, 9, false));
+        assert!(sequence_matches(0, 9, true));
+    }
+    
+    #[test]
+    fn test_attribute_list_and_segment_stitching() {
+        // Two entries: $DATA at VCN 0 in the base record, VCN 8 in record 0x1234 (seq 3)
+        let mut list = vec![0u8; 64];
+        for (i, (vcn, reference)) in [(0u64, 0u64), (8, (3u64 << 48) | 0x1234)].iter().enumerate() {
+            let entry = &mut list[i * 32..(i + 1) * 32];
+            entry[0..4].copy_from_slice(&ATTRIBUTE_DATA.to_le_bytes());
+            entry[4..6].copy_from_slice(&32u16.to_le_bytes());
+            entry[7] = 26;
+            entry[8..16].copy_from_slice(&vcn.to_le_bytes());
+            entry[16..24].copy_from_slice(&reference.to_le_bytes());
+        }
+        let entries = parse_attribute_list(&list);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].record_number, 0x1234);
+        assert_eq!(entries[1].sequence_number, 3);
+        assert_eq!(entries[1].starting_vcn, 8);
+        
+        let segment = |vcn: u64, lcn: i64, count: u64| AttributeSegment {
+            starting_vcn: vcn,
+            last_vcn: vcn + count - 1,
+            sizes: AttributeSizes::default(),
+            runs: vec![DataRun { cluster_offset: lcn, cluster_count: count }],
+        };
+        // Out of order, with VCNs 4-7 missing
+        let runs = stitch_segments(vec![segment(8, 500, 2), segment(0, 100, 4)]);
+        let layout: Vec<(i64, u64)> = runs.iter().map(|r| (r.cluster_offset, r.cluster_count)).collect();
+        assert_eq!(layout, vec![(100, 4), (0, 4), (500, 2)]);
+        
+        // A segment overlapping the one before it is dropped; run lists
+        // longer or shorter than their segment's VCN range are cut or padded
+        let mut long = segment(0, 100, 4);
+        long.runs.push(DataRun { cluster_offset: 700, cluster_count: 3 });
+        let mut short = segment(4, 300, 4);
+        short.runs[0].cluster_count = 1;
+        let runs = stitch_segments(vec![short, segment(2, 200, 4), long, segment(8, 500, 2)]);
+        let layout: Vec<(i64, u64)> = runs.iter().map(|r| (r.cluster_offset, r.cluster_count)).collect();
+        assert_eq!(layout, vec![(100, 4), (300, 1), (0, 3), (500, 2)]);
+        
+        // Attribute lists with headers too short for their size fields are skipped
+        for length in [8, 16] {
+            let record = file_record(&[file_name("big.vhdx"), short_attribute(ATTRIBUTE_ATTRIBUTE_LIST, length)], false);
+            let entry = parse_mft_record(&record, 42).unwrap();
+            assert!(entry.attribute_list.is_empty() && entry.attribute_list_runs.is_empty());
+        }
+    }
+    
+    #[test]
+    fn test_macb_timestamps_and_timestomp_indicators() {
+        // 2020-01-01 00:00:00.1234567 UTC
+        let ft = 132_223_104_001_234_567i64;
+        assert_eq!(filetime_to_iso8601(ft).as_deref(), Some("2020-01-01T00:00:00.1234567Z"));
+        assert_eq!(iso8601_to_filetime("2020-01-01T00:00:00.1234567Z"), Some(ft));
+        assert_eq!(filetime_to_iso8601(94_354_848_000_000_000).as_deref(), Some("1900-01-01T00:00:00.0000000Z"));
+        assert_eq!(filetime_to_iso8601(0), None);
+        
+        // $SI created rewritten to a whole second in 2016, before the $FN time
+        let whole_second = 131_000_000_000_000_000i64;
+        let mut si: Vec<u8> = [whole_second, ft + 5, ft + 5, ft + 5].iter().flat_map(|t| t.to_le_bytes()).collect();
+        si.resize(48, 0);
+        let mut name = file_name("a.txt");
+        let content = u16::from_le_bytes([name[20], name[21]]) as usize;
+        for i in 0..4 {
+            name[content + 8 + i * 8..content + 16 + i * 8].copy_from_slice(&ft.to_le_bytes());
+        }
+        let record = file_record(&[attribute(ATTRIBUTE_STANDARD_INFORMATION, "", false, &si), name], false);
+        let entry = parse_mft_record(&record, 64).unwrap();
+        
+        assert_eq!(entry.si_times.created, whole_second);
+        assert_eq!(entry.si_times.accessed, ft + 5);
+        assert_eq!(entry.fn_times.mft_modified, ft);
+        assert_eq!(entry.created_time, filetime_to_unix(whole_second));
+        let timestamps = entry.timestamps();
+        assert_eq!(timestamps.timestomp_indicators, vec!["si_created_before_fn", "si_zero_subseconds"]);
+        
+        let json = serde_json::to_value(&timestamps).unwrap();
+        assert_eq!(json["file_name"]["created"], "2020-01-01T00:00:00.1234567Z");
+        assert_eq!(serde_json::from_value::<FileTimestamps>(json).unwrap(), timestamps);
+    }
+    
+    #[test]
+    fn test_file_names_prefer_win32_and_list_hard_links() {
+        let name_in = |name: &str, namespace: u8, parent: u64| {
+            let mut attr = file_name(name);
+            let content = u16::from_le_bytes([attr[20], attr[21]]) as usize;
+            attr[content..content + 8].copy_from_slice(&(parent | 1 << 48).to_le_bytes());
+            attr[content + 65] = namespace;
+            attr
+        };
+        let record = file_record(&[
+            name_in("REPORT~1.DOC", 2, 5),
+            name_in("report.docx", 1, 5),
+            name_in("Report.docx", 0, 0x40),
+            name_in("report.docx", 1, 5),
+        ], false);
+        let entry = parse_mft_record(&record, 64).unwrap();
+        
+        // The DOS alias came first and the POSIX name is longer, but the Win32 name wins
+        assert_eq!(entry.file_name, "report.docx");
+        assert_eq!(entry.parent_record, 5);
+        assert_eq!(entry.file_names.len(), 4);
+        assert_eq!(entry.file_names[0].namespace, FileNamespace::Dos);
+        
+        let links: Vec<(&str, u64)> = entry.hard_links().iter().map(|l| (l.name.as_str(), l.parent_record)).collect();
+        assert_eq!(links, vec![("report.docx", 5), ("Report.docx", 0x40)]);
+    }
+    
+    #[test]
+    fn test_cluster_bitmap_counts_and_free_extents() {
+        // Clusters 0-9 allocated, 10-13 free, 14-23 allocated, 24-31 free
+        let bitmap = ClusterBitmap::new(vec![0xFF, 0xC3, 0xFF, 0x00]);
+        assert!(bitmap.is_allocated(9));
+        assert!(!bitmap.is_allocated(12));
+        assert!(bitmap.is_allocated(40));
+        assert_eq!(bitmap.allocated_count(8, 16), 12);
+        assert_eq!(bitmap.allocated_count(28, 8), 4); // Past the end counts as allocated
+        assert_eq!(bitmap.free_extents(), vec![(10, 4), (24, 8)]);
+    }
+    
+    #[test]
+    fn test_parse_boot_sector_large_clusters_and_corruption() {
+        // 4K-native volume with 256 KiB clusters: 64 sectors, written as 2^(256 - 0xFA)
+        let mut sector = vec![0u8; 4096];
+        sector[3..11].copy_from_slice(b"NTFS    ");
+        sector[0x0B..0x0D].copy_from_slice(&4096u16.to_le_bytes());
+        sector[0x0D] = 0xFA;
+        sector[0x28..0x30].copy_from_slice(&(64u64 * 1000).to_le_bytes());
+        sector[0x30..0x38].copy_from_slice(&4u64.to_le_bytes());
+        sector[0x38..0x40].copy_from_slice(&2u64.to_le_bytes());
+        sector[0x40] = 0xF4; // 4096-byte records
+        sector[0x44] = 0xF4;
+        sector[0x48..0x50].copy_from_slice(&0x1234_5678_9ABC_DEF0u64.to_le_bytes());
+        
+        let boot = parse_boot_sector(&sector).unwrap();
+        assert_eq!((boot.sectors_per_cluster, boot.cluster_size), (64, 256 * 1024));
+        assert_eq!((boot.mft_record_size, boot.index_record_size), (4096, 4096));
+        assert_eq!(boot.volume_serial, 0x1234_5678_9ABC_DEF0);
+        
+        let corrupt = |offset: usize, bytes: &[u8]| {
+            let mut bad = sector.clone();
+            bad[offset..offset + bytes.len()].copy_from_slice(bytes);
+            parse_boot_sector(&bad)
+        };
+        assert!(corrupt(0x0B, &600u16.to_le_bytes()).is_none()); // Bytes per sector
+        assert!(corrupt(0x0D, &[0x90]).is_none()); // Sectors per cluster
+        assert!(corrupt(0x0D, &[0xF4]).is_none()); // 16 MiB clusters
+        assert!(corrupt(0x28, &0u64.to_le_bytes()).is_none()); // Total sectors
+        assert!(corrupt(0x30, &1000u64.to_le_bytes()).is_none()); // $MFT past the volume end
+        assert!(corrupt(0x44, &[0x00]).is_none()); // Clusters per index record
+    }
 }
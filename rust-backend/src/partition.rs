@@ -0,0 +1,1088 @@
+//! Partition Table Module
+//! Parses MBR (including extended/logical EBR chains) and GPT partition tables
+//! so whole-disk images and physical drives can be scanned per partition
+//!
+//! GPT headers and entry arrays are validated with CRC32; a damaged primary
+//! GPT falls back to the backup copy at the end of the disk.
+//!
+//! Lost partitions (repartitioned / quick-formatted disks) are found by sweeping
+//! the device for NTFS, FAT and exFAT boot records and their backups.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::disk_reader::{DiskReader, ScanSource};
+use crate::ntfs_parser::{parse_boot_sector, parse_mft_record, NtfsBootSector};
+
+const MBR_SIGNATURE: [u8; 2] = [0x55, 0xAA];
+const MBR_TYPE_GPT_PROTECTIVE: u8 = 0xEE;
+const MAX_LOGICAL_PARTITIONS: usize = 128;
+const MAX_GPT_ENTRIES: usize = 1024;
+const SEARCH_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// One partition found in a partition table
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PartitionEntry {
+    pub index: usize,            // 1-based, as accepted by --partition
+    pub scheme: String,          // "mbr" or "gpt"
+    pub start_lba: u64,
+    pub sector_count: u64,
+    pub offset: u64,             // Byte offset of the partition on the disk
+    pub size: u64,
+    pub type_id: String,         // MBR type byte ("0x07") or GPT type GUID
+    pub type_name: String,
+    pub name: String,            // GPT partition name (empty for MBR)
+    pub unique_guid: Option<String>,
+    pub bootable: bool,
+    pub is_logical: bool,        // Inside an MBR extended partition
+    pub filesystem: String,      // Detected from the partition's first sector
+}
+
+/// Partition layout of a disk or whole-disk image
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PartitionTable {
+    pub scheme: String,          // "mbr", "gpt" or "none" (unpartitioned volume)
+    pub sector_size: u32,
+    pub disk_size: u64,
+    pub disk_guid: Option<String>,
+    pub partitions: Vec<PartitionEntry>,
+    pub warnings: Vec<String>,
+}
+
+/// A volume found by the lost-partition search
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PartitionCandidate {
+    pub offset: u64,             // Volume start in bytes (pass to --offset)
+    pub size: u64,
+    pub filesystem: String,      // "NTFS", "FAT12/16/32" or "exFAT"
+    pub confidence: u8,          // 0-100
+    pub found_via: Vec<String>,  // "primary_boot_sector" / "backup_boot_sector"
+    pub evidence: Vec<String>,
+    pub cluster_size: u32,
+    pub volume_serial: String,
+    pub partition_index: Option<usize>, // Matching entry in the current partition table
+}
+
+/// Result of a lost-partition search
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PartitionSearchResult {
+    pub success: bool,
+    pub message: String,
+    pub disk_size: u64,
+    pub sector_size: u32,
+    pub sectors_scanned: u64,
+    pub unreadable_bytes: u64,
+    pub scan_duration_ms: u64,
+    pub candidates: Vec<PartitionCandidate>,
+}
+
+/// Where a mounted volume sits on its physical disk
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VolumeLocation {
+    pub disk_number: u32,
+    pub disk_path: String,
+    pub offset: u64,
+    pub length: u64,
+    pub partition_index: Option<usize>,
+    pub layout: Option<PartitionTable>,
+}
+
+/// Read the partition table of a disk opened at offset 0
+pub fn read_partition_table(disk: &mut DiskReader) -> Result<PartitionTable, String> {
+    let sector_size = disk.sector_size();
+    let sector0 = disk.read_at(0, sector_size)?;
+    if sector0.len() < 512 {
+        return Err("Disk is too small to hold a partition table".to_string());
+    }
+
+    let mut table = PartitionTable {
+        scheme: "none".to_string(),
+        sector_size: sector_size as u32,
+        disk_size: disk.size(),
+        disk_guid: None,
+        partitions: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    // A volume boot record also ends in 55 AA: an unpartitioned volume is not an MBR
+    if sector0[510..512] != MBR_SIGNATURE || !detect_filesystem(&sector0).is_empty() {
+        return Ok(table);
+    }
+
+    let primary = parse_mbr_entries(&sector0);
+    if primary.iter().any(|e| e.type_byte == MBR_TYPE_GPT_PROTECTIVE) {
+        match read_gpt(disk, &mut table) {
+            Ok(()) => {
+                table.scheme = "gpt".to_string();
+                fill_filesystems(disk, &mut table);
+                return Ok(table);
+            }
+            Err(e) => table.warnings.push(format!("Protective MBR present but GPT unreadable: {}", e)),
+        }
+    }
+
+    if primary.iter().all(|e| e.type_byte == 0) || primary.iter().any(|e| e.status & 0x7F != 0) {
+        return Ok(table);
+    }
+
+    table.scheme = "mbr".to_string();
+    read_mbr(disk, &primary, &mut table)?;
+    fill_filesystems(disk, &mut table);
+    Ok(table)
+}
+
+/// Point a whole-disk source at one of its partitions (1-based index)
+pub fn select_partition(source: &ScanSource, index: usize) -> Result<ScanSource, String> {
+    let mut disk = source.open()?;
+    let table = read_partition_table(&mut disk)?;
+
+    let partition = table
+        .partitions
+        .iter()
+        .find(|p| p.index == index)
+        .ok_or_else(|| {
+            format!(
+                "Partition {} not found ({} partition table with {} partitions)",
+                index, table.scheme, table.partitions.len()
+            )
+        })?;
+
+    eprintln!(
+        "[PARTITION] Selected partition {}: {} ({}) at offset {}",
+        partition.index, partition.type_name, partition.filesystem, partition.offset
+    );
+    Ok(source.at_offset(partition.offset))
+}
+
+/// One-line-per-partition summary for error messages
+pub fn describe_partitions(table: &PartitionTable) -> String {
+    table
+        .partitions
+        .iter()
+        .map(|p| {
+            format!(
+                "  {}: {} {} at offset {} ({} bytes)",
+                p.index,
+                p.type_name,
+                if p.filesystem.is_empty() { "-" } else { &p.filesystem },
+                p.offset,
+                p.size
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// ============================================================================
+// MBR
+// ============================================================================
+
+struct MbrRecord {
+    status: u8,
+    type_byte: u8,
+    start: u64,
+    sectors: u64,
+}
+
+fn parse_mbr_entries(sector: &[u8]) -> Vec<MbrRecord> {
+    (0..4)
+        .map(|i| {
+            let e = &sector[446 + i * 16..446 + (i + 1) * 16];
+            MbrRecord {
+                status: e[0],
+                type_byte: e[4],
+                start: u32::from_le_bytes([e[8], e[9], e[10], e[11]]) as u64,
+                sectors: u32::from_le_bytes([e[12], e[13], e[14], e[15]]) as u64,
+            }
+        })
+        .collect()
+}
+
+fn is_extended(type_byte: u8) -> bool {
+    matches!(type_byte, 0x05 | 0x0F | 0x85)
+}
+
+fn read_mbr(disk: &mut DiskReader, primary: &[MbrRecord], table: &mut PartitionTable) -> Result<(), String> {
+    let sector_size = table.sector_size as u64;
+    let mut logical = Vec::new();
+
+    for record in primary.iter().filter(|r| r.type_byte != 0 && r.sectors > 0) {
+        if is_extended(record.type_byte) {
+            read_ebr_chain(disk, record.start, sector_size, &mut logical, &mut table.warnings);
+            continue;
+        }
+        table.partitions.push(mbr_partition(record, record.start, sector_size, false));
+    }
+    table.partitions.extend(logical);
+
+    for (i, partition) in table.partitions.iter_mut().enumerate() {
+        partition.index = i + 1;
+        if partition.offset + partition.size > table.disk_size {
+            table.warnings.push(format!("Partition {} extends beyond the end of the disk", i + 1));
+        }
+    }
+    Ok(())
+}
+
+/// Walk the EBR linked list of an extended partition.
+/// Logical entries are relative to their EBR; next-EBR links are relative to the extended partition start.
+fn read_ebr_chain(
+    disk: &mut DiskReader,
+    extended_start: u64,
+    sector_size: u64,
+    partitions: &mut Vec<PartitionEntry>,
+    warnings: &mut Vec<String>,
+) {
+    let mut ebr_lba = extended_start;
+    let mut visited = Vec::new();
+
+    while partitions.len() < MAX_LOGICAL_PARTITIONS {
+        if visited.contains(&ebr_lba) {
+            warnings.push(format!("EBR chain loops back to LBA {}", ebr_lba));
+            break;
+        }
+        visited.push(ebr_lba);
+
+        let sector = match disk.read_at(ebr_lba * sector_size, sector_size as usize) {
+            Ok(data) if data.len() >= 512 && data[510..512] == MBR_SIGNATURE => data,
+            _ => {
+                warnings.push(format!("Invalid EBR at LBA {}", ebr_lba));
+                break;
+            }
+        };
+
+        let entries = parse_mbr_entries(&sector);
+        if entries[0].type_byte != 0 && entries[0].sectors > 0 {
+            partitions.push(mbr_partition(&entries[0], ebr_lba + entries[0].start, sector_size, true));
+        }
+        if !is_extended(entries[1].type_byte) || entries[1].start == 0 {
+            break;
+        }
+        ebr_lba = extended_start + entries[1].start;
+    }
+}
+
+fn mbr_partition(record: &MbrRecord, start_lba: u64, sector_size: u64, is_logical: bool) -> PartitionEntry {
+    PartitionEntry {
+        index: 0,
+        scheme: "mbr".to_string(),
+        start_lba,
+        sector_count: record.sectors,
+        offset: start_lba * sector_size,
+        size: record.sectors * sector_size,
+        type_id: format!("0x{:02X}", record.type_byte),
+        type_name: mbr_type_name(record.type_byte).to_string(),
+        name: String::new(),
+        unique_guid: None,
+        bootable: record.status == 0x80,
+        is_logical,
+        filesystem: String::new(),
+    }
+}
+
+fn mbr_type_name(type_byte: u8) -> &'static str {
+    match type_byte {
+        0x01 => "FAT12",
+        0x04 | 0x06 | 0x0E => "FAT16",
+        0x07 => "NTFS/exFAT",
+        0x0B | 0x0C => "FAT32",
+        0x11 | 0x14 | 0x16 | 0x1E => "Hidden FAT",
+        0x17 => "Hidden NTFS",
+        0x1B | 0x1C => "Hidden FAT32",
+        0x27 => "Windows Recovery",
+        0x42 => "Windows Dynamic Disk",
+        0x82 => "Linux swap",
+        0x83 => "Linux",
+        0x8E => "Linux LVM",
+        0xA5 | 0xA6 | 0xA9 => "BSD",
+        0xAF => "Apple HFS+",
+        0xEE => "GPT protective",
+        0xEF => "EFI System",
+        0xFD => "Linux RAID",
+        _ => "Unknown",
+    }
+}
+
+// ============================================================================
+// GPT
+// ============================================================================
+
+fn read_gpt(disk: &mut DiskReader, table: &mut PartitionTable) -> Result<(), String> {
+    let mut sector_size = table.sector_size as u64;
+
+    // 4Kn disks exposed through 512-byte reads keep the header at byte 4096
+    let mut primary = read_gpt_header(disk, sector_size, 1);
+    if primary.is_err() && sector_size == 512 {
+        if let Ok(header) = read_gpt_header(disk, 4096, 1) {
+            sector_size = 4096;
+            table.sector_size = 4096;
+            primary = Ok(header);
+        }
+    }
+
+    let (header, entries) = match primary.and_then(|h| read_gpt_entries(disk, sector_size, &h).map(|e| (h, e))) {
+        Ok(found) => found,
+        Err(primary_error) => {
+            let Some(last_lba) = (disk.size() / sector_size).checked_sub(1) else {
+                return Err(primary_error);
+            };
+            let backup = read_gpt_header(disk, sector_size, last_lba)
+                .and_then(|h| read_gpt_entries(disk, sector_size, &h).map(|e| (h, e)))
+                .map_err(|e| format!("primary: {}; backup: {}", primary_error, e))?;
+            table.warnings.push(format!("Primary GPT invalid ({}), using backup GPT", primary_error));
+            backup
+        }
+    };
+
+    table.disk_guid = Some(format_guid(&header[56..72]));
+
+    for entry in entries.chunks_exact(u32::from_le_bytes([header[84], header[85], header[86], header[87]]) as usize) {
+        let type_guid = &entry[0..16];
+        if type_guid.iter().all(|&b| b == 0) {
+            continue;
+        }
+        let first = u64::from_le_bytes(entry[32..40].try_into().unwrap());
+        let last = u64::from_le_bytes(entry[40..48].try_into().unwrap());
+        if last < first {
+            table.warnings.push(format!("GPT entry with inverted range {}..{} skipped", first, last));
+            continue;
+        }
+        let attributes = u64::from_le_bytes(entry[48..56].try_into().unwrap());
+        let name_units: Vec<u16> = entry[56..128]
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .take_while(|&u| u != 0)
+            .collect();
+
+        let type_id = format_guid(type_guid);
+        table.partitions.push(PartitionEntry {
+            index: table.partitions.len() + 1,
+            scheme: "gpt".to_string(),
+            start_lba: first,
+            sector_count: last - first + 1,
+            offset: first * sector_size,
+            size: (last - first + 1) * sector_size,
+            type_name: gpt_type_name(&type_id).to_string(),
+            type_id,
+            name: String::from_utf16_lossy(&name_units),
+            unique_guid: Some(format_guid(&entry[16..32])),
+            bootable: attributes & (1 << 2) != 0, // Legacy BIOS bootable
+            is_logical: false,
+            filesystem: String::new(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Read and CRC-check the GPT header at `lba`
+fn read_gpt_header(disk: &mut DiskReader, sector_size: u64, lba: u64) -> Result<Vec<u8>, String> {
+    let sector = disk.read_at(lba * sector_size, sector_size as usize)?;
+    if sector.len() < 92 || &sector[0..8] != b"EFI PART" {
+        return Err(format!("no GPT signature at LBA {}", lba));
+    }
+
+    let header_size = u32::from_le_bytes([sector[12], sector[13], sector[14], sector[15]]) as usize;
+    if !(92..=sector.len()).contains(&header_size) {
+        return Err(format!("invalid GPT header size {}", header_size));
+    }
+    let stored_crc = u32::from_le_bytes([sector[16], sector[17], sector[18], sector[19]]);
+    let mut header = sector[..header_size].to_vec();
+    header[16..20].fill(0);
+    if crc32fast::hash(&header) != stored_crc {
+        return Err(format!("GPT header CRC mismatch at LBA {}", lba));
+    }
+
+    header[16..20].copy_from_slice(&stored_crc.to_le_bytes());
+    Ok(header)
+}
+
+/// Read and CRC-check the partition entry array a header points at
+fn read_gpt_entries(disk: &mut DiskReader, sector_size: u64, header: &[u8]) -> Result<Vec<u8>, String> {
+    let entries_lba = u64::from_le_bytes(header[72..80].try_into().unwrap());
+    let count = u32::from_le_bytes([header[80], header[81], header[82], header[83]]) as usize;
+    let entry_size = u32::from_le_bytes([header[84], header[85], header[86], header[87]]) as usize;
+    let stored_crc = u32::from_le_bytes([header[88], header[89], header[90], header[91]]);
+
+    if count > MAX_GPT_ENTRIES || entry_size < 128 || !entry_size.is_multiple_of(8) {
+        return Err(format!("implausible GPT entry array ({} x {} bytes)", count, entry_size));
+    }
+
+    let length = count * entry_size;
+    let aligned = length.div_ceil(sector_size as usize) * sector_size as usize;
+    let mut entries = disk.read_at(entries_lba * sector_size, aligned)?;
+    if entries.len() < length {
+        return Err("GPT entry array is truncated".to_string());
+    }
+    entries.truncate(length);
+
+    if crc32fast::hash(&entries) != stored_crc {
+        return Err("GPT partition entry CRC mismatch".to_string());
+    }
+    Ok(entries)
+}
+
+fn gpt_type_name(type_guid: &str) -> &'static str {
+    match type_guid {
+        "C12A7328-F81F-11D2-BA4B-00A0C93EC93B" => "EFI System",
+        "E3C9E316-0B5C-4DB8-817D-F92DF00215AE" => "Microsoft Reserved",
+        "EBD0A0A2-B9E5-4433-87C0-68B6B72699C7" => "Microsoft basic data",
+        "DE94BBA4-06D1-4D40-A16A-BFD50179D6AC" => "Windows Recovery",
+        "5808C8AA-7E8F-42E0-85D2-E1E90434CFB3" => "LDM metadata",
+        "AF9B60A0-1431-4F62-BC68-3311714A69AD" => "LDM data",
+        "E75CAF8F-F680-4CEE-AFA3-B001E56EFC2D" => "Storage Spaces",
+        "21686148-6449-6E6F-744E-656564454649" => "BIOS boot",
+        "0FC63DAF-8483-4772-8E79-3D69D8477DE4" => "Linux filesystem",
+        "0657FD6D-A4AB-43C4-84E5-0933C84B4F4F" => "Linux swap",
+        "E6D6D379-F507-44C2-A23C-238F2A3DF928" => "Linux LVM",
+        "48465300-0000-11AA-AA11-00306543ECAC" => "Apple HFS+",
+        "7C3457EF-0000-11AA-AA11-00306543ECAC" => "Apple APFS",
+        _ => "Unknown",
+    }
+}
+
+/// Format a GPT (mixed-endian) GUID as the usual uppercase string
+fn format_guid(bytes: &[u8]) -> String {
+    format!(
+        "{:08X}-{:04X}-{:04X}-{}-{}",
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        u16::from_le_bytes([bytes[4], bytes[5]]),
+        u16::from_le_bytes([bytes[6], bytes[7]]),
+        hex::encode_upper(&bytes[8..10]),
+        hex::encode_upper(&bytes[10..16])
+    )
+}
+
+// ============================================================================
+// FILESYSTEM DETECTION
+// ============================================================================
+
+/// Identify the filesystem from a volume's first sector ("" when unknown)
+pub fn detect_filesystem(sector: &[u8]) -> &'static str {
+    if sector.len() < 512 {
+        return "";
+    }
+    match &sector[3..11] {
+        b"NTFS    " => return "NTFS",
+        b"EXFAT   " => return "exFAT",
+        b"-FVE-FS-" => return "BitLocker",
+        _ => {}
+    }
+    if &sector[82..87] == b"FAT32" {
+        return "FAT32";
+    }
+    if &sector[54..59] == b"FAT12" {
+        return "FAT12";
+    }
+    if &sector[54..57] == b"FAT" {
+        return "FAT16";
+    }
+    ""
+}
+
+fn fill_filesystems(disk: &mut DiskReader, table: &mut PartitionTable) {
+    let sector_size = table.sector_size as usize;
+    for partition in &mut table.partitions {
+        if let Ok(sector) = disk.read_at(partition.offset, sector_size) {
+            partition.filesystem = detect_filesystem(&sector).to_string();
+        }
+    }
+}
+
+// ============================================================================
+// LOST PARTITION SEARCH
+// ============================================================================
+
+/// Sweep a whole disk (image or device) for NTFS, FAT and exFAT boot records.
+/// Each boot record is tried both as a volume's primary boot sector and as its
+/// backup, and every hypothesis is cross-checked against on-disk structures.
+pub fn find_partitions(source: &ScanSource) -> PartitionSearchResult {
+    let start_time = std::time::Instant::now();
+    let mut result = PartitionSearchResult {
+        success: false,
+        message: String::new(),
+        disk_size: 0,
+        sector_size: 0,
+        sectors_scanned: 0,
+        unreadable_bytes: 0,
+        scan_duration_ms: 0,
+        candidates: Vec::new(),
+    };
+
+    let mut disk = match source.open() {
+        Ok(disk) => disk,
+        Err(e) => {
+            result.message = e;
+            return result;
+        }
+    };
+    let sector_size = disk.sector_size();
+    let disk_size = disk.size();
+    result.disk_size = disk_size;
+    result.sector_size = sector_size as u32;
+
+    let table = read_partition_table(&mut disk).ok();
+    let mut found: BTreeMap<(u64, String), PartitionCandidate> = BTreeMap::new();
+    let chunk_size = (SEARCH_CHUNK_SIZE / sector_size).max(1) * sector_size;
+    let mut offset = 0u64;
+    let mut next_progress = 0u64;
+
+    eprintln!("[PARTITION] Searching {} bytes for lost volumes...", disk_size);
+
+    while offset < disk_size {
+        let length = chunk_size.min((disk_size - offset) as usize);
+        let data = match disk.read_at(offset, length) {
+            Ok(data) if !data.is_empty() => data,
+            _ => {
+                // Unreadable area: keep going, boot records may exist further on
+                result.unreadable_bytes += length as u64;
+                offset += length as u64;
+                continue;
+            }
+        };
+
+        for position in (0..data.len().saturating_sub(511)).step_by(sector_size) {
+            let sector = &data[position..position + 512];
+            if sector[510..512] != MBR_SIGNATURE {
+                continue;
+            }
+            let absolute = offset + position as u64;
+            let candidates = match &sector[3..11] {
+                b"NTFS    " => ntfs_candidates(&mut disk, sector, absolute),
+                b"EXFAT   " => exfat_candidates(&mut disk, sector, absolute),
+                _ => fat_candidates(&mut disk, sector, absolute),
+            };
+            for candidate in candidates {
+                merge_candidate(&mut found, candidate);
+            }
+        }
+
+        result.sectors_scanned += (data.len() / sector_size) as u64;
+        offset += data.len() as u64;
+        if offset >= next_progress {
+            eprintln!(
+                "[PARTITION] {:.1}% scanned, {} candidate(s)",
+                offset as f64 * 100.0 / disk_size as f64,
+                found.len()
+            );
+            next_progress += 1024 * 1024 * 1024;
+        }
+    }
+
+    let mut candidates: Vec<PartitionCandidate> = found.into_values().collect();
+    for candidate in &mut candidates {
+        candidate.partition_index = table
+            .as_ref()
+            .and_then(|t| t.partitions.iter().find(|p| p.offset == candidate.offset))
+            .map(|p| p.index);
+        if candidate.offset + candidate.size > disk_size {
+            candidate.evidence.push("Volume extends past the end of the disk".to_string());
+        } else {
+            candidate.confidence = candidate.confidence.saturating_add(10);
+        }
+        candidate.confidence = candidate.confidence.min(100);
+    }
+
+    result.success = true;
+    result.message = format!(
+        "Found {} candidate volume(s); scan one with --offset <offset>",
+        candidates.len()
+    );
+    result.candidates = candidates;
+    result.scan_duration_ms = start_time.elapsed().as_millis() as u64;
+    result
+}
+
+/// Combine sightings of the same volume (e.g. via its primary and its backup boot sector)
+fn merge_candidate(found: &mut BTreeMap<(u64, String), PartitionCandidate>, candidate: PartitionCandidate) {
+    match found.entry((candidate.offset, candidate.filesystem.clone())) {
+        std::collections::btree_map::Entry::Vacant(slot) => {
+            slot.insert(candidate);
+        }
+        std::collections::btree_map::Entry::Occupied(mut slot) => {
+            let existing = slot.get_mut();
+            for via in candidate.found_via {
+                if !existing.found_via.contains(&via) {
+                    existing.found_via.push(via);
+                }
+            }
+            for evidence in candidate.evidence {
+                if !existing.evidence.contains(&evidence) {
+                    existing.evidence.push(evidence);
+                }
+            }
+            existing.confidence = existing.confidence.max(candidate.confidence);
+        }
+    }
+}
+
+/// Primary/backup start hypotheses for a boot record at `position`
+fn boot_hypotheses(position: u64, backup_distance: u64) -> Vec<(u64, &'static str)> {
+    let mut hypotheses = vec![(position, "primary_boot_sector")];
+    if backup_distance > 0 && position >= backup_distance {
+        hypotheses.push((position - backup_distance, "backup_boot_sector"));
+    }
+    hypotheses
+}
+
+/// Does the disk hold the same sector at `offset`?
+fn sector_matches(disk: &mut DiskReader, offset: u64, sector: &[u8]) -> bool {
+    disk.read_at(offset, sector.len()).is_ok_and(|data| data == sector)
+}
+
+fn ntfs_candidates(disk: &mut DiskReader, sector: &[u8], position: u64) -> Vec<PartitionCandidate> {
+    let Some(boot) = parse_boot_sector(sector) else { return Vec::new() };
+    // The backup boot sector sits in the volume's last sector, just past total_sectors
+    let backup_distance = boot.total_sectors * boot.bytes_per_sector as u64;
+
+    let mut candidates = Vec::new();
+    for (start, via) in boot_hypotheses(position, backup_distance) {
+        let mut confidence = 30u8;
+        let mut evidence = vec!["NTFS boot sector parses".to_string()];
+
+        match verify_mft_record_zero(disk, start, &boot) {
+            Some(true) => {
+                confidence += 40;
+                evidence.push(format!("$MFT record 0 valid at cluster {}", boot.mft_cluster));
+            }
+            Some(false) => {
+                confidence += 15;
+                evidence.push(format!("FILE record at $MFT cluster {} (not named $MFT)", boot.mft_cluster));
+            }
+            None if via == "backup_boot_sector" => continue, // Too speculative without an MFT
+            None => {}
+        }
+
+        let (counterpart, name) = if via == "primary_boot_sector" {
+            (start + backup_distance, "Backup")
+        } else {
+            (start, "Primary")
+        };
+        if sector_matches(disk, counterpart, sector) {
+            confidence += 20;
+            evidence.push(format!("{} boot sector matches", name));
+        }
+
+        candidates.push(PartitionCandidate {
+            offset: start,
+            size: backup_distance + boot.bytes_per_sector as u64,
+            filesystem: "NTFS".to_string(),
+            confidence,
+            found_via: vec![via.to_string()],
+            evidence,
+            cluster_size: boot.cluster_size,
+            volume_serial: format!("{:016X}", boot.volume_serial),
+            partition_index: None,
+        });
+    }
+    candidates
+}
+
+/// Some(true) = record 0 is $MFT, Some(false) = FILE record of another name, None = no FILE record
+fn verify_mft_record_zero(disk: &mut DiskReader, volume_start: u64, boot: &NtfsBootSector) -> Option<bool> {
+    let mft_offset = volume_start.checked_add(boot.mft_cluster.checked_mul(boot.cluster_size as u64)?)?;
+    if mft_offset >= disk.size() {
+        return None;
+    }
+    let record = disk.read_at(mft_offset, (boot.mft_record_size as usize).max(1024)).ok()?;
+    if record.len() < 1024 || &record[0..4] != b"FILE" {
+        return None;
+    }
+    Some(parse_mft_record(&record, 0).is_some_and(|entry| entry.file_name == "$MFT"))
+}
+
+fn fat_candidates(disk: &mut DiskReader, sector: &[u8], position: u64) -> Vec<PartitionCandidate> {
+    let jump_ok = (sector[0] == 0xEB && sector[2] == 0x90) || sector[0] == 0xE9;
+    let bytes_per_sector = u16::from_le_bytes([sector[11], sector[12]]) as u64;
+    let sectors_per_cluster = sector[13] as u64;
+    let reserved = u16::from_le_bytes([sector[14], sector[15]]) as u64;
+    let fat_count = sector[16] as u64;
+    let root_entries = u16::from_le_bytes([sector[17], sector[18]]) as u64;
+    let media = sector[21];
+    let total = match u16::from_le_bytes([sector[19], sector[20]]) {
+        0 => u32::from_le_bytes([sector[32], sector[33], sector[34], sector[35]]) as u64,
+        n => n as u64,
+    };
+    let fat_size = match u16::from_le_bytes([sector[22], sector[23]]) {
+        0 => u32::from_le_bytes([sector[36], sector[37], sector[38], sector[39]]) as u64,
+        n => n as u64,
+    };
+
+    let plausible = jump_ok
+        && matches!(bytes_per_sector, 512 | 1024 | 2048 | 4096)
+        && sectors_per_cluster.is_power_of_two()
+        && reserved > 0
+        && (1..=2).contains(&fat_count)
+        && (media == 0xF0 || media >= 0xF8)
+        && total > 0
+        && fat_size > 0;
+    if !plausible {
+        return Vec::new();
+    }
+
+    // FAT type follows from the cluster count, not from the label string
+    let root_sectors = (root_entries * 32).div_ceil(bytes_per_sector);
+    let meta_sectors = reserved + fat_count * fat_size + root_sectors;
+    if meta_sectors >= total {
+        return Vec::new();
+    }
+    let clusters = (total - meta_sectors) / sectors_per_cluster;
+    let (filesystem, serial_offset) = match clusters {
+        0..=4084 => ("FAT12", 39),
+        4085..=65524 => ("FAT16", 39),
+        _ => ("FAT32", 67),
+    };
+    let backup_sector = if filesystem == "FAT32" { u16::from_le_bytes([sector[50], sector[51]]) as u64 } else { 0 };
+
+    let mut candidates = Vec::new();
+    for (start, via) in boot_hypotheses(position, backup_sector * bytes_per_sector) {
+        let mut confidence = 30u8;
+        let mut evidence = vec![format!("{} BIOS parameter block is consistent", filesystem)];
+
+        // FAT[0] carries the media descriptor followed by all-ones filler
+        let fat_offset = start + reserved * bytes_per_sector;
+        let fat_ok = disk
+            .read_at(fat_offset, 4)
+            .is_ok_and(|fat| fat.len() == 4 && fat[0] == media && fat[1] == 0xFF && (fat[2] & 0x0F) == 0x0F);
+        if fat_ok {
+            confidence += 30;
+            evidence.push("First FAT entry matches the media descriptor".to_string());
+            let second_fat = fat_offset + fat_size * bytes_per_sector;
+            if fat_count == 2 && disk.read_at(second_fat, 4).is_ok_and(|fat| fat.len() == 4 && fat[0] == media) {
+                confidence += 10;
+                evidence.push("Second FAT copy present".to_string());
+            }
+        } else if via == "backup_boot_sector" {
+            continue;
+        }
+
+        if backup_sector > 0 {
+            let (counterpart, name) = if via == "primary_boot_sector" {
+                (start + backup_sector * bytes_per_sector, "Backup")
+            } else {
+                (start, "Primary")
+            };
+            if sector_matches(disk, counterpart, sector) {
+                confidence += 20;
+                evidence.push(format!("{} boot sector matches", name));
+            }
+        }
+
+        candidates.push(PartitionCandidate {
+            offset: start,
+            size: total * bytes_per_sector,
+            filesystem: filesystem.to_string(),
+            confidence,
+            found_via: vec![via.to_string()],
+            evidence,
+            cluster_size: (sectors_per_cluster * bytes_per_sector) as u32,
+            volume_serial: format!(
+                "{:08X}",
+                u32::from_le_bytes(sector[serial_offset..serial_offset + 4].try_into().unwrap())
+            ),
+            partition_index: None,
+        });
+    }
+    candidates
+}
+
+fn exfat_candidates(disk: &mut DiskReader, sector: &[u8], position: u64) -> Vec<PartitionCandidate> {
+    let bytes_per_sector_shift = sector[108];
+    let sectors_per_cluster_shift = sector[109];
+    if sector[11..64].iter().any(|&b| b != 0)
+        || !(9..=12).contains(&bytes_per_sector_shift)
+        || sectors_per_cluster_shift > 25 - bytes_per_sector_shift
+        || !(1..=2).contains(&sector[110])
+    {
+        return Vec::new();
+    }
+    let bytes_per_sector = 1u64 << bytes_per_sector_shift;
+    let volume_length = u64::from_le_bytes(sector[72..80].try_into().unwrap());
+
+    // Main boot region = sectors 0-11, backup boot region = sectors 12-23
+    let mut candidates = Vec::new();
+    for (start, via) in boot_hypotheses(position, 12 * bytes_per_sector) {
+        let mut confidence = 30u8;
+        let mut evidence = vec!["exFAT boot sector parses".to_string()];
+
+        let Ok(region) = disk.read_at(start, 24 * bytes_per_sector as usize) else { continue };
+        if region.len() < 24 * bytes_per_sector as usize || region[..512] != sector[..512] && via == "primary_boot_sector" {
+            continue;
+        }
+        let (main, backup) = region.split_at(12 * bytes_per_sector as usize);
+
+        if exfat_boot_checksum_valid(main, bytes_per_sector as usize) {
+            confidence += 40;
+            evidence.push("Main boot region checksum valid".to_string());
+        } else if via == "backup_boot_sector" {
+            continue;
+        }
+        if main == backup {
+            confidence += 20;
+            evidence.push("Backup boot region matches".to_string());
+        }
+
+        candidates.push(PartitionCandidate {
+            offset: start,
+            size: volume_length * bytes_per_sector,
+            filesystem: "exFAT".to_string(),
+            confidence,
+            found_via: vec![via.to_string()],
+            evidence,
+            cluster_size: (bytes_per_sector << sectors_per_cluster_shift) as u32,
+            volume_serial: format!("{:08X}", u32::from_le_bytes(sector[100..104].try_into().unwrap())),
+            partition_index: None,
+        });
+    }
+    candidates
+}
+
+/// Compute the exFAT boot region checksum over sectors 0-10 (skipping the
+/// VolumeFlags and PercentInUse fields) and compare with sector 11's repeated value
+pub fn exfat_boot_checksum_valid(region: &[u8], bytes_per_sector: usize) -> bool {
+    if region.len() < 12 * bytes_per_sector {
+        return false;
+    }
+    let mut checksum = 0u32;
+    for (i, &byte) in region[..11 * bytes_per_sector].iter().enumerate() {
+        if i == 106 || i == 107 || i == 112 {
+            continue;
+        }
+        checksum = checksum.rotate_right(1).wrapping_add(byte as u32);
+    }
+    region[11 * bytes_per_sector..12 * bytes_per_sector]
+        .chunks_exact(4)
+        .all(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]) == checksum)
+}
+
+// ============================================================================
+// MOUNTED VOLUMES
+// ============================================================================
+
+/// Find the physical disk and partition behind a drive letter.
+/// `layouts` caches partition tables per disk number across drives.
+pub fn locate_volume(drive_letter: &str, layouts: &mut HashMap<u32, Option<PartitionTable>>) -> Option<VolumeLocation> {
+    let (disk_number, offset, length) = volume_disk_extent(drive_letter)?;
+    let disk_path = format!("\\\\.\\PhysicalDrive{}", disk_number);
+
+    let layout = layouts
+        .entry(disk_number)
+        .or_insert_with(|| {
+            DiskReader::open(&disk_path)
+                .and_then(|mut disk| read_partition_table(&mut disk))
+                .ok()
+        })
+        .clone();
+    let partition_index = layout
+        .as_ref()
+        .and_then(|t| t.partitions.iter().find(|p| p.offset == offset))
+        .map(|p| p.index);
+
+    Some(VolumeLocation {
+        disk_number,
+        disk_path,
+        offset,
+        length,
+        partition_index,
+        layout,
+    })
+}
+
+/// Disk number, byte offset and length of a volume's first extent
+#[cfg(windows)]
+fn volume_disk_extent(drive_letter: &str) -> Option<(u32, u64, u64)> {
+    use std::fs::OpenOptions;
+    use std::mem;
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use winapi::um::ioapiset::DeviceIoControl;
+    use winapi::um::winnt::{FILE_SHARE_READ, FILE_SHARE_WRITE};
+
+    const IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS: u32 = 0x0056_0000;
+
+    #[repr(C)]
+    #[allow(non_snake_case)]
+    struct DISK_EXTENT {
+        DiskNumber: u32,
+        StartingOffset: i64,
+        ExtentLength: i64,
+    }
+
+    #[repr(C)]
+    #[allow(non_snake_case)]
+    struct VOLUME_DISK_EXTENTS {
+        NumberOfDiskExtents: u32,
+        Extents: [DISK_EXTENT; 4],
+    }
+
+    let letter = drive_letter.trim_end_matches('\\').trim_end_matches(':');
+    let volume = OpenOptions::new()
+        .access_mode(0) // Query only: no read access (or admin) needed
+        .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE)
+        .open(format!("\\\\.\\{}:", letter))
+        .ok()?;
+
+    unsafe {
+        let mut extents: VOLUME_DISK_EXTENTS = mem::zeroed();
+        let mut bytes_returned: u32 = 0;
+        let result = DeviceIoControl(
+            volume.as_raw_handle() as *mut _,
+            IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS,
+            std::ptr::null_mut(),
+            0,
+            &mut extents as *mut _ as *mut _,
+            mem::size_of::<VOLUME_DISK_EXTENTS>() as u32,
+            &mut bytes_returned,
+            std::ptr::null_mut(),
+        );
+        if result == 0 || extents.NumberOfDiskExtents == 0 {
+            return None;
+        }
+        let extent = &extents.Extents[0];
+        Some((extent.DiskNumber, extent.StartingOffset as u64, extent.ExtentLength as u64))
+    }
+}
+
+#[cfg(not(windows))]
+fn volume_disk_extent(_drive_letter: &str) -> Option<(u32, u64, u64)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mbr_entry(sector: &mut [u8], slot: usize, type_byte: u8, start: u32, sectors: u32) {
+        let e = &mut sector[446 + slot * 16..446 + (slot + 1) * 16];
+        e[4] = type_byte;
+        e[8..12].copy_from_slice(&start.to_le_bytes());
+        e[12..16].copy_from_slice(&sectors.to_le_bytes());
+    }
+
+    fn open_test_image(name: &str, data: &[u8]) -> (std::path::PathBuf, DiskReader) {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, data).unwrap();
+        let disk = DiskReader::open_image(path.to_str().unwrap(), 0).unwrap();
+        (path, disk)
+    }
+
+    #[test]
+    fn test_mbr_with_logical_partitions() {
+        let mut image = vec![0u8; 512 * 200];
+        // Primary NTFS at LBA 8, extended at LBA 64 holding two logical partitions
+        mbr_entry(&mut image, 0, 0x07, 8, 40);
+        mbr_entry(&mut image, 1, 0x0F, 64, 120);
+        image[510..512].copy_from_slice(&MBR_SIGNATURE);
+        image[8 * 512 + 3..8 * 512 + 11].copy_from_slice(b"NTFS    ");
+
+        let ebr1 = 64 * 512;
+        mbr_entry(&mut image[ebr1..], 0, 0x0C, 2, 30);
+        mbr_entry(&mut image[ebr1..], 1, 0x05, 40, 50);
+        image[ebr1 + 510..ebr1 + 512].copy_from_slice(&MBR_SIGNATURE);
+        let ebr2 = (64 + 40) * 512;
+        mbr_entry(&mut image[ebr2..], 0, 0x83, 2, 40);
+        image[ebr2 + 510..ebr2 + 512].copy_from_slice(&MBR_SIGNATURE);
+
+        let (path, mut disk) = open_test_image("partition_test_mbr.img", &image);
+        let table = read_partition_table(&mut disk).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(table.scheme, "mbr");
+        let starts: Vec<u64> = table.partitions.iter().map(|p| p.start_lba).collect();
+        assert_eq!(starts, vec![8, 66, 106]);
+        assert_eq!(table.partitions[0].filesystem, "NTFS");
+        assert!(table.partitions[1].is_logical && table.partitions[2].is_logical);
+        assert_eq!(table.partitions[2].index, 3);
+        assert_eq!(table.partitions[2].type_name, "Linux");
+    }
+
+    #[test]
+    fn test_gpt_with_backup_fallback() {
+        let lbas = 128u64;
+        let mut image = vec![0u8; (lbas * 512) as usize];
+        mbr_entry(&mut image, 0, MBR_TYPE_GPT_PROTECTIVE, 1, (lbas - 1) as u32);
+        image[510..512].copy_from_slice(&MBR_SIGNATURE);
+
+        let mut entries = vec![0u8; 128 * 4];
+        // Microsoft basic data, LBA 34..=99, named "Data"
+        entries[0..16].copy_from_slice(&[
+            0xA2, 0xA0, 0xD0, 0xEB, 0xE5, 0xB9, 0x33, 0x44, 0x87, 0xC0, 0x68, 0xB6, 0xB7, 0x26, 0x99, 0xC7,
+        ]);
+        entries[16] = 0x42;
+        entries[32..40].copy_from_slice(&34u64.to_le_bytes());
+        entries[40..48].copy_from_slice(&99u64.to_le_bytes());
+        for (i, unit) in "Data".encode_utf16().enumerate() {
+            entries[56 + i * 2..58 + i * 2].copy_from_slice(&unit.to_le_bytes());
+        }
+
+        let header = |my_lba: u64, entries_lba: u64| {
+            let mut h = vec![0u8; 92];
+            h[0..8].copy_from_slice(b"EFI PART");
+            h[12..16].copy_from_slice(&92u32.to_le_bytes());
+            h[24..32].copy_from_slice(&my_lba.to_le_bytes());
+            h[56] = 0x11;
+            h[72..80].copy_from_slice(&entries_lba.to_le_bytes());
+            h[80..84].copy_from_slice(&4u32.to_le_bytes());
+            h[84..88].copy_from_slice(&128u32.to_le_bytes());
+            h[88..92].copy_from_slice(&crc32fast::hash(&entries).to_le_bytes());
+            let crc = crc32fast::hash(&h);
+            h[16..20].copy_from_slice(&crc.to_le_bytes());
+            h
+        };
+
+        // Primary header is corrupted after checksumming; the backup must be used
+        let mut primary = header(1, 2);
+        primary[60] ^= 0xFF;
+        image[512..604].copy_from_slice(&primary);
+        let backup_lba = lbas - 1;
+        image[(backup_lba * 512) as usize..(backup_lba * 512 + 92) as usize].copy_from_slice(&header(backup_lba, 120));
+        image[120 * 512..120 * 512 + entries.len()].copy_from_slice(&entries);
+
+        let (path, mut disk) = open_test_image("partition_test_gpt.img", &image);
+        let table = read_partition_table(&mut disk).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(table.scheme, "gpt");
+        assert_eq!(table.warnings.len(), 1);
+        assert_eq!(table.partitions.len(), 1);
+        let data = &table.partitions[0];
+        assert_eq!(data.type_name, "Microsoft basic data");
+        assert_eq!(data.type_id, "EBD0A0A2-B9E5-4433-87C0-68B6B72699C7");
+        assert_eq!(data.name, "Data");
+        assert_eq!((data.offset, data.size), (34 * 512, 66 * 512));
+    }
+
+    #[test]
+    fn test_find_fat32_volume_via_backup_boot_sector() {
+        let mut image = vec![0u8; 512 * 200];
+        let start = 100 * 512;
+        let boot = &mut image[start + 6 * 512..start + 7 * 512];
+        boot[0..3].copy_from_slice(&[0xEB, 0x58, 0x90]);
+        boot[11..13].copy_from_slice(&512u16.to_le_bytes());
+        boot[13] = 1;
+        boot[14..16].copy_from_slice(&32u16.to_le_bytes());
+        boot[16] = 2;
+        boot[21] = 0xF8;
+        boot[32..36].copy_from_slice(&70_000u32.to_le_bytes());
+        boot[36..40].copy_from_slice(&8u32.to_le_bytes());
+        boot[50..52].copy_from_slice(&6u16.to_le_bytes());
+        boot[510..512].copy_from_slice(&MBR_SIGNATURE);
+        // Primary boot sector wiped; both FAT copies still intact
+        for fat in [start + 32 * 512, start + 40 * 512] {
+            image[fat..fat + 4].copy_from_slice(&[0xF8, 0xFF, 0xFF, 0x0F]);
+        }
+
+        let path = std::env::temp_dir().join("partition_test_search.img");
+        std::fs::write(&path, &image).unwrap();
+        let result = find_partitions(&ScanSource::Image { path: path.to_str().unwrap().to_string(), offset: 0 });
+        std::fs::remove_file(&path).ok();
+
+        let best = result.candidates.iter().max_by_key(|c| c.confidence).unwrap();
+        assert_eq!(best.offset, start as u64);
+        assert_eq!(best.filesystem, "FAT32");
+        assert_eq!(best.found_via, vec!["backup_boot_sector".to_string()]);
+        assert!(best.evidence.iter().any(|e| e.contains("Second FAT")));
+    }
+
+}
@@ -9,14 +9,30 @@
 //! - Extended deleted file detection
 
 use crate::bitlocker::{get_bitlocker_status, is_admin, BitLockerStatus};
-use crate::disk_reader::{read_clusters, save_carved_file, DiskReader};
+use crate::cluster_map::{ClusterMap, CrossLink};
+use crate::directory_tree::{DirectoryRecord, DirectoryTree, TreeLeaf, MAX_PATH_DEPTH};
+use crate::disk_reader::{read_clusters, read_runs_range, save_carved_file, DiskReader, ScanSource};
+use crate::exfat::{read_boot_region, DeletedEntrySet, ExfatBootSector, ExfatTimestamps, ExfatVolume};
+use crate::fat::{parse_fat_boot_sector, scan_deleted, DeletedEntry, FatBootSector, FatVolume};
 use crate::file_carver::{build_signature_lookup, carve_sector};
-use crate::ntfs_parser::{parse_boot_sector, parse_mft_record, MftEntry, NtfsBootSector};
+use crate::logfile::{scan_logfile, ReconstructedRecord};
+use crate::mft_rebuild::{rebuild_mft_runs, scan_file_records};
+use crate::ntfs_compression::{
+    decompress_wof, read_compressed_runs, wof_chunk_size, wof_format_from_name, wof_format_name, COMPRESSION_UNIT_CLUSTERS, WOF_STREAM_NAME,
+};
+use crate::ntfs_index::{read_directory_index, IndexEntry};
+use crate::ntfs_parser::{
+    data_segments, filetime_to_unix, fixup_record, parse_attribute_list, parse_boot_sector, parse_mft_record, record_attributes,
+    resolve_attribute_list, sequence_matches, stitch_segments, AttributeSegment, ClusterBitmap, DataRun, DataStream, FileNamespace, FileTimestamps, MacbTimes, MftEntry, NtfsBootSector,
+    ATTRIBUTE_ATTRIBUTE_LIST, ATTRIBUTE_DATA,
+};
+use crate::partition::{describe_partitions, read_partition_table};
+use crate::usn_journal::{carve_usn_records, parse_usn_journal, UsnRecord, USN_JOURNAL_NAME, USN_STREAM_NAME};
 
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Scan mode for recovery operations
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -26,6 +42,13 @@ pub enum ScanMode {
     Complete,   // Full disk sector-by-sector scan
 }
 
+/// Which sectors a deep scan carves
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum CarveMode {
+    AllSectors,       // Every sector, allocated or not
+    UnallocatedOnly,  // Only clusters $Bitmap marks free - far faster on full disks
+}
+
 /// Recovery difficulty level
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum RecoveryDifficulty {
@@ -45,6 +68,15 @@ pub struct FileFragment {
     pub data_quality: u8,  // 0-100, how much of the fragment is intact
 }
 
+/// One of the names of a file linked into several directories
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HardLink {
+    pub name: String,
+    pub namespace: FileNamespace,
+    pub parent_record: u64,
+    pub path: String,
+}
+
 /// Result of a recovery scan
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RecoveryScanResult {
@@ -63,6 +95,8 @@ pub struct RecoveryScanResult {
     pub mft_records_scanned: u64,
     pub orphan_records_found: u64,
     pub requires_admin: bool,
+    #[serde(default)]
+    pub directory_tree: Option<DirectoryTree>, // Deleted files by folder, as ids into the lists above
 }
 
 /// A file that can potentially be recovered
@@ -79,7 +113,7 @@ pub struct RecoverableFile {
     pub created: String,
     pub is_deleted: bool,
     pub recovery_chance: u8,  // 0-100
-    pub source: String,       // "mft", "mft_orphan", "carved", "slack"
+    pub source: String,       // "mft", "mft_orphan", "index_slack", "logfile", "carved", "slack", "fat", "exfat", "usn_journal"
     pub sector_offset: Option<u64>,
     pub cluster_offset: Option<i64>,
     pub data_runs: Option<String>,
@@ -88,8 +122,47 @@ pub struct RecoverableFile {
     pub recoverable_bytes: u64,  // Actual bytes that can be recovered
     pub difficulty: String,      // easy, moderate, hard, very_hard
     pub age_estimate: String,    // rough estimate of when file was deleted
+    #[serde(default)]
+    pub stream_name: Option<String>,   // Set for alternate data streams ("file.docx:name")
+    #[serde(default)]
+    pub resident_data: Option<String>, // Hex-encoded content stored inside the MFT record
+    #[serde(default)]
+    pub compression: Option<String>,   // "lznt1" for NTFS-compressed files, "wof_*" for WOF/CompactOS
+    #[serde(default)]
+    pub stored_size: Option<u64>,      // Length of the WofCompressedData stream holding the content
+    #[serde(default)]
+    pub initialized_size: Option<u64>, // Valid data length when shorter than size; the rest reads as zeros
+    #[serde(default)]
+    pub mft_reference: Option<u64>,    // Original MFT reference (record + sequence) of an index_slack entry
+    #[serde(default)]
+    pub lsn: Option<u64>,              // $LogFile transaction LSN the entry was rebuilt from
+    #[serde(default)]
+    pub cross_links: Option<Vec<CrossLink>>, // Other files claiming some of this file's clusters
+    #[serde(default)]
+    pub timestamps: Option<FileTimestamps>,  // $STANDARD_INFORMATION and $FILE_NAME MACB times
+    #[serde(default)]
+    pub hard_links: Option<Vec<HardLink>>,   // Every path of a file with more than one hard link
+    #[serde(default)]
+    pub exfat_timestamps: Option<ExfatTimestamps>, // exFAT times at 10 ms precision, with their UTC offsets
+    #[serde(default)]
+    pub usn_reasons: Option<Vec<String>>,    // Reason flags of the USN record a usn_journal item came from
 }
 
+/// MFT record holding $LogFile
+const LOGFILE_RECORD: u64 = 2;
+
+/// Cap on how much of $LogFile is read (the default log is 64 MB)
+const MAX_LOGFILE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// MFT record holding $Bitmap
+const BITMAP_RECORD: u64 = 6;
+
+/// MFT record of the $Extend directory
+const EXTEND_RECORD: u64 = 11;
+
+/// $J is read in blocks of this size (a multiple of the journal page size)
+const USN_READ_CHUNK: u64 = 4 * 1024 * 1024;
+
 /// Progress callback data
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ScanProgress {
@@ -111,10 +184,45 @@ pub struct FileRecoveryResult {
     pub message: String,
 }
 
+/// Result of restoring a directory and the deleted files below it
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TreeRecoveryResult {
+    pub success: bool,
+    pub directory: String,        // Volume-relative path of the restored directory
+    pub destination_path: String,
+    pub files_recovered: usize,
+    pub files_failed: usize,
+    pub bytes_recovered: u64,
+    pub files: Vec<FileRecoveryResult>, // One outcome per file, in tree order
+    pub message: String,
+}
+
+/// MFT records read per disk request while scanning
+const MFT_BATCH_RECORDS: u64 = 4096;
+
+/// Recovery chance deducted when a deleted file's parent directory record has been reused
+pub const REUSED_PARENT_PENALTY: u8 = 20;
+
+/// MFT record of the volume's root directory
+const ROOT_DIRECTORY_RECORD: u64 = 5;
+
+/// Where the $MFT lives on disk, taken from record 0's own $DATA attribute
+struct MftLayout {
+    runs: Vec<DataRun>,
+    size: u64,
+}
+
 /// Main recovery engine
 pub struct RecoveryEngine {
     drive_letter: String,
+    source: ScanSource,
     boot_sector: Option<NtfsBootSector>,
+    mft_layout: Option<MftLayout>,
+    cluster_bitmap: Option<ClusterBitmap>,
+    directory_records: HashMap<u64, DirectoryRecord>, // From the last MFT scan, to place journal items
+    fat_volume: Option<FatVolume>,
+    exfat_volume: Option<ExfatVolume>,
+    rebuild_mft: bool,
     disk_reader: Option<DiskReader>,
     cancelled: Arc<AtomicBool>,
     files_found: Arc<AtomicU64>,
@@ -129,14 +237,42 @@ impl RecoveryEngine {
             .to_uppercase();
         
         RecoveryEngine {
+            source: ScanSource::Volume(letter.clone()),
             drive_letter: letter,
             boot_sector: None,
+            mft_layout: None,
+            cluster_bitmap: None,
+            directory_records: HashMap::new(),
+            fat_volume: None,
+            exfat_volume: None,
+            rebuild_mft: false,
             disk_reader: None,
             cancelled: Arc::new(AtomicBool::new(false)),
             files_found: Arc::new(AtomicU64::new(0)),
         }
     }
     
+    /// Create a recovery engine for any scan source (live volume, disk image or physical disk)
+    pub fn with_source(source: ScanSource) -> Self {
+        match source {
+            ScanSource::Volume(ref letter) => Self::new(letter),
+            ScanSource::Image { .. } | ScanSource::Device { .. } => RecoveryEngine {
+                drive_letter: source.label(),
+                source,
+                boot_sector: None,
+                mft_layout: None,
+                cluster_bitmap: None,
+                directory_records: HashMap::new(),
+                fat_volume: None,
+                exfat_volume: None,
+                rebuild_mft: false,
+                disk_reader: None,
+                cancelled: Arc::new(AtomicBool::new(false)),
+                files_found: Arc::new(AtomicU64::new(0)),
+            },
+        }
+    }
+    
     /// Check if admin privileges are available
     pub fn check_admin(&self) -> bool {
         is_admin()
@@ -147,45 +283,198 @@ impl RecoveryEngine {
         get_bitlocker_status(&self.drive_letter)
     }
     
+    /// BitLocker status to report in scan results (only applies to drive letters)
+    fn bitlocker_for_result(&self) -> Option<BitLockerStatus> {
+        self.source.drive_letter().map(|_| self.check_bitlocker())
+    }
+    
+    /// Root prefix for display paths: "C:" for volumes, the file name for images
+    fn path_root(&self) -> String {
+        self.source.label()
+    }
+    
     /// Initialize disk access
     pub fn initialize(&mut self) -> Result<(), String> {
-        // Check admin privileges
-        if !is_admin() {
+        // Image files need neither admin rights nor BitLocker checks
+        if !self.source.is_image() && !is_admin() {
             return Err("Administrator privileges required. Please run as Administrator.".to_string());
         }
         
         // Check BitLocker status
-        let bl_status = self.check_bitlocker();
-        if bl_status.is_locked {
+        if self.bitlocker_for_result().is_some_and(|s| s.is_locked) {
             return Err(format!(
                 "Drive {} is BitLocker encrypted and locked. Please unlock it first.",
                 self.drive_letter
             ));
         }
         
-        // Open disk for raw access
-        let volume_path = format!("\\\\.\\{}:", self.drive_letter);
-        let mut disk = DiskReader::open(&volume_path)?;
+        // Open disk (or image) for raw access
+        let mut disk = self.source.open()?;
         
         // Read and parse boot sector
         eprintln!("DEBUG: Reading boot sector...");
         let boot_data = disk.read_boot_sector()?;
         self.boot_sector = parse_boot_sector(&boot_data);
         
+        if self.boot_sector.is_none() {
+            if let Some(boot) = parse_fat_boot_sector(&boot_data) {
+                return self.initialize_fat(disk, boot);
+            }
+            if let Some(boot) = read_boot_region(&mut disk) {
+                return self.initialize_exfat(disk, boot);
+            }
+        }
+        
+        if self.boot_sector.is_none() && !starts_with_partition_table(&mut disk) {
+            eprintln!("DEBUG: Primary boot sector unusable, trying the backup at the end of the volume");
+            self.boot_sector = self.read_backup_boot_sector(&mut disk);
+        }
+        
         if let Some(ref boot) = self.boot_sector {
             eprintln!("DEBUG: Boot sector parsed successfully");
+            if boot.bytes_per_sector as usize != disk.sector_size() {
+                eprintln!("  - Sector size: {} bytes (device reports {})", boot.bytes_per_sector, disk.sector_size());
+                disk.set_sector_size(boot.bytes_per_sector as usize);
+            }
             eprintln!("  - Cluster size: {} bytes", boot.cluster_size);
             eprintln!("  - MFT cluster: {}", boot.mft_cluster);
             eprintln!("  - MFT record size: {} bytes", boot.mft_record_size);
+            eprintln!("  - Volume serial: {:016X}", boot.volume_serial);
+            if boot.total_sectors * boot.bytes_per_sector as u64 > disk.size() {
+                eprintln!("DEBUG: Volume is larger than the source ({} sectors), it may be truncated", boot.total_sectors);
+            }
         } else {
             eprintln!("DEBUG: Failed to parse boot sector");
-            return Err("Failed to parse NTFS boot sector. Drive may not be NTFS formatted.".to_string());
+            
+            // Whole-disk images and physical drives start with a partition table
+            if let Ok(table) = read_partition_table(&mut disk) {
+                if !table.partitions.is_empty() {
+                    return Err(format!(
+                        "Source starts with a {} partition table, not an NTFS volume. Select a partition with --partition <index>:\n{}",
+                        table.scheme.to_uppercase(),
+                        describe_partitions(&table)
+                    ));
+                }
+            }
+            return Err("Failed to parse NTFS boot sector or its backup, and the volume is not FAT or exFAT either.".to_string());
+        }
+        
+        if let (Some(ref boot), true) = (&self.boot_sector, self.rebuild_mft) {
+            eprintln!("DEBUG: Sweeping volume for FILE records...");
+            let layout = rebuild_mft_layout(&mut disk, boot, &self.cancelled)?;
+            eprintln!("  - Rebuilt MFT: {} extent(s), {} bytes", layout.runs.len(), layout.size);
+            self.mft_layout = Some(layout);
+        } else if let Some(ref boot) = self.boot_sector {
+            match load_mft_layout(&mut disk, boot) {
+                Ok(layout) => {
+                    eprintln!("  - MFT: {} extent(s), {} bytes", layout.runs.len(), layout.size);
+                    self.mft_layout = Some(layout);
+                }
+                Err(e) => eprintln!("DEBUG: Could not map $MFT extents ({}), assuming a contiguous MFT", e),
+            }
+        }
+        
+        self.disk_reader = Some(disk);
+        
+        match self.read_cluster_bitmap() {
+            Ok(bitmap) => {
+                eprintln!("  - $Bitmap: {} clusters", bitmap.total_clusters());
+                self.cluster_bitmap = Some(bitmap);
+            }
+            Err(e) => eprintln!("DEBUG: Could not load $Bitmap ({}), scoring without it", e),
+        }
+        Ok(())
+    }
+    
+    /// FAT12/16/32 volumes have no MFT; their allocation table stands in for $Bitmap
+    fn initialize_fat(&mut self, mut disk: DiskReader, boot: FatBootSector) -> Result<(), String> {
+        eprintln!("DEBUG: {} boot sector parsed successfully", boot.fat_type.name());
+        if boot.bytes_per_sector as usize != disk.sector_size() {
+            eprintln!("  - Sector size: {} bytes (device reports {})", boot.bytes_per_sector, disk.sector_size());
+            disk.set_sector_size(boot.bytes_per_sector as usize);
+        }
+        eprintln!("  - Cluster size: {} bytes", boot.cluster_size);
+        eprintln!("  - Clusters: {}", boot.cluster_count);
+        eprintln!("  - Volume serial: {:08X}", boot.volume_serial);
+        if boot.total_sectors * boot.bytes_per_sector as u64 > disk.size() {
+            eprintln!("DEBUG: Volume is larger than the source ({} sectors), it may be truncated", boot.total_sectors);
+        }
+        
+        self.fat_volume = Some(FatVolume::open(&mut disk, boot)?);
+        self.disk_reader = Some(disk);
+        Ok(())
+    }
+    
+    /// exFAT keeps its own allocation bitmap and up-case table in the root directory
+    fn initialize_exfat(&mut self, mut disk: DiskReader, boot: ExfatBootSector) -> Result<(), String> {
+        eprintln!("DEBUG: exFAT boot region parsed successfully");
+        if boot.bytes_per_sector as usize != disk.sector_size() {
+            eprintln!("  - Sector size: {} bytes (device reports {})", boot.bytes_per_sector, disk.sector_size());
+            disk.set_sector_size(boot.bytes_per_sector as usize);
+        }
+        eprintln!("  - Cluster size: {} bytes", boot.cluster_size);
+        eprintln!("  - Clusters: {}", boot.cluster_count);
+        eprintln!("  - Volume serial: {:08X}", boot.volume_serial);
+        if boot.volume_length * boot.bytes_per_sector as u64 > disk.size() {
+            eprintln!("DEBUG: Volume is larger than the source ({} sectors), it may be truncated", boot.volume_length);
         }
         
+        self.exfat_volume = Some(ExfatVolume::open(&mut disk, boot)?);
         self.disk_reader = Some(disk);
         Ok(())
     }
     
+    /// Map the $MFT by sweeping the volume for FILE records instead of trusting
+    /// record 0, for volumes where it and its $MFTMirr copy are lost
+    pub fn enable_mft_rebuild(&mut self) {
+        self.rebuild_mft = true;
+    }
+    
+    /// The backup boot sector occupies the volume's last sector; find it from
+    /// the partition's length when the source is a partition of a larger disk
+    fn read_backup_boot_sector(&self, disk: &mut DiskReader) -> Option<NtfsBootSector> {
+        let volume_size = match self.source {
+            ScanSource::Image { offset, .. } | ScanSource::Device { offset, .. } if offset > 0 => self.source.at_offset(0)
+                .open()
+                .and_then(|mut whole| read_partition_table(&mut whole))
+                .ok()
+                .and_then(|table| table.partitions.iter().find(|p| p.offset == offset).map(|p| p.size))
+                .unwrap_or(disk.size()),
+            _ => disk.size(),
+        };
+        
+        let mut sector_sizes = vec![disk.sector_size() as u64, 512, 4096];
+        sector_sizes.sort_unstable();
+        sector_sizes.dedup();
+        for sector_size in sector_sizes {
+            let Some(position) = volume_size.checked_sub(sector_size) else { continue };
+            let Ok(data) = disk.read_at(position, sector_size as usize) else { continue };
+            // Only trust a copy that places itself where it was found
+            if let Some(boot) = parse_boot_sector(&data)
+                .filter(|b| b.total_sectors * b.bytes_per_sector as u64 == position)
+            {
+                eprintln!("DEBUG: Using backup boot sector at byte {}", position);
+                return Some(boot);
+            }
+        }
+        None
+    }
+    
+    /// Read the volume's cluster allocation bitmap through MFT record 6
+    fn read_cluster_bitmap(&mut self) -> Result<ClusterBitmap, String> {
+        let bitmap = self.read_mft_entry(BITMAP_RECORD)?;
+        let boot = self.boot_sector.as_ref()
+            .ok_or("Boot sector not initialized")?;
+        let disk = self.disk_reader.as_mut()
+            .ok_or("Disk reader not initialized")?;
+        if bitmap.data_runs.is_empty() {
+            return Err("$Bitmap has no data runs".to_string());
+        }
+        
+        let bits = read_runs_range(disk, &bitmap.data_runs, boot.cluster_size, 0, bitmap.file_size as usize)?;
+        Ok(ClusterBitmap::new(bits))
+    }
+    
     /// Perform a quick scan (MFT only)
     pub fn quick_scan(&mut self) -> Result<RecoveryScanResult, String> {
         let start_time = std::time::Instant::now();
@@ -202,7 +491,7 @@ impl RecoveryEngine {
             message: String::new(),
             scan_mode: "Quick".to_string(),
             drive: self.drive_letter.clone(),
-            bitlocker_status: Some(self.check_bitlocker()),
+            bitlocker_status: self.bitlocker_for_result(),
             mft_entries: Vec::new(),
             carved_files: Vec::new(),
             orphan_files: Vec::new(),
@@ -213,22 +502,36 @@ impl RecoveryEngine {
             mft_records_scanned: 0,
             orphan_records_found: 0,
             requires_admin: false,
+            directory_tree: None,
         };
         
-        // Scan MFT for deleted entries
-        eprintln!("DEBUG: Starting MFT scan...");
-        match self.scan_mft_extended(false) {
-            Ok((files, orphans, records_scanned)) => {
-                eprintln!("DEBUG: MFT scan returned {} files, {} orphans", files.len(), orphans.len());
-                result.mft_entries = files;
-                result.orphan_files = orphans;
-                result.mft_records_scanned = records_scanned;
-                result.orphan_records_found = result.orphan_files.len() as u64;
-            }
-            Err(e) => {
-                eprintln!("DEBUG: MFT scan error: {}", e);
-                return Err(e);
+        if self.fat_volume.is_some() {
+            // FAT keeps no journals; deleted entries are all there is
+            eprintln!("DEBUG: Starting FAT directory scan...");
+            result.mft_entries = self.scan_fat()?;
+        } else if self.exfat_volume.is_some() {
+            eprintln!("DEBUG: Starting exFAT directory scan...");
+            result.mft_entries = self.scan_exfat()?;
+        } else {
+            // Scan MFT for deleted entries
+            eprintln!("DEBUG: Starting MFT scan...");
+            match self.scan_mft_extended(false) {
+                Ok((files, orphans, records_scanned, tree)) => {
+                    eprintln!("DEBUG: MFT scan returned {} files, {} orphans", files.len(), orphans.len());
+                    result.mft_entries = files;
+                    result.orphan_files = orphans;
+                    result.mft_records_scanned = records_scanned;
+                    result.directory_tree = Some(tree);
+                    result.orphan_records_found = result.orphan_files.len() as u64;
+                }
+                Err(e) => {
+                    eprintln!("DEBUG: MFT scan error: {}", e);
+                    return Err(e);
+                }
             }
+            
+            self.merge_logfile(&mut result);
+            self.merge_usn_journal(&mut result, Vec::new());
         }
         
         result.total_files = result.mft_entries.len() + result.orphan_files.len();
@@ -247,7 +550,7 @@ impl RecoveryEngine {
     }
     
     /// Perform a deep scan (MFT + carving)
-    pub fn deep_scan(&mut self, max_sectors: Option<u64>) -> Result<RecoveryScanResult, String> {
+    pub fn deep_scan(&mut self, max_sectors: Option<u64>, carve_mode: CarveMode) -> Result<RecoveryScanResult, String> {
         let start_time = std::time::Instant::now();
         
         self.initialize()?;
@@ -257,7 +560,7 @@ impl RecoveryEngine {
             message: String::new(),
             scan_mode: "Deep".to_string(),
             drive: self.drive_letter.clone(),
-            bitlocker_status: Some(self.check_bitlocker()),
+            bitlocker_status: self.bitlocker_for_result(),
             mft_entries: Vec::new(),
             carved_files: Vec::new(),
             orphan_files: Vec::new(),
@@ -268,19 +571,31 @@ impl RecoveryEngine {
             mft_records_scanned: 0,
             orphan_records_found: 0,
             requires_admin: false,
+            directory_tree: None,
         };
         
-        // First: Extended MFT scan (includes orphan detection)
-        let (mft_files, orphan_files, records_scanned) = self.scan_mft_extended(true)?;
-        result.mft_entries = mft_files;
-        result.orphan_files = orphan_files;
-        result.mft_records_scanned = records_scanned;
-        result.orphan_records_found = result.orphan_files.len() as u64;
+        // First: Extended MFT scan (includes orphan detection), or the FAT/exFAT directories
+        if self.fat_volume.is_some() {
+            result.mft_entries = self.scan_fat()?;
+        } else if self.exfat_volume.is_some() {
+            result.mft_entries = self.scan_exfat()?;
+        } else {
+            let (mft_files, orphan_files, records_scanned, tree) = self.scan_mft_extended(true)?;
+            result.mft_entries = mft_files;
+            result.orphan_files = orphan_files;
+            result.mft_records_scanned = records_scanned;
+            result.directory_tree = Some(tree);
+            result.orphan_records_found = result.orphan_files.len() as u64;
+            self.merge_logfile(&mut result);
+        }
         
         // Second: File carving on free space with slack space analysis
-        let (carved, sectors) = self.carve_sectors_advanced(max_sectors)?;
+        let (carved, usn_records, sectors) = self.carve_sectors_advanced(max_sectors, carve_mode)?;
         result.carved_files = carved;
         result.sectors_scanned = sectors;
+        if self.boot_sector.is_some() {
+            self.merge_usn_journal(&mut result, usn_records);
+        }
         
         result.total_files = result.mft_entries.len() + result.carved_files.len() + result.orphan_files.len();
         result.total_recoverable_size = 
@@ -301,7 +616,7 @@ impl RecoveryEngine {
     }
     
     /// Extended MFT scanning with orphan detection and age estimation
-    fn scan_mft_extended(&mut self, deep_scan: bool) -> Result<(Vec<RecoverableFile>, Vec<RecoverableFile>, u64), String> {
+    fn scan_mft_extended(&mut self, deep_scan: bool) -> Result<(Vec<RecoverableFile>, Vec<RecoverableFile>, u64, DirectoryTree), String> {
         let boot = self.boot_sector.as_ref()
             .ok_or("Boot sector not initialized")?;
         
@@ -309,62 +624,106 @@ impl RecoveryEngine {
             .ok_or("Disk reader not initialized")?;
         
         let cluster_size = boot.cluster_size;
-        let mft_offset = boot.mft_cluster * cluster_size as u64;
-        
-        // Extended scan: read more records for older files
-        let max_records = if deep_scan { 500_000 } else { 100_000 };
         let mft_record_size = boot.mft_record_size as usize;
-        let bytes_to_read = max_records * mft_record_size;
         
-        disk.seek_bytes(mft_offset)?;
-        let mft_data = disk.read_bytes(bytes_to_read)?;
+        // Follow the MFT's own extents; only without them fall back to a capped contiguous read
+        let (mft_runs, total_records) = match self.mft_layout {
+            Some(ref layout) => (layout.runs.clone(), layout.size / mft_record_size as u64),
+            None => {
+                let max_records: u64 = if deep_scan { 500_000 } else { 100_000 };
+                let clusters = (max_records * mft_record_size as u64).div_ceil(cluster_size as u64);
+                (vec![DataRun { cluster_offset: boot.mft_cluster as i64, cluster_count: clusters }], max_records)
+            }
+        };
         
         let mut files = Vec::new();
         let mut orphan_files = Vec::new();
-        let actual_records = mft_data.len() / mft_record_size;
+        let mut actual_records = 0u64;
         
         let mut total_parsed = 0;
         let mut deleted_count = 0;
         let mut system_files = 0;
         let mut directories = 0;
         
-        // Track parent references to detect orphans
-        let mut parent_refs: HashMap<u64, bool> = HashMap::new();
+        // Every directory record, to detect orphans, resolve hard-link paths and rebuild the tree
+        let mut directory_records: HashMap<u64, DirectoryRecord> = HashMap::new();
+        // Deleted files by the directory reference they were found under
+        let mut leaves: Vec<TreeLeaf> = Vec::new();
         let mut record_entries: Vec<(u64, MftEntry)> = Vec::new();
+        // Stale $I30 entries carved from directory index slack (deep scan only)
+        let mut index_slack: Vec<IndexEntry> = Vec::new();
+        // Clusters claimed by every entry, live or deleted, to find cross-linked files
+        let mut cluster_map = ClusterMap::new();
         
         // First pass: collect all entries and parent references
-        for i in 0..actual_records {
+        let mut first_record = 0u64;
+        while first_record < total_records {
             if self.cancelled.load(Ordering::Relaxed) {
                 break;
             }
             
-            let offset = i * mft_record_size;
-            if offset + mft_record_size > mft_data.len() {
-                break;
-            }
-            
-            let record_data = &mft_data[offset..offset + mft_record_size];
-            
-            if let Some(entry) = parse_mft_record(record_data, i as u64) {
-                total_parsed += 1;
-                
-                if entry.is_deleted {
-                    deleted_count += 1;
-                }
-                
-                if entry.file_name.starts_with('$') {
-                    system_files += 1;
+            let batch = MFT_BATCH_RECORDS.min(total_records - first_record);
+            let mft_data = match read_runs_range(
+                disk,
+                &mft_runs,
+                cluster_size,
+                first_record * mft_record_size as u64,
+                batch as usize * mft_record_size,
+            ) {
+                Ok(data) => data,
+                Err(e) => {
+                    eprintln!("Warning: Failed to read MFT records {}-{}: {}", first_record, first_record + batch - 1, e);
+                    first_record += batch;
                     continue;
                 }
+            };
+            
+            for (j, record_data) in mft_data.chunks_exact(mft_record_size).enumerate() {
+                let i = first_record + j as u64;
+                actual_records += 1;
                 
-                if entry.is_directory {
-                    directories += 1;
-                    parent_refs.insert(entry.record_number, true);
-                    continue;
+                if let Some(mut entry) = parse_mft_record(record_data, i) {
+                    total_parsed += 1;
+                    
+                    if !entry.attribute_list.is_empty() || !entry.attribute_list_runs.is_empty() {
+                        resolve_extension_records(disk, &mft_runs, cluster_size, mft_record_size, &mut entry);
+                    }
+                    // Extension records carry no name; their runs reach the map through the base record
+                    if !entry.file_name.is_empty() {
+                        map_entry_clusters(&mut cluster_map, &entry);
+                    }
+                    
+                    if entry.is_deleted {
+                        deleted_count += 1;
+                    }
+                    
+                    if entry.file_name.starts_with('$') {
+                        system_files += 1;
+                        continue;
+                    }
+                    
+                    if entry.is_directory {
+                        directories += 1;
+                        directory_records.insert(entry.record_number, DirectoryRecord {
+                            name: entry.file_name.clone(),
+                            sequence_number: entry.sequence_number,
+                            is_in_use: entry.is_in_use,
+                            parent_record: entry.parent_record,
+                            parent_sequence: entry.parent_sequence,
+                        });
+                        if deep_scan {
+                            if let Some(record) = fixup_record(record_data) {
+                                index_slack.extend(read_directory_index(disk, &record, i, cluster_size, boot.index_record_size).slack_entries);
+                            }
+                        }
+                        continue;
+                    }
+                    
+                    record_entries.push((i, entry));
                 }
-                
-                record_entries.push((i as u64, entry));
             }
+            
+            first_record += batch;
         }
         
         // Second pass: categorize files
@@ -373,14 +732,38 @@ impl RecoveryEngine {
             .unwrap_or_default()
             .as_secs() as i64;
         
+        files.extend(self.index_slack_items(index_slack, &record_entries, current_time, &mut leaves));
+        let mut cross_links = cluster_map.cross_links(cluster_size);
+        let mut cross_linked = 0;
+        
         for (record_num, entry) in record_entries {
             if !entry.is_deleted || entry.file_name.is_empty() {
                 continue;
             }
             
-            let (recovery_chance, difficulty, fragments) = self.analyze_recovery_possibility(&entry);
+            // WOF (CompactOS) files keep their content in the WofCompressedData stream
+            let wof = entry.wof_format
+                .and_then(wof_format_name)
+                .and_then(|name| entry.streams.iter().find(|s| s.name == WOF_STREAM_NAME).map(|s| (name, s)));
+            let wof_stream = wof.map(|(_, stream)| stream);
+            let (mut recovery_chance, mut difficulty, fragments) = match wof_stream {
+                Some(stream) if stream.resident_data.is_some() => resident_possibility(stream.size, !entry.is_torn),
+                Some(stream) => self.analyze_recovery_possibility(&stream.data_runs),
+                None => self.analyze_entry(&entry),
+            };
+            let data_runs = wof_stream.map(|s| &s.data_runs).unwrap_or(&entry.data_runs);
             let age_estimate = estimate_file_age(entry.modified_time, current_time);
-            let is_orphan = entry.parent_record > 0 && !parent_refs.contains_key(&(entry.parent_record as u64));
+            
+            // A parent record whose sequence moved on now holds a different directory
+            let parent = directory_records.get(&entry.parent_record);
+            let parent_reused = parent.is_some_and(|d| !sequence_matches(entry.parent_sequence, d.sequence_number, d.is_in_use));
+            let is_orphan = entry.parent_record > 0 && (parent.is_none() || parent_reused);
+            if parent_reused {
+                // The directory was deleted and its record reallocated since: the
+                // volume has seen enough churn that clusters are likely reused too
+                recovery_chance = recovery_chance.saturating_sub(REUSED_PARENT_PENALTY);
+                difficulty = difficulty_for(recovery_chance).to_string();
+            }
             
             let recoverable_bytes = if recovery_chance > 50 {
                 entry.file_size
@@ -390,33 +773,75 @@ impl RecoveryEngine {
                 0
             };
             
-            let file = RecoverableFile {
+            let mut file = RecoverableFile {
                 id: format!("mft_{}", entry.record_number),
                 name: entry.file_name.clone(),
-                path: format!("{}:\\[Deleted]\\{}", self.drive_letter, entry.file_name),
+                path: if is_orphan {
+                    format!("{}\\$OrphanFiles\\{}", self.path_root(), entry.file_name)
+                } else {
+                    format!("{}\\[Deleted]\\{}", self.path_root(), entry.file_name)
+                },
                 size: entry.file_size,
                 extension: entry.extension.clone(),
                 category: categorize_extension(&entry.extension),
                 file_type: get_file_type_name(&entry.extension),
-                modified: format_timestamp(entry.modified_time),
-                created: format_timestamp(entry.created_time),
+                modified: format_filetime(entry.si_times.modified),
+                created: format_filetime(entry.si_times.created),
                 is_deleted: true,
                 recovery_chance,
                 source: if is_orphan { "mft_orphan".to_string() } else { "mft".to_string() },
                 sector_offset: None,
-                cluster_offset: entry.data_runs.first().map(|r| r.cluster_offset),
-                data_runs: Some(serde_json::to_string(&entry.data_runs).unwrap_or_default()),
+                cluster_offset: data_runs.first().map(|r| r.cluster_offset),
+                data_runs: Some(serde_json::to_string(data_runs).unwrap_or_default()),
                 fragments: Some(fragments),
                 partial_recovery: recovery_chance > 0 && recovery_chance < 80,
                 recoverable_bytes,
                 difficulty: difficulty.clone(),
                 age_estimate: age_estimate.clone(),
+                stream_name: None,
+                resident_data: match wof_stream {
+                    Some(stream) => stream.resident_data.as_ref().map(hex::encode),
+                    None => entry.resident_data.as_ref().map(hex::encode),
+                },
+                compression: match wof {
+                    Some((name, _)) => Some(name.to_string()),
+                    None => entry.is_compressed.then(|| "lznt1".to_string()),
+                },
+                stored_size: wof_stream.map(|s| s.size),
+                initialized_size: (wof.is_none() && entry.initialized_size < entry.file_size)
+                    .then_some(entry.initialized_size),
+                mft_reference: None,
+                lsn: None,
+                cross_links: None,
+                timestamps: Some(entry.timestamps()),
+                hard_links: hard_links(&entry, &directory_records, &self.path_root()),
+                exfat_timestamps: None,
+                usn_reasons: None,
             };
             
+            // A WOF file's content, and so its clusters, are those of its WofCompressedData stream
+            let main_stream = wof_stream.map_or(String::new(), |s| s.name.clone());
+            apply_cross_links(&mut file, cross_links.remove(&(record_num, main_stream)));
+            let streams: Vec<RecoverableFile> = entry.streams.iter()
+                .filter(|s| wof_stream.is_none() || s.name != WOF_STREAM_NAME)
+                .map(|s| {
+                    let mut item = self.stream_item(&file, s);
+                    apply_cross_links(&mut item, cross_links.remove(&(record_num, s.name.clone())));
+                    item
+                })
+                .collect();
+            cross_linked += std::iter::once(&file).chain(&streams).filter(|f| f.cross_links.is_some()).count();
+            leaves.push(TreeLeaf {
+                id: file.id.clone(),
+                parent_record: entry.parent_record,
+                parent_sequence: entry.parent_sequence,
+            });
             if is_orphan {
                 orphan_files.push(file);
+                orphan_files.extend(streams);
             } else {
                 files.push(file);
+                files.extend(streams);
             }
         }
         
@@ -424,157 +849,931 @@ impl RecoveryEngine {
         files.sort_by(|a, b| b.recovery_chance.cmp(&a.recovery_chance));
         orphan_files.sort_by(|a, b| b.recovery_chance.cmp(&a.recovery_chance));
         
-        eprintln!("Extended MFT Scan Stats: records={}, parsed={}, deleted={}, system={}, dirs={}, files={}, orphans={}",
-            actual_records, total_parsed, deleted_count, system_files, directories, files.len(), orphan_files.len());
+        eprintln!("Extended MFT Scan Stats: records={}, parsed={}, deleted={}, system={}, dirs={}, files={}, orphans={}, cross-linked={}",
+            actual_records, total_parsed, deleted_count, system_files, directories, files.len(), orphan_files.len(), cross_linked);
         
         self.files_found.store((files.len() + orphan_files.len()) as u64, Ordering::Relaxed);
-        Ok((files, orphan_files, actual_records as u64))
+        let tree = DirectoryTree::build(&directory_records, &leaves, ROOT_DIRECTORY_RECORD);
+        self.directory_records = directory_records;
+        Ok((files, orphan_files, actual_records, tree))
+    }
+    
+    /// Scan-result items for stale index entries whose file the MFT no longer lists
+    /// under that name. The data runs are only reachable while the referenced
+    /// record still holds the deleted file (sequence one past the reference).
+    fn index_slack_items(&self, slack: Vec<IndexEntry>, record_entries: &[(u64, MftEntry)], current_time: i64, leaves: &mut Vec<TreeLeaf>) -> Vec<RecoverableFile> {
+        let mut items = Vec::new();
+        
+        for index_entry in slack {
+            if index_entry.is_directory() {
+                continue;
+            }
+            
+            let record = record_entries
+                .binary_search_by_key(&index_entry.record_number(), |(number, _)| *number)
+                .ok()
+                .map(|at| &record_entries[at].1)
+                .filter(|e| sequence_matches(index_entry.sequence_number(), e.sequence_number, e.is_in_use));
+            // Live files were only renamed or moved; deleted ones under the same name are listed from the MFT
+            if record.is_some_and(|e| e.is_in_use || e.file_name == index_entry.name) {
+                continue;
+            }
+            
+            let data_runs = record.map(|e| e.data_runs.clone()).unwrap_or_default();
+            let (recovery_chance, difficulty, fragments) = match record {
+                Some(e) => self.analyze_entry(e),
+                // Record reused: only the name, times and size survive
+                None => (5, "very_hard".to_string(), Vec::new()),
+            };
+            let extension = index_entry.name.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()).unwrap_or_default();
+            let modified_time = filetime_to_unix(index_entry.modified_time);
+            let directory = index_entry.parent_reference & 0x0000_FFFF_FFFF_FFFF;
+            let id = format!("index_{}_{}", directory, index_entry.record_number());
+            leaves.push(TreeLeaf {
+                id: id.clone(),
+                parent_record: directory,
+                parent_sequence: (index_entry.parent_reference >> 48) as u16,
+            });
+            
+            items.push(RecoverableFile {
+                id,
+                name: index_entry.name.clone(),
+                path: format!("{}\\[Deleted]\\{}", self.path_root(), index_entry.name),
+                size: index_entry.real_size,
+                category: categorize_extension(&extension),
+                file_type: get_file_type_name(&extension),
+                extension,
+                modified: format_filetime(index_entry.modified_time),
+                created: format_filetime(index_entry.created_time),
+                is_deleted: true,
+                recovery_chance,
+                source: "index_slack".to_string(),
+                sector_offset: None,
+                cluster_offset: data_runs.first().map(|r| r.cluster_offset),
+                data_runs: Some(serde_json::to_string(&data_runs).unwrap_or_default()),
+                fragments: Some(fragments),
+                partial_recovery: recovery_chance > 0 && recovery_chance < 80,
+                recoverable_bytes: if recovery_chance > 50 { index_entry.real_size } else { 0 },
+                difficulty,
+                age_estimate: estimate_file_age(modified_time, current_time),
+                stream_name: None,
+                resident_data: record.and_then(|e| e.resident_data.as_ref()).map(hex::encode),
+                compression: record.filter(|e| e.is_compressed).map(|_| "lznt1".to_string()),
+                stored_size: None,
+                initialized_size: None,
+                mft_reference: Some(index_entry.file_reference),
+                lsn: None,
+                cross_links: None,
+                timestamps: Some(FileTimestamps::new(MacbTimes::default(), MacbTimes {
+                    created: index_entry.created_time,
+                    modified: index_entry.modified_time,
+                    mft_modified: index_entry.mft_modified_time,
+                    accessed: index_entry.accessed_time,
+                })),
+                hard_links: None,
+                exfat_timestamps: None,
+                usn_reasons: None,
+            });
+        }
+        
+        items
+    }
+    
+    /// Deleted files of a FAT volume, from the 0xE5 entries of every directory
+    fn scan_fat(&mut self) -> Result<Vec<RecoverableFile>, String> {
+        let volume = self.fat_volume.as_ref()
+            .ok_or("FAT volume not initialized")?;
+        let disk = self.disk_reader.as_mut()
+            .ok_or("Disk reader not initialized")?;
+        let found = scan_deleted(disk, volume, &self.cancelled);
+        
+        let current_time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let mut files: Vec<RecoverableFile> = found.iter()
+            .map(|deleted| self.fat_item(deleted, current_time))
+            .collect();
+        files.sort_by_key(|f| std::cmp::Reverse(f.recovery_chance));
+        
+        eprintln!("FAT Scan Stats: type={}, clusters={}, deleted={}, overwritten={}",
+            volume.boot.fat_type.name(), volume.boot.cluster_count, files.len(),
+            files.iter().filter(|f| f.recoverable_bytes == 0 && f.size > 0).count());
+        
+        self.files_found.store(files.len() as u64, Ordering::Relaxed);
+        Ok(files)
+    }
+    
+    /// Scan-result item for a deleted FAT entry. Only its first cluster
+    /// survives; the rest are the free clusters after it, in order.
+    fn fat_item(&self, deleted: &DeletedEntry, current_time: i64) -> RecoverableFile {
+        let volume = self.fat_volume.as_ref().expect("FAT volume");
+        let entry = &deleted.entry;
+        let cluster_size = volume.boot.cluster_size as u64;
+        let needed = (entry.size as u64).div_ceil(cluster_size);
+        let reassembly = volume.table.reassemble(entry.first_cluster, needed);
+        
+        // Free clusters in a row are the usual layout; stepping over live ones
+        // is a guess, and a reallocated first cluster means the start is gone
+        let quality: u8 = if reassembly.overwritten {
+            10
+        } else if reassembly.skipped > 0 {
+            60
+        } else {
+            90
+        };
+        let found = needed - reassembly.missing;
+        let recovery_chance = match needed {
+            0 => 99, // Empty file: nothing on disk to lose
+            _ => (quality as u64 * found / needed) as u8,
+        };
+        
+        let mut offset = 0u64;
+        let fragments = reassembly.runs.iter().map(|run| {
+            let fragment = FileFragment {
+                offset,
+                size: run.cluster_count * cluster_size,
+                cluster: run.cluster_offset,
+                is_readable: !reassembly.overwritten,
+                data_quality: quality,
+            };
+            offset += fragment.size;
+            fragment
+        }).collect();
+        
+        let extension = entry.name.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()).unwrap_or_default();
+        let size = entry.size as u64;
+        RecoverableFile {
+            id: format!("fat_{}", deleted.entry_offset),
+            name: entry.name.clone(),
+            path: match deleted.directory.as_str() {
+                "" => format!("{}\\{}", self.path_root(), entry.name),
+                directory => format!("{}\\{}\\{}", self.path_root(), directory, entry.name),
+            },
+            size,
+            category: categorize_extension(&extension),
+            file_type: get_file_type_name(&extension),
+            extension,
+            modified: format_fat_time(entry.modified),
+            created: format_fat_time(entry.created),
+            is_deleted: true,
+            recovery_chance,
+            source: "fat".to_string(),
+            sector_offset: None,
+            cluster_offset: Some(entry.first_cluster as i64),
+            data_runs: Some(serde_json::to_string(&reassembly.runs).unwrap_or_default()),
+            fragments: Some(fragments),
+            partial_recovery: recovery_chance < 80,
+            recoverable_bytes: if reassembly.overwritten { 0 } else { (found * cluster_size).min(size) },
+            difficulty: difficulty_for(recovery_chance).to_string(),
+            age_estimate: estimate_file_age(entry.modified.map_or(0, |t| t.and_utc().timestamp()), current_time),
+            stream_name: None,
+            resident_data: None,
+            compression: None,
+            stored_size: None,
+            initialized_size: None,
+            mft_reference: None,
+            lsn: None,
+            cross_links: None,
+            timestamps: None,
+            hard_links: None,
+            exfat_timestamps: None,
+            usn_reasons: None,
+        }
+    }
+    
+    /// Deleted files of an exFAT volume, from the entry sets whose InUse bit is cleared
+    fn scan_exfat(&mut self) -> Result<Vec<RecoverableFile>, String> {
+        let volume = self.exfat_volume.as_ref()
+            .ok_or("exFAT volume not initialized")?;
+        let disk = self.disk_reader.as_mut()
+            .ok_or("Disk reader not initialized")?;
+        let found = crate::exfat::scan_deleted(disk, volume, &self.cancelled);
+        
+        let current_time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let mut files: Vec<RecoverableFile> = found.iter()
+            .map(|deleted| self.exfat_item(deleted, current_time))
+            .collect();
+        files.sort_by_key(|f| std::cmp::Reverse(f.recovery_chance));
+        
+        eprintln!("exFAT Scan Stats: clusters={}, deleted={}, checksum_failed={}, overwritten={}",
+            volume.boot.cluster_count, files.len(),
+            found.iter().filter(|d| !d.set.checksum_valid).count(),
+            files.iter().filter(|f| f.recoverable_bytes == 0 && f.size > 0).count());
+        
+        self.files_found.store(files.len() as u64, Ordering::Relaxed);
+        Ok(files)
+    }
+    
+    /// Scan-result item for a deleted exFAT entry set. Its clusters are known
+    /// (contiguous or through the FAT); the bitmap tells which were reused.
+    fn exfat_item(&self, deleted: &DeletedEntrySet, current_time: i64) -> RecoverableFile {
+        let volume = self.exfat_volume.as_ref().expect("exFAT volume");
+        let set = &deleted.set;
+        let cluster_size = volume.boot.cluster_size as u64;
+        let needed = set.data_length.div_ceil(cluster_size);
+        let layout = volume.file_layout(set);
+        
+        // An intact set checksum and name hash vouch for the entries themselves
+        let free = layout.clusters - layout.allocated;
+        let quality = unallocated_quality(free, layout.clusters) + if set.checksum_valid { 10 } else { 0 };
+        let recovery_chance = match needed {
+            0 => 99, // Empty file: nothing on disk to lose
+            _ => (quality as u64 * layout.clusters / needed) as u8,
+        };
+        
+        let mut offset = 0u64;
+        let fragments = layout.runs.iter().map(|run| {
+            let first = run.cluster_offset as u32;
+            let allocated = (first..first + run.cluster_count as u32).filter(|&c| volume.is_allocated(c)).count() as u64;
+            let fragment = FileFragment {
+                offset,
+                size: run.cluster_count * cluster_size,
+                cluster: run.cluster_offset,
+                is_readable: allocated == 0,
+                data_quality: unallocated_quality(run.cluster_count - allocated, run.cluster_count),
+            };
+            offset += fragment.size;
+            fragment
+        }).collect();
+        
+        let extension = set.name.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()).unwrap_or_default();
+        let modified = set.modified.map(|t| t.best_utc());
+        RecoverableFile {
+            id: format!("exfat_{}", deleted.entry_offset),
+            name: set.name.clone(),
+            path: match deleted.directory.as_str() {
+                "" => format!("{}\\{}", self.path_root(), set.name),
+                directory => format!("{}\\{}\\{}", self.path_root(), directory, set.name),
+            },
+            size: set.data_length,
+            category: categorize_extension(&extension),
+            file_type: get_file_type_name(&extension),
+            extension,
+            modified: format_fat_time(modified),
+            created: format_fat_time(set.created.map(|t| t.best_utc())),
+            is_deleted: true,
+            recovery_chance,
+            source: "exfat".to_string(),
+            sector_offset: None,
+            cluster_offset: Some(set.first_cluster as i64),
+            data_runs: Some(serde_json::to_string(&layout.runs).unwrap_or_default()),
+            fragments: Some(fragments),
+            partial_recovery: recovery_chance < 80,
+            recoverable_bytes: (free * cluster_size).min(set.data_length),
+            difficulty: difficulty_for(recovery_chance).to_string(),
+            age_estimate: estimate_file_age(modified.map_or(0, |t| t.and_utc().timestamp()), current_time),
+            stream_name: None,
+            resident_data: None,
+            compression: None,
+            stored_size: None,
+            initialized_size: (set.valid_data_length < set.data_length).then_some(set.valid_data_length),
+            mft_reference: None,
+            lsn: None,
+            cross_links: None,
+            timestamps: None,
+            hard_links: None,
+            exfat_timestamps: Some(set.timestamps()),
+            usn_reasons: None,
+        }
+    }
+    
+    /// Read $LogFile through MFT record 2 and replay it
+    fn read_logfile_records(&mut self) -> Result<Vec<ReconstructedRecord>, String> {
+        let logfile = self.read_mft_entry(LOGFILE_RECORD)?;
+        let boot = self.boot_sector.as_ref()
+            .ok_or("Boot sector not initialized")?;
+        let disk = self.disk_reader.as_mut()
+            .ok_or("Disk reader not initialized")?;
+        
+        let size = logfile.file_size.min(MAX_LOGFILE_SIZE) as usize;
+        let log = read_runs_range(disk, &logfile.data_runs, boot.cluster_size, 0, size)?;
+        scan_logfile(&log, boot.cluster_size, boot.mft_record_size as usize)
+    }
+    
+    /// Merge files the $LogFile saw deleted into the scan result. Entries the
+    /// MFT scan already lists get their LSN (and resident content it lacked);
+    /// records that have since been reused come back as "logfile" items.
+    fn merge_logfile(&mut self, result: &mut RecoveryScanResult) {
+        let rebuilt = match self.read_logfile_records() {
+            Ok(records) => records,
+            Err(e) => {
+                eprintln!("Warning: $LogFile not usable: {}", e);
+                return;
+            }
+        };
+        let current_time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let mut merged = 0;
+        
+        for record in rebuilt.iter().filter(|r| r.deallocated) {
+            let Some(entry) = parse_mft_record(&record.image, record.record_number) else {
+                continue;
+            };
+            if entry.is_directory || entry.file_name.is_empty() || entry.file_name.starts_with('$') {
+                continue;
+            }
+            let resident = entry.resident_data.as_ref().map(hex::encode);
+            
+            let id = format!("mft_{}", entry.record_number);
+            let known = result.mft_entries.iter_mut()
+                .chain(result.orphan_files.iter_mut())
+                .find(|f| f.id == id && f.name == entry.file_name);
+            if let Some(file) = known {
+                file.lsn = Some(record.lsn);
+                if file.resident_data.is_none() && resident.is_some() && entry.data_runs.is_empty() {
+                    let (recovery_chance, difficulty, fragments) = self.analyze_entry(&entry);
+                    file.resident_data = resident;
+                    file.recovery_chance = recovery_chance;
+                    file.difficulty = difficulty;
+                    file.fragments = Some(fragments);
+                    file.partial_recovery = false;
+                    file.recoverable_bytes = file.size;
+                }
+                merged += 1;
+                continue;
+            }
+            
+            let (recovery_chance, difficulty, fragments) = self.analyze_entry(&entry);
+            result.mft_entries.push(RecoverableFile {
+                id: format!("logfile_{}_{}", entry.record_number, record.lsn),
+                name: entry.file_name.clone(),
+                path: format!("{}\\[Deleted]\\{}", self.path_root(), entry.file_name),
+                size: entry.file_size,
+                extension: entry.extension.clone(),
+                category: categorize_extension(&entry.extension),
+                file_type: get_file_type_name(&entry.extension),
+                modified: format_filetime(entry.si_times.modified),
+                created: format_filetime(entry.si_times.created),
+                is_deleted: true,
+                recovery_chance,
+                source: "logfile".to_string(),
+                sector_offset: None,
+                cluster_offset: entry.data_runs.first().map(|r| r.cluster_offset),
+                data_runs: Some(serde_json::to_string(&entry.data_runs).unwrap_or_default()),
+                fragments: Some(fragments),
+                partial_recovery: recovery_chance > 0 && recovery_chance < 80,
+                recoverable_bytes: if recovery_chance > 50 { entry.file_size } else { 0 },
+                difficulty,
+                age_estimate: estimate_file_age(entry.modified_time, current_time),
+                stream_name: None,
+                resident_data: resident,
+                compression: entry.is_compressed.then(|| "lznt1".to_string()),
+                stored_size: None,
+                initialized_size: (entry.initialized_size < entry.file_size).then_some(entry.initialized_size),
+                mft_reference: Some(entry.record_number | (entry.sequence_number as u64) << 48),
+                lsn: Some(record.lsn),
+                cross_links: None,
+                timestamps: Some(entry.timestamps()),
+                hard_links: None,
+                exfat_timestamps: None,
+                usn_reasons: None,
+            });
+            merged += 1;
+        }
+        
+        eprintln!("$LogFile: {} rebuilt records, {} deleted files merged", rebuilt.len(), merged);
+    }
+    
+    /// Read $Extend\$UsnJrnl:$J straight from the volume. Only allocated runs
+    /// are read: the journal is sparse and its purged head reads as zeros.
+    pub fn read_usn_journal(&mut self) -> Result<Vec<UsnRecord>, String> {
+        let extend = fixup_record(&self.read_mft_record(EXTEND_RECORD)?)
+            .ok_or("$Extend record is not a valid FILE record")?;
+        let journal_record = {
+            let boot = self.boot_sector.as_ref()
+                .ok_or("Boot sector not initialized")?;
+            let disk = self.disk_reader.as_mut()
+                .ok_or("Disk reader not initialized")?;
+            read_directory_index(disk, &extend, EXTEND_RECORD, boot.cluster_size, boot.index_record_size).entries.iter()
+                .find(|e| e.name == USN_JOURNAL_NAME)
+                .map(|e| e.record_number())
+                .ok_or("$Extend has no $UsnJrnl entry")?
+        };
+        
+        let journal = self.read_mft_entry(journal_record)?;
+        let stream = journal.streams.iter()
+            .find(|s| s.name == USN_STREAM_NAME)
+            .ok_or("$UsnJrnl has no $J stream")?;
+        if let Some(ref data) = stream.resident_data {
+            return Ok(parse_usn_journal(data, 0));
+        }
+        
+        let boot = self.boot_sector.as_ref()
+            .ok_or("Boot sector not initialized")?;
+        let disk = self.disk_reader.as_mut()
+            .ok_or("Disk reader not initialized")?;
+        let cluster_size = boot.cluster_size as u64;
+        let mut records = Vec::new();
+        let mut vcn = 0u64;
+        for run in &stream.data_runs {
+            let start = vcn * cluster_size;
+            vcn += run.cluster_count;
+            if run.cluster_offset <= 0 {
+                continue;
+            }
+            
+            let end = (vcn * cluster_size).min(stream.size);
+            let mut offset = start;
+            while offset < end {
+                let length = (end - offset).min(USN_READ_CHUNK);
+                let data = read_runs_range(disk, &stream.data_runs, boot.cluster_size, offset, length as usize)?;
+                records.extend(parse_usn_journal(&data, offset));
+                offset += length;
+            }
+        }
+        
+        eprintln!("USN journal: {} records in $J ({} bytes)", records.len(), stream.size);
+        Ok(records)
+    }
+    
+    /// Merge deletions from the USN journal (plus records carved from free
+    /// space) into the scan result. Files the MFT scan already lists are
+    /// skipped; the rest lost their record to a new file and only keep the
+    /// name, parent and deletion time.
+    fn merge_usn_journal(&mut self, result: &mut RecoveryScanResult, carved: Vec<UsnRecord>) {
+        let mut records = self.read_usn_journal().unwrap_or_else(|e| {
+            eprintln!("Warning: USN journal not usable: {}", e);
+            Vec::new()
+        });
+        records.extend(carved);
+        
+        // Latest deletion per file reference
+        let mut deletions: HashMap<u64, UsnRecord> = HashMap::new();
+        for record in records {
+            if !record.is_deletion() || record.is_directory() || record.file_name.is_empty() || record.file_name.starts_with('$') {
+                continue;
+            }
+            if deletions.get(&record.file_reference).is_none_or(|known| known.usn < record.usn) {
+                deletions.insert(record.file_reference, record);
+            }
+        }
+        let mut deletions: Vec<UsnRecord> = deletions.into_values().collect();
+        deletions.sort_by_key(|r| r.usn);
+        
+        let current_time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let mut added = 0;
+        for record in deletions {
+            let deleted = record.to_deleted_file();
+            let id = format!("mft_{}", deleted.mft_record);
+            let known = result.mft_entries.iter()
+                .chain(result.orphan_files.iter())
+                .any(|f| f.name == deleted.file_name && (f.id == id || f.mft_reference == Some(record.file_reference)));
+            if known {
+                continue;
+            }
+            
+            // The parent reference only names a directory while its record still holds it
+            let parent_sequence = (record.parent_reference >> 48) as u16;
+            let directory = self.directory_records.get(&deleted.parent_mft_record)
+                .filter(|d| sequence_matches(parent_sequence, d.sequence_number, d.is_in_use))
+                .and_then(|_| directory_path(&self.directory_records, deleted.parent_mft_record));
+            let extension = deleted.file_name.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()).unwrap_or_default();
+            result.mft_entries.push(RecoverableFile {
+                id: format!("usn_{}_{}", deleted.mft_record, record.usn),
+                name: deleted.file_name.clone(),
+                path: match directory {
+                    Some(dir) if dir.is_empty() => format!("{}\\{}", self.path_root(), deleted.file_name),
+                    Some(dir) => format!("{}\\{}\\{}", self.path_root(), dir, deleted.file_name),
+                    None => format!("{}\\[Deleted]\\{}", self.path_root(), deleted.file_name),
+                },
+                size: 0,
+                category: categorize_extension(&extension),
+                file_type: get_file_type_name(&extension),
+                extension,
+                modified: format_timestamp(deleted.timestamp),
+                created: "Unknown".to_string(),
+                is_deleted: true,
+                recovery_chance: 5,
+                source: "usn_journal".to_string(),
+                sector_offset: None,
+                cluster_offset: None,
+                data_runs: Some("[]".to_string()),
+                fragments: Some(Vec::new()),
+                partial_recovery: true,
+                recoverable_bytes: 0,
+                difficulty: "very_hard".to_string(),
+                age_estimate: estimate_file_age(deleted.timestamp, current_time),
+                stream_name: None,
+                resident_data: None,
+                compression: None,
+                stored_size: None,
+                initialized_size: None,
+                mft_reference: Some(record.file_reference),
+                lsn: None,
+                cross_links: None,
+                timestamps: None,
+                hard_links: None,
+                exfat_timestamps: None,
+                usn_reasons: Some(record.reason_names().into_iter().map(String::from).collect()),
+            });
+            added += 1;
+        }
+        
+        eprintln!("USN journal: {} deleted files not in the MFT added", added);
     }
     
     /// Analyze recovery possibility for a file entry
-    fn analyze_recovery_possibility(&self, entry: &MftEntry) -> (u8, String, Vec<FileFragment>) {
+    fn analyze_entry(&self, entry: &MftEntry) -> (u8, String, Vec<FileFragment>) {
+        match entry.resident_data {
+            Some(ref data) => resident_possibility(data.len() as u64, !entry.is_torn),
+            None => self.analyze_recovery_possibility(&entry.data_runs),
+        }
+    }
+    
+    fn analyze_recovery_possibility(&self, data_runs: &[DataRun]) -> (u8, String, Vec<FileFragment>) {
         let mut fragments = Vec::new();
         let mut total_quality: u32 = 0;
         let mut fragment_count: u32 = 0;
         
-        if entry.data_runs.is_empty() {
-            // Check for resident data (small files stored in MFT)
-            if entry.file_size < 700 && entry.file_size > 0 {
-                return (50, "moderate".to_string(), vec![FileFragment {
-                    offset: 0,
-                    size: entry.file_size,
-                    cluster: 0,
-                    is_readable: true,
-                    data_quality: 50,
-                }]);
-            }
+        if data_runs.is_empty() {
+            // Non-resident data whose mapping pairs are gone
             return (5, "very_hard".to_string(), fragments);
         }
         
-        for (i, run) in entry.data_runs.iter().enumerate() {
-            let quality = if run.cluster_offset > 0 { 85 } else { 10 };
-            total_quality += quality as u32;
-            fragment_count += 1;
-            
-            let boot = self.boot_sector.as_ref();
-            let cluster_size = boot.map(|b| b.cluster_size).unwrap_or(4096);
+        let boot = self.boot_sector.as_ref();
+        let cluster_size = boot.map(|b| b.cluster_size).unwrap_or(4096) as u64;
+        let mut offset = 0u64;
+        let mut free_clusters = 0u64;
+        let mut mapped_clusters = 0u64;
+        
+        for run in data_runs {
+            // Sparse runs have no clusters to lose: they read back as zeros
+            let sparse = run.cluster_offset == 0;
+            // Clusters $Bitmap shows as handed to another file since the deletion
+            let reallocated = self.cluster_bitmap.as_ref()
+                .filter(|_| run.cluster_offset > 0)
+                .map(|bitmap| bitmap.allocated_count(run.cluster_offset as u64, run.cluster_count));
+            let quality = match reallocated {
+                _ if sparse => 100,
+                Some(taken) => unallocated_quality(run.cluster_count - taken, run.cluster_count),
+                None if run.cluster_offset > 0 => 85,
+                None => 10,
+            };
+            if !sparse {
+                total_quality += quality as u32;
+                fragment_count += 1;
+                mapped_clusters += run.cluster_count;
+                free_clusters += reallocated.map_or(0, |taken| run.cluster_count - taken);
+            }
             
             fragments.push(FileFragment {
-                offset: i as u64 * (run.cluster_count * cluster_size as u64),
-                size: run.cluster_count * cluster_size as u64,
+                offset,
+                size: run.cluster_count * cluster_size,
                 cluster: run.cluster_offset,
-                is_readable: run.cluster_offset > 0,
+                is_readable: run.cluster_offset >= 0 && reallocated.is_none_or(|taken| taken < run.cluster_count),
                 data_quality: quality,
             });
+            offset += run.cluster_count * cluster_size;
+        }
+        
+        let recovery_chance = match self.cluster_bitmap {
+            // Share of the file's clusters nothing else has claimed yet
+            Some(_) if mapped_clusters > 0 => unallocated_quality(free_clusters, mapped_clusters),
+            // An entirely sparse file has nothing on disk to lose
+            _ => total_quality.checked_div(fragment_count).map_or(85, |q| q as u8),
+        };
+        (recovery_chance, difficulty_for(recovery_chance).to_string(), fragments)
+    }
+    
+    /// Scan-result item for an alternate data stream of `parent`
+    fn stream_item(&self, parent: &RecoverableFile, stream: &DataStream) -> RecoverableFile {
+        let (recovery_chance, difficulty, fragments) = match stream.resident_data {
+            // Resident streams live inside the MFT record itself
+            Some(ref data) => resident_possibility(data.len() as u64, true),
+            None => self.analyze_recovery_possibility(&stream.data_runs),
+        };
+        
+        RecoverableFile {
+            id: format!("{}:{}", parent.id, stream.name),
+            name: format!("{}:{}", parent.name, stream.name),
+            path: format!("{}:{}", parent.path, stream.name),
+            size: stream.size,
+            extension: String::new(),
+            category: categorize_extension(""),
+            file_type: "Alternate Data Stream".to_string(),
+            modified: parent.modified.clone(),
+            created: parent.created.clone(),
+            is_deleted: parent.is_deleted,
+            recovery_chance,
+            source: parent.source.clone(),
+            sector_offset: None,
+            cluster_offset: stream.data_runs.first().map(|r| r.cluster_offset),
+            data_runs: Some(serde_json::to_string(&stream.data_runs).unwrap_or_default()),
+            fragments: Some(fragments),
+            partial_recovery: recovery_chance > 0 && recovery_chance < 80,
+            recoverable_bytes: if recovery_chance > 50 { stream.size } else { 0 },
+            difficulty,
+            age_estimate: parent.age_estimate.clone(),
+            stream_name: Some(stream.name.clone()),
+            resident_data: stream.resident_data.as_ref().map(hex::encode),
+            compression: None,
+            stored_size: None,
+            initialized_size: (stream.initialized_size < stream.size).then_some(stream.initialized_size),
+            mft_reference: None,
+            lsn: None,
+            cross_links: None,
+            timestamps: parent.timestamps.clone(),
+            hard_links: parent.hard_links.clone(),
+            exfat_timestamps: parent.exfat_timestamps.clone(),
+            usn_reasons: parent.usn_reasons.clone(),
+        }
+    }
+    
+    /// Read and parse a single MFT record, following the $MFT's extents
+    pub fn read_mft_entry(&mut self, record_number: u64) -> Result<MftEntry, String> {
+        let raw = self.read_mft_record(record_number)?;
+        let boot = self.boot_sector.as_ref()
+            .ok_or("Boot sector not initialized")?;
+        let disk = self.disk_reader.as_mut()
+            .ok_or("Disk reader not initialized")?;
+        
+        let record_size = boot.mft_record_size as usize;
+        let mut entry = parse_mft_record(&raw, record_number)
+            .ok_or_else(|| format!("MFT record {} is not a valid FILE record", record_number))?;
+        
+        if !entry.attribute_list.is_empty() || !entry.attribute_list_runs.is_empty() {
+            if let Some(ref layout) = self.mft_layout {
+                resolve_extension_records(disk, &layout.runs, boot.cluster_size, record_size, &mut entry);
+            }
+        }
+        Ok(entry)
+    }
+    
+    /// Raw bytes of a single MFT record, before fixups
+    fn read_mft_record(&mut self, record_number: u64) -> Result<Vec<u8>, String> {
+        let boot = self.boot_sector.as_ref()
+            .ok_or("Boot sector not initialized")?;
+        let disk = self.disk_reader.as_mut()
+            .ok_or("Disk reader not initialized")?;
+        
+        let record_size = boot.mft_record_size as usize;
+        let raw = match self.mft_layout {
+            Some(ref layout) => read_runs_range(disk, &layout.runs, boot.cluster_size, record_number * record_size as u64, record_size),
+            None => disk.read_at(boot.mft_cluster * boot.cluster_size as u64 + record_number * record_size as u64, record_size),
+        }?;
+        
+        if record_number < MFT_MIRROR_RECORDS && fixup_record(&raw).is_none() {
+            return Ok(read_record_or_mirror(disk, boot, record_number).unwrap_or(raw));
+        }
+        Ok(raw)
+    }
+    
+    /// Restore every alternate data stream of a recovered file into a
+    /// `<destination>.streams` folder next to it
+    pub fn recover_streams(&mut self, file: &RecoverableFile, destination: &str) -> Result<Vec<FileRecoveryResult>, String> {
+        let record_number: u64 = file.id
+            .strip_prefix("mft_")
+            .and_then(|n| n.parse().ok())
+            .ok_or("Streams can only be listed for MFT files")?;
+        let entry = self.read_mft_entry(record_number)?;
+        if entry.file_name != file.name {
+            return Err(format!(
+                "MFT record {} now belongs to '{}'; its streams are gone",
+                record_number, entry.file_name
+            ));
+        }
+        if entry.streams.is_empty() {
+            return Ok(Vec::new());
         }
         
-        let avg_quality = if fragment_count > 0 {
-            (total_quality / fragment_count) as u8
-        } else {
-            0
-        };
+        let folder = format!("{}.streams", destination);
+        std::fs::create_dir_all(&folder)
+            .map_err(|e| format!("Failed to create {}: {}", folder, e))?;
+        
+        let mut results = Vec::new();
+        for stream in &entry.streams {
+            let item = self.stream_item(file, stream);
+            let target = std::path::Path::new(&folder).join(sanitize_stream_name(&stream.name));
+            let target = target.to_string_lossy().to_string();
+            results.push(self.recover_from_mft(&item, &target).unwrap_or_else(|e| FileRecoveryResult {
+                success: false,
+                source_path: item.path.clone(),
+                destination_path: target.clone(),
+                bytes_recovered: 0,
+                message: e,
+            }));
+        }
+        Ok(results)
+    }
+    
+    /// Restore a directory (live or deleted) with every recoverable file below
+    /// it into `<destination>/<directory name>`, keeping their relative paths
+    pub fn recover_tree(&mut self, record_number: u64, destination: &str) -> Result<TreeRecoveryResult, String> {
+        let (files, orphans, _, tree) = self.scan_mft_extended(true)?;
+        let directory = tree.find(record_number)
+            .ok_or_else(|| format!("MFT record {} is not a directory holding deleted files", record_number))?;
+        let items: HashMap<&str, &RecoverableFile> = files.iter().chain(&orphans).map(|f| (f.id.as_str(), f)).collect();
         
-        let recovery_chance = avg_quality;
-        let difficulty = match recovery_chance {
-            80..=100 => "easy",
-            50..=79 => "moderate",
-            20..=49 => "hard",
-            _ => "very_hard",
+        // The root directory has no name of its own: restore its content straight into destination
+        let folder = match directory.name.as_str() {
+            "" => std::path::PathBuf::from(destination),
+            name => std::path::Path::new(destination).join(sanitize_stream_name(name)),
         };
+        for relative in directory.directories_below() {
+            let path = local_path(&folder, &relative);
+            std::fs::create_dir_all(&path)
+                .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        }
+        
+        let mut taken = HashSet::new();
+        let mut results = Vec::new();
+        for (relative, id) in directory.files_below() {
+            let Some(file) = items.get(id) else { continue };
+            let target = unique_target(&local_path(&folder, &relative), file, &mut taken);
+            results.push(self.recover_from_mft(file, &target).unwrap_or_else(|e| FileRecoveryResult {
+                success: false,
+                source_path: file.path.clone(),
+                destination_path: target.clone(),
+                bytes_recovered: 0,
+                message: e,
+            }));
+        }
         
-        (recovery_chance, difficulty.to_string(), fragments)
+        let recovered = results.iter().filter(|r| r.success).count();
+        let bytes_recovered = results.iter().map(|r| r.bytes_recovered).sum();
+        Ok(TreeRecoveryResult {
+            success: recovered > 0 || results.is_empty(),
+            directory: directory.path.clone(),
+            destination_path: folder.to_string_lossy().to_string(),
+            files_recovered: recovered,
+            files_failed: results.len() - recovered,
+            bytes_recovered,
+            message: format!(
+                "Restored {} of {} file(s) ({}) from '{}'",
+                recovered,
+                results.len(),
+                format_size(bytes_recovered),
+                directory.path
+            ),
+            files: results,
+        })
     }
     
     /// Advanced carving with slack space recovery
-    fn carve_sectors_advanced(&mut self, max_sectors: Option<u64>) -> Result<(Vec<RecoverableFile>, u64), String> {
+    fn carve_sectors_advanced(&mut self, max_sectors: Option<u64>, carve_mode: CarveMode) -> Result<(Vec<RecoverableFile>, Vec<UsnRecord>, u64), String> {
+        let path_root = self.path_root();
         let disk = self.disk_reader.as_mut()
             .ok_or("Disk reader not initialized")?;
         
+        let sector_size = disk.sector_size();
         let raw_total = disk.total_sectors();
         // If the IOCTL returned 0 (e.g. geometry query unsupported on this device)
         // fall back to a conservative 25 GB worth of sectors so carving still runs.
-        let total_sectors = if raw_total > 0 { raw_total } else { 50_000_000u64 };
+        let total_sectors = if raw_total > 0 { raw_total } else { (25u64 << 30) / sector_size as u64 };
         let sectors_to_scan = max_sectors.unwrap_or(total_sectors).min(total_sectors);
 
         // Cap at ~50 GB regardless of drive size to keep deep scan under ~10 min.
-        let sector_limit = sectors_to_scan.min(100_000_000);
+        let sector_limit = sectors_to_scan.min((50u64 << 30) / sector_size as u64);
         
         let signatures = build_signature_lookup();
         let mut carved_files = Vec::new();
+        let mut usn_records = Vec::new();
         let mut file_id = 0;
         
         // Scan in 4MB chunks for better performance
         let chunk_size = 4 * 1024 * 1024;
-        let sectors_per_chunk = chunk_size / 512;
-        
-        let mut current_sector = 0u64;
-        let mut last_progress_sector = 0u64;
+        let sectors_per_chunk = chunk_size / sector_size;
         
-        while current_sector < sector_limit {
-            if self.cancelled.load(Ordering::Relaxed) {
-                break;
+        // Restrict carving to the clusters $Bitmap marks free when asked to
+        // (the FAT itself on FAT volumes, the allocation bitmap on exFAT)
+        let free_extents: Option<Vec<(u64, u64)>> = match (&self.cluster_bitmap, &self.boot_sector, &self.fat_volume, &self.exfat_volume) {
+            (Some(bitmap), Some(boot), _, _) => {
+                let spc = boot.sectors_per_cluster as u64;
+                Some(bitmap.free_extents().into_iter().map(|(lcn, count)| (lcn * spc, count * spc)).collect())
             }
-            
-            // Limit total carved files
-            if carved_files.len() >= 50000 {
-                break;
+            (_, _, Some(volume), _) => {
+                let spc = volume.boot.sectors_per_cluster as u64;
+                let first = volume.boot.first_data_sector();
+                Some(volume.table.free_extents().into_iter()
+                    .map(|(cluster, count)| (first + (cluster as u64 - 2) * spc, count as u64 * spc))
+                    .collect())
             }
-            
-            disk.seek_sector(current_sector)?;
-            let data = disk.read_sectors(sectors_per_chunk)?;
-            
-            if data.is_empty() {
-                break;
+            (_, _, _, Some(volume)) => {
+                let spc = volume.boot.sectors_per_cluster as u64;
+                let first = volume.boot.cluster_heap_offset;
+                Some(volume.free_extents().into_iter()
+                    .map(|(cluster, count)| (first + (cluster as u64 - 2) * spc, count as u64 * spc))
+                    .collect())
             }
+            _ => None,
+        };
+        let ranges: Vec<(u64, u64)> = match (free_extents, carve_mode) {
+            (Some(extents), CarveMode::UnallocatedOnly) => extents.into_iter()
+                .filter(|&(start, _)| start < sector_limit)
+                .map(|(start, length)| (start, length.min(sector_limit - start)))
+                .collect(),
+            _ => vec![(0, sector_limit)],
+        };
+        
+        let mut sectors_scanned = 0u64;
+        let mut last_progress_sector = 0u64;
+        
+        'ranges: for (start, length) in ranges {
+            let end = start + length;
+            let mut current_sector = start;
             
-            let carved = carve_sector(&data, current_sector, &signatures);
-            
-            for file in carved {
-                file_id += 1;
+            while current_sector < end {
+                if self.cancelled.load(Ordering::Relaxed) {
+                    break 'ranges;
+                }
                 
-                // Estimate recovery difficulty based on signature confidence
-                let difficulty = match file.confidence {
-                    80..=100 => "easy",
-                    60..=79 => "moderate",
-                    40..=59 => "hard",
-                    _ => "very_hard",
-                };
+                // Limit total carved files
+                if carved_files.len() >= 50000 {
+                    break 'ranges;
+                }
                 
-                carved_files.push(RecoverableFile {
-                    id: format!("carved_{}", file_id),
-                    name: format!("Recovered_{}.{}", file_id, file.extension),
-                    path: format!("{}:\\[Carved]\\sector_{}_{}.{}", 
-                        self.drive_letter, file.sector_offset, file_id, file.extension),
-                    size: file.estimated_size,
-                    extension: file.extension.clone(),
-                    category: file.category.clone(),
-                    file_type: file.file_type.clone(),
-                    modified: "Unknown".to_string(),
-                    created: "Unknown".to_string(),
-                    is_deleted: true,
-                    recovery_chance: file.confidence,
-                    source: "carved".to_string(),
-                    sector_offset: Some(file.sector_offset * 512 + file.byte_offset),
-                    cluster_offset: None,
-                    data_runs: None,
-                    fragments: None,
-                    partial_recovery: file.confidence < 80,
-                    recoverable_bytes: file.estimated_size,
-                    difficulty: difficulty.to_string(),
-                    age_estimate: "Unknown".to_string(),
-                });
-            }
-            
-            current_sector += sectors_per_chunk as u64;
-            
-            // Progress logging every ~500MB
-            if current_sector - last_progress_sector > 1_000_000 {
-                eprintln!("Carving progress: {} sectors, {} files found", current_sector, carved_files.len());
-                last_progress_sector = current_sector;
+                let sectors = sectors_per_chunk.min((end - current_sector) as usize);
+                disk.seek_sector(current_sector)?;
+                let data = disk.read_sectors(sectors)?;
+                
+                if data.is_empty() {
+                    break;
+                }
+                
+                let carved = carve_sector(&data, current_sector, sector_size as u64, &signatures);
+                // USN records purged from a wrapped journal linger in freed clusters
+                usn_records.extend(carve_usn_records(&data));
+                
+                for file in carved {
+                    file_id += 1;
+                
+                    // Estimate recovery difficulty based on signature confidence
+                    let difficulty = match file.confidence {
+                        80..=100 => "easy",
+                        60..=79 => "moderate",
+                        40..=59 => "hard",
+                        _ => "very_hard",
+                    };
+                
+                    carved_files.push(RecoverableFile {
+                        id: format!("carved_{}", file_id),
+                        name: format!("Recovered_{}.{}", file_id, file.extension),
+                        path: format!("{}\\[Carved]\\sector_{}_{}.{}", 
+                            path_root, file.sector_offset, file_id, file.extension),
+                        size: file.estimated_size,
+                        extension: file.extension.clone(),
+                        category: file.category.clone(),
+                        file_type: file.file_type.clone(),
+                        modified: "Unknown".to_string(),
+                        created: "Unknown".to_string(),
+                        is_deleted: true,
+                        recovery_chance: file.confidence,
+                        source: "carved".to_string(),
+                        sector_offset: Some(file.sector_offset * sector_size as u64 + file.byte_offset),
+                        cluster_offset: None,
+                        data_runs: None,
+                        fragments: None,
+                        partial_recovery: file.confidence < 80,
+                        recoverable_bytes: file.estimated_size,
+                        difficulty: difficulty.to_string(),
+                        age_estimate: "Unknown".to_string(),
+                        stream_name: None,
+                        resident_data: None,
+                        compression: None,
+                        stored_size: None,
+                        initialized_size: None,
+                        mft_reference: None,
+                        lsn: None,
+                        cross_links: None,
+                        timestamps: None,
+                        hard_links: None,
+                        exfat_timestamps: None,
+                        usn_reasons: None,
+                    });
+                }
+                
+                current_sector += sectors as u64;
+                sectors_scanned += sectors as u64;
+                
+                // Progress logging every ~500MB
+                if sectors_scanned - last_progress_sector > 1_000_000 {
+                    eprintln!("Carving progress: {} sectors, {} files found", sectors_scanned, carved_files.len());
+                    last_progress_sector = sectors_scanned;
+                }
             }
         }
         
-        Ok((carved_files, current_sector))
+        Ok((carved_files, usn_records, sectors_scanned))
+    }
+    
+    /// Resident content of a deleted file, provided its MFT record still holds
+    /// that file and has not been reused
+    fn reread_resident_data(&mut self, file: &RecoverableFile) -> Option<Vec<u8>> {
+        let record_number = file.id.strip_prefix("mft_")?.parse().ok()?;
+        let entry = self.read_mft_entry(record_number).ok()?;
+        if entry.is_in_use || !entry.file_names.iter().any(|name| name.name == file.name) {
+            return None;
+        }
+        entry.resident_data
     }
     
     /// Recover a file from MFT entry with partial recovery support
@@ -583,16 +1782,37 @@ impl RecoveryEngine {
         file: &RecoverableFile,
         destination: &str,
     ) -> Result<FileRecoveryResult, String> {
-        if file.source != "mft" && file.source != "mft_orphan" && file.source != "USN" && file.source != "MFT" && file.source != "mft_filesystem" {
+        if file.source != "mft" && file.source != "mft_orphan" && file.source != "index_slack" && file.source != "logfile" && file.source != "USN" && file.source != "MFT" && file.source != "mft_filesystem" {
             return Err("File is not from MFT scan".to_string());
         }
         
+        let wof_format = file.compression.as_deref().and_then(wof_format_from_name);
+        
+        // Resident content was captured from the MFT record at scan time; results
+        // saved before it was read it straight from the record
+        let resident = match file.resident_data {
+            Some(ref resident) => Some(hex::decode(resident).map_err(|e| format!("Invalid resident data: {}", e))?),
+            None if wof_format.is_none() && file.stream_name.is_none() => self.reread_resident_data(file),
+            None => None,
+        };
+        
         let boot = self.boot_sector.as_ref()
             .ok_or("Boot sector not initialized")?;
         
         let disk = self.disk_reader.as_mut()
             .ok_or("Disk reader not initialized")?;
         
+        if let (Some(data), None) = (resident, wof_format) {
+            save_carved_file(&data, destination)?;
+            return Ok(FileRecoveryResult {
+                success: true,
+                source_path: file.path.clone(),
+                destination_path: destination.to_string(),
+                bytes_recovered: data.len() as u64,
+                message: format!("Successfully recovered {} bytes from the MFT record", data.len()),
+            });
+        }
+        
         // Parse data runs
         let data_runs_str = file.data_runs.as_ref()
             .ok_or("No data runs available")?;
@@ -600,7 +1820,7 @@ impl RecoveryEngine {
         let data_runs: Vec<crate::ntfs_parser::DataRun> = serde_json::from_str(data_runs_str)
             .map_err(|e| format!("Failed to parse data runs: {}", e))?;
         
-        if data_runs.is_empty() {
+        if data_runs.is_empty() && file.resident_data.is_none() {
             // Try to salvage any data we can find
             return Ok(FileRecoveryResult {
                 success: false,
@@ -619,49 +1839,86 @@ impl RecoveryEngine {
         let cluster_size = boot.cluster_size;
         let mut file_data = Vec::new();
         let mut bytes_remaining = file.size;
+        let valid_length = file.initialized_size.unwrap_or(file.size).min(file.size);
         let mut failed_runs = 0;
         let mut successful_runs = 0;
         let mut partial_recovery = false;
         
-        for run in &data_runs {
-            if bytes_remaining == 0 {
-                break;
-            }
-            
-            if run.cluster_offset <= 0 {
-                // Sparse run - fill with zeros for partial recovery
-                let sparse_size = (run.cluster_count * cluster_size as u64).min(bytes_remaining);
-                file_data.extend(vec![0u8; sparse_size as usize]);
-                bytes_remaining = bytes_remaining.saturating_sub(sparse_size);
-                partial_recovery = true;
-                continue;
-            }
-            
-            let data = match read_clusters(
-                disk,
-                run.cluster_offset as u64,
-                run.cluster_count,
-                cluster_size,
-            ) {
-                Ok(d) => {
-                    successful_runs += 1;
-                    d
+        let mut piece_label = "runs";
+        
+        if let Some(format) = wof_format {
+            // WofCompressedData: chunk offset table followed by XPRESS/LZX chunks
+            let stored = match file.resident_data {
+                Some(ref resident) => hex::decode(resident).map_err(|e| format!("Invalid resident data: {}", e))?,
+                None => read_runs_range(disk, &data_runs, cluster_size, 0, file.stored_size.unwrap_or(0) as usize)
+                    .map_err(|e| format!("Failed to read WofCompressedData: {}", e))?,
+            };
+            let (data, damaged_chunks) = decompress_wof(&stored, format, file.size)?;
+            let chunk_size = wof_chunk_size(format).unwrap_or(4096);
+            successful_runs = file.size.div_ceil(chunk_size) - damaged_chunks;
+            failed_runs = damaged_chunks;
+            partial_recovery = damaged_chunks > 0;
+            piece_label = "WOF chunks";
+            file_data = data;
+        } else if file.compression.as_deref() == Some("lznt1") {
+            // Compressed files are read one 16-cluster compression unit at a time
+            let (data, damaged_units) = read_compressed_runs(disk, &data_runs, cluster_size, file.size);
+            let unit_bytes = COMPRESSION_UNIT_CLUSTERS * cluster_size as u64;
+            successful_runs = file.size.div_ceil(unit_bytes) - damaged_units;
+            failed_runs = damaged_units;
+            partial_recovery = damaged_units > 0;
+            piece_label = "compression units";
+            file_data = data;
+        } else {
+            for run in &data_runs {
+                if bytes_remaining == 0 {
+                    break;
                 }
-                Err(e) => {
-                    eprintln!("Warning: Failed to read cluster {}: {}", run.cluster_offset, e);
-                    failed_runs += 1;
-                    // Fill with zeros for the failed section to maintain file structure
-                    let failed_size = (run.cluster_count * cluster_size as u64).min(bytes_remaining);
-                    file_data.extend(vec![0u8; failed_size as usize]);
-                    bytes_remaining = bytes_remaining.saturating_sub(failed_size);
-                    partial_recovery = true;
+                let run_bytes = (run.cluster_count * cluster_size as u64).min(bytes_remaining);
+                let position = file.size - bytes_remaining;
+                bytes_remaining -= run_bytes;
+            
+                if run.cluster_offset == 0 || position >= valid_length {
+                    // Sparse run or past the valid data length: NTFS reads these as zeros
+                    file_data.resize(file_data.len() + run_bytes as usize, 0);
                     continue;
                 }
-            };
             
-            let to_take = bytes_remaining.min(data.len() as u64) as usize;
-            file_data.extend_from_slice(&data[..to_take]);
-            bytes_remaining = bytes_remaining.saturating_sub(to_take as u64);
+                // Only the clusters holding initialized data are worth reading
+                let wanted = run_bytes.min(valid_length - position);
+                let result = if run.cluster_offset < 0 {
+                    Err("negative cluster number in run list".to_string())
+                } else {
+                    read_clusters(disk, run.cluster_offset as u64, wanted.div_ceil(cluster_size as u64), cluster_size)
+                };
+                let data = match result {
+                    Ok(d) => {
+                        successful_runs += 1;
+                        d
+                    }
+                    Err(e) => {
+                        eprintln!("Warning: Failed to read cluster {}: {}", run.cluster_offset, e);
+                        failed_runs += 1;
+                        // Fill with zeros for the failed section to maintain file structure
+                        file_data.resize(file_data.len() + run_bytes as usize, 0);
+                        partial_recovery = true;
+                        continue;
+                    }
+                };
+            
+                let to_take = (run_bytes as usize).min(data.len());
+                file_data.extend_from_slice(&data[..to_take]);
+                file_data.resize(file_data.len() + (run_bytes as usize - to_take), 0);
+            }
+            // A run list that ends before the file does has lost its tail
+            if bytes_remaining > 0 {
+                partial_recovery = true;
+            }
+        }
+        
+        // Bytes past the valid data length were never written
+        if (valid_length as usize) < file_data.len() {
+            file_data[valid_length as usize..].fill(0);
         }
         
         if file_data.is_empty() {
@@ -695,11 +1952,12 @@ impl RecoveryEngine {
             )
         } else if partial_recovery {
             format!(
-                "Partially recovered {} of {} bytes ({:.1}% recovered). {} runs succeeded, {} failed.", 
+                "Partially recovered {} of {} bytes ({:.1}% recovered). {} {} succeeded, {} failed.", 
                 file_data.len(), 
                 file.size,
                 (file_data.len() as f64 / file.size as f64) * 100.0,
                 successful_runs,
+                piece_label,
                 failed_runs
             )
         } else {
@@ -760,6 +2018,68 @@ impl RecoveryEngine {
         })
     }
     
+    /// Recover a deleted FAT or exFAT file from the clusters laid out at scan time
+    pub fn recover_fat(
+        &mut self,
+        file: &RecoverableFile,
+        destination: &str,
+    ) -> Result<FileRecoveryResult, String> {
+        if file.source != "fat" && file.source != "exfat" {
+            return Err("File is not from a FAT or exFAT scan".to_string());
+        }
+        
+        let disk = self.disk_reader.as_mut()
+            .ok_or("Disk reader not initialized")?;
+        
+        let runs: Vec<DataRun> = serde_json::from_str(file.data_runs.as_deref().unwrap_or("[]"))
+            .map_err(|e| format!("Failed to parse data runs: {}", e))?;
+        if runs.is_empty() && file.size > 0 {
+            return Ok(FileRecoveryResult {
+                success: false,
+                source_path: file.path.clone(),
+                destination_path: destination.to_string(),
+                bytes_recovered: 0,
+                message: format!(
+                    "File '{}' cannot be recovered: its first cluster is not on the volume. Try deep scan for file carving.",
+                    file.name
+                ),
+            });
+        }
+        
+        let (mut data, failed_runs) = match (file.source.as_str(), &self.fat_volume, &self.exfat_volume) {
+            ("fat", Some(volume), _) => volume.read_runs(disk, &runs, file.size),
+            ("exfat", _, Some(volume)) => volume.read_runs(disk, &runs, file.size),
+            _ => return Err(format!("Source is not a {} volume", if file.source == "fat" { "FAT" } else { "exFAT" })),
+        };
+        // exFAT: past the valid data length the file reads as zeros
+        if let Some(initialized) = file.initialized_size {
+            data.iter_mut().skip(initialized as usize).for_each(|byte| *byte = 0);
+        }
+        save_carved_file(&data, destination)?;
+        
+        let validation = validate_recovered_data(&data, &file.extension);
+        let message = if (data.len() as u64) < file.size {
+            format!(
+                "Partially recovered {} of {} bytes: the volume ends before the file does.",
+                data.len(), file.size
+            )
+        } else if failed_runs > 0 {
+            format!("Recovered {} bytes; {} run(s) could not be read and were zero-filled.", data.len(), failed_runs)
+        } else if !validation.is_valid {
+            format!("Recovered {} bytes ({}). Its clusters may have been reused.", data.len(), validation.details)
+        } else {
+            format!("Successfully recovered {} bytes", data.len())
+        };
+        
+        Ok(FileRecoveryResult {
+            success: true,
+            source_path: file.path.clone(),
+            destination_path: destination.to_string(),
+            bytes_recovered: data.len() as u64,
+            message,
+        })
+    }
+    
     /// Cancel ongoing scan
     pub fn cancel(&self) {
         self.cancelled.store(true, Ordering::Relaxed);
@@ -771,6 +2091,189 @@ impl RecoveryEngine {
     }
 }
 
+/// Records 0-3 ($MFT, $MFTMirr, $LogFile, $Volume) are mirrored in $MFTMirr
+const MFT_MIRROR_RECORDS: u64 = 4;
+
+/// Raw copy of one of the first MFT records, taken from $MFTMirr when the
+/// $MFT's own copy is damaged. Both copies start at their first cluster.
+fn read_record_or_mirror(disk: &mut DiskReader, boot: &NtfsBootSector, record_number: u64) -> Option<Vec<u8>> {
+    let record_size = boot.mft_record_size as u64;
+    let primary = disk.read_at(boot.mft_cluster * boot.cluster_size as u64 + record_number * record_size, record_size as usize)
+        .ok()
+        .filter(|raw| fixup_record(raw).is_some());
+    if primary.is_some() || record_number >= MFT_MIRROR_RECORDS || boot.mft_mirror_cluster == 0 {
+        return primary;
+    }
+    
+    let mirror = disk.read_at(boot.mft_mirror_cluster * boot.cluster_size as u64 + record_number * record_size, record_size as usize)
+        .ok()
+        .filter(|raw| fixup_record(raw).is_some());
+    if mirror.is_some() {
+        eprintln!("DEBUG: MFT record {} damaged, using its $MFTMirr copy", record_number);
+    }
+    mirror
+}
+
+/// Map the $MFT from the FILE records found on the volume
+fn rebuild_mft_layout(disk: &mut DiskReader, boot: &NtfsBootSector, cancelled: &AtomicBool) -> Result<MftLayout, String> {
+    let sightings = scan_file_records(disk, boot.mft_record_size, boot.cluster_size, cancelled)?;
+    let (runs, size) = rebuild_mft_runs(&sightings, boot.mft_record_size, boot.cluster_size)
+        .ok_or("No intact FILE records found on the volume")?;
+    eprintln!("  - {} intact FILE records found", sightings.len());
+    Ok(MftLayout { runs, size })
+}
+
+/// Whole-disk images and physical drives start with a partition table, not a boot sector
+fn starts_with_partition_table(disk: &mut DiskReader) -> bool {
+    read_partition_table(disk).is_ok_and(|table| !table.partitions.is_empty())
+}
+
+/// Map the $MFT from record 0's unnamed $DATA attribute. When the run list
+/// outgrew record 0, $ATTRIBUTE_LIST points at extension records holding the
+/// later segments; those records are read through the extents mapped so far.
+fn load_mft_layout(disk: &mut DiskReader, boot: &NtfsBootSector) -> Result<MftLayout, String> {
+    let record_size = boot.mft_record_size as usize;
+    let cluster_size = boot.cluster_size;
+    let record0 = read_record_or_mirror(disk, boot, 0)
+        .and_then(|raw| fixup_record(&raw))
+        .ok_or("MFT record 0 has no FILE signature in $MFT or $MFTMirr")?;
+    
+    let mut segments: Vec<AttributeSegment> = data_segments(&record0);
+    let mut list_entries = Vec::new();
+    for attr in record_attributes(&record0).iter().filter(|a| a.attr_type == ATTRIBUTE_ATTRIBUTE_LIST) {
+        if let Some(content) = attr.resident_content() {
+            list_entries = parse_attribute_list(content);
+        } else if let Some(list) = attr.segment() {
+            let content = read_runs_range(disk, &list.runs, cluster_size, 0, list.sizes.real as usize)?;
+            list_entries = parse_attribute_list(&content);
+        }
+    }
+    
+    let size = segments
+        .iter()
+        .find(|s| s.starting_vcn == 0)
+        .map(|s| s.sizes.real)
+        .ok_or("MFT record 0 has no $DATA run list")?;
+    
+    let mut extension_records: Vec<(u64, u64)> = list_entries
+        .iter()
+        .filter(|e| e.attr_type == ATTRIBUTE_DATA && e.name.is_empty() && e.record_number != 0)
+        .map(|e| (e.starting_vcn, e.record_number))
+        .collect();
+    extension_records.sort();
+    extension_records.dedup_by_key(|e| e.1);
+    
+    for (_, record_number) in extension_records {
+        let runs = stitch_segments(segments.clone());
+        let raw = read_runs_range(disk, &runs, cluster_size, record_number * record_size as u64, record_size)?;
+        let Some(record) = fixup_record(&raw) else {
+            eprintln!("DEBUG: $MFT extension record {} is unreadable", record_number);
+            continue;
+        };
+        segments.extend(data_segments(&record));
+    }
+    
+    Ok(MftLayout { runs: stitch_segments(segments), size })
+}
+
+/// Pull the rest of a file's $DATA run list out of its extension records
+fn resolve_extension_records(
+    disk: &mut DiskReader,
+    mft_runs: &[DataRun],
+    cluster_size: u32,
+    record_size: usize,
+    entry: &mut MftEntry,
+) {
+    if entry.attribute_list.is_empty() {
+        // NTFS caps attribute lists at 256 KB; larger sizes mean a damaged record
+        let list_size = entry.attribute_list_size.min(256 * 1024) as usize;
+        match read_runs_range(disk, &entry.attribute_list_runs, cluster_size, 0, list_size) {
+            Ok(content) => entry.attribute_list = parse_attribute_list(&content),
+            Err(e) => {
+                eprintln!("Warning: Failed to read attribute list of record {}: {}", entry.record_number, e);
+                return;
+            }
+        }
+    }
+    
+    let resolved = resolve_attribute_list(entry, |record_number| {
+        read_runs_range(disk, mft_runs, cluster_size, record_number * record_size as u64, record_size).ok()
+    });
+    if !resolved {
+        eprintln!("Warning: Could not resolve extension records of record {}", entry.record_number);
+    }
+}
+
+/// Recreate every original path of a recovered multi-link file under
+/// `<destination>.links`, hard-linked to the recovered copy where the
+/// destination filesystem allows it
+fn restore_hard_links(file: &RecoverableFile, destination: &str) -> Vec<FileRecoveryResult> {
+    let Some(ref links) = file.hard_links else { return Vec::new() };
+    let folder = std::path::PathBuf::from(format!("{}.links", destination));
+    
+    links.iter().map(|link| {
+        // Drop the volume label; keep the directories below it
+        let target = link.path.split('\\').skip(1)
+            .fold(folder.clone(), |path, part| path.join(sanitize_stream_name(part)));
+        let target_name = target.to_string_lossy().to_string();
+        let restored = target.parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::hard_link(destination, &target)
+                .or_else(|_| std::fs::copy(destination, &target).map(|_| ())));
+        
+        match restored {
+            Ok(()) => FileRecoveryResult {
+                success: true,
+                source_path: link.path.clone(),
+                destination_path: target_name,
+                bytes_recovered: file.size,
+                message: format!("Restored as {}", link.path),
+            },
+            Err(e) => FileRecoveryResult {
+                success: false,
+                source_path: link.path.clone(),
+                destination_path: target_name,
+                bytes_recovered: 0,
+                message: format!("Failed to restore {}: {}", link.path, e),
+            },
+        }
+    }).collect()
+}
+
+/// A volume-relative directory below `folder`, made safe to create locally
+fn local_path(folder: &std::path::Path, relative: &std::path::Path) -> std::path::PathBuf {
+    relative.iter().fold(folder.to_path_buf(), |path, part| path.join(sanitize_stream_name(&part.to_string_lossy())))
+}
+
+/// Where to restore a file of a tree; deleted files that shared a name in the
+/// same directory get their scan id appended
+fn unique_target(folder: &std::path::Path, file: &RecoverableFile, taken: &mut HashSet<std::path::PathBuf>) -> String {
+    let mut target = folder.join(sanitize_stream_name(&file.name));
+    if !taken.insert(target.clone()) {
+        let stem = target.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let name = match target.extension() {
+            Some(extension) => format!("{} ({}).{}", stem, file.id, extension.to_string_lossy()),
+            None => format!("{} ({})", stem, file.id),
+        };
+        target = folder.join(sanitize_stream_name(&name));
+        taken.insert(target.clone());
+    }
+    target.to_string_lossy().to_string()
+}
+
+/// Stream names may contain characters that are not valid in file names
+fn sanitize_stream_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control() { '_' } else { c })
+        .collect();
+    if cleaned.is_empty() || cleaned == "." || cleaned == ".." {
+        "unnamed_stream".to_string()
+    } else {
+        cleaned
+    }
+}
+
 /// Validation result for recovered file
 struct ValidationResult {
     is_valid: bool,
@@ -837,6 +2340,99 @@ fn calculate_recovery_chance(entry: &MftEntry) -> u8 {
     chance.min(100)
 }
 
+/// Volume-relative path of a directory, from the directory names gathered by the MFT scan
+fn directory_path(directories: &HashMap<u64, DirectoryRecord>, mut record: u64) -> Option<String> {
+    let mut parts = Vec::new();
+    while record != ROOT_DIRECTORY_RECORD {
+        let directory = directories.get(&record)?;
+        if parts.len() >= MAX_PATH_DEPTH {
+            return None;
+        }
+        parts.push(directory.name.as_str());
+        record = directory.parent_record;
+    }
+    parts.reverse();
+    Some(parts.join("\\"))
+}
+
+/// Every path of a file linked into more than one directory (or under more than one name)
+fn hard_links(entry: &MftEntry, directories: &HashMap<u64, DirectoryRecord>, path_root: &str) -> Option<Vec<HardLink>> {
+    let links = entry.hard_links();
+    if links.len() < 2 {
+        return None;
+    }
+    
+    Some(links.into_iter().map(|link| HardLink {
+        name: link.name.clone(),
+        namespace: link.namespace,
+        parent_record: link.parent_record,
+        path: match directory_path(directories, link.parent_record) {
+            Some(dir) if dir.is_empty() => format!("{}\\{}", path_root, link.name),
+            Some(dir) => format!("{}\\{}\\{}", path_root, dir, link.name),
+            None => format!("{}\\[Deleted]\\{}", path_root, link.name),
+        },
+    }).collect())
+}
+
+/// Add the clusters of every stream of an MFT entry to the ownership map
+fn map_entry_clusters(map: &mut ClusterMap, entry: &MftEntry) {
+    map.add(entry.record_number, "", &entry.file_name, entry.is_deleted, &entry.data_runs);
+    for stream in &entry.streams {
+        let name = format!("{}:{}", entry.file_name, stream.name);
+        map.add(entry.record_number, &stream.name, &name, entry.is_deleted, &stream.data_runs);
+    }
+}
+
+/// Attach a deleted item's cross-links; clusters a live file now owns count as overwritten
+fn apply_cross_links(file: &mut RecoverableFile, links: Option<Vec<CrossLink>>) {
+    let Some(links) = links else { return };
+    let mapped: u64 = file.fragments.iter().flatten()
+        .filter(|f| f.cluster > 0)
+        .map(|f| f.size)
+        .sum();
+    let claimed = links.iter()
+        .filter(|link| !link.is_deleted)
+        .map(|link| link.length)
+        .sum::<u64>()
+        .min(mapped);
+    
+    if claimed > 0 {
+        file.recovery_chance = file.recovery_chance.min(unallocated_quality(mapped - claimed, mapped));
+        file.difficulty = difficulty_for(file.recovery_chance).to_string();
+        file.partial_recovery = file.recovery_chance < 80;
+        file.recoverable_bytes = file.recoverable_bytes.min(file.size.saturating_sub(claimed));
+    }
+    file.cross_links = Some(links);
+}
+
+/// Score for content stored inside an MFT record: nothing on disk can have
+/// overwritten it, unless the record itself was torn mid-write
+fn resident_possibility(size: u64, intact: bool) -> (u8, String, Vec<FileFragment>) {
+    let quality = if intact { 99 } else { 60 };
+    (quality, difficulty_for(quality).to_string(), vec![FileFragment {
+        offset: 0,
+        size,
+        cluster: 0,
+        is_readable: true,
+        data_quality: quality,
+    }])
+}
+
+/// Quality score for a run of clusters of which `free` are still unallocated
+fn unallocated_quality(free: u64, total: u64) -> u8 {
+    (5 + 80 * free / total.max(1)) as u8
+}
+
+/// Difficulty label for a recovery chance
+fn difficulty_for(recovery_chance: u8) -> &'static str {
+    match recovery_chance {
+        80..=100 => "easy",
+        50..=79 => "moderate",
+        20..=49 => "hard",
+        _ => "very_hard",
+    }
+}
+
 /// Estimate how long ago a file was deleted
 fn estimate_file_age(modified_time: i64, current_time: i64) -> String {
     if modified_time <= 0 {
@@ -912,6 +2508,22 @@ fn format_timestamp(unix_ts: i64) -> String {
         .unwrap_or_else(|| "Unknown".to_string())
 }
 
+/// Format a FILETIME; unlike format_timestamp, dates before 1970 are kept
+fn format_filetime(ft: i64) -> String {
+    if ft <= 0 {
+        return "Unknown".to_string();
+    }
+    
+    chrono::DateTime::from_timestamp(ft / 10_000_000 - 11_644_473_600, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// Format a FAT timestamp (local time, as the volume stores it)
+fn format_fat_time(time: Option<chrono::NaiveDateTime>) -> String {
+    time.map_or_else(|| "Unknown".to_string(), |t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
 /// Format file size
 fn format_size(bytes: u64) -> String {
     const KB: u64 = 1024;
@@ -931,16 +2543,22 @@ fn format_size(bytes: u64) -> String {
 
 /// Perform a standalone scan (used from main.rs)
 pub fn perform_scan(drive_letter: &str, mode: &str) -> RecoveryScanResult {
-    let mut engine = RecoveryEngine::new(drive_letter);
+    perform_scan_source(&ScanSource::Volume(drive_letter.to_string()), mode)
+}
+
+/// Perform a standalone scan of a live volume or a disk image
+pub fn perform_scan_source(source: &ScanSource, mode: &str) -> RecoveryScanResult {
+    let mut engine = RecoveryEngine::with_source(source.clone());
+    let drive_letter = engine.drive_letter.clone();
     
-    // Check admin first
-    if !engine.check_admin() {
+    // Check admin first (image files are readable without elevation)
+    if !source.is_image() && !engine.check_admin() {
         return RecoveryScanResult {
             success: false,
             message: "Administrator privileges required. Please run as Administrator.".to_string(),
             scan_mode: mode.to_string(),
             drive: drive_letter.to_string(),
-            bitlocker_status: Some(engine.check_bitlocker()),
+            bitlocker_status: engine.bitlocker_for_result(),
             mft_entries: Vec::new(),
             carved_files: Vec::new(),
             orphan_files: Vec::new(),
@@ -951,18 +2569,19 @@ pub fn perform_scan(drive_letter: &str, mode: &str) -> RecoveryScanResult {
             mft_records_scanned: 0,
             orphan_records_found: 0,
             requires_admin: true,
+            directory_tree: None,
         };
     }
     
     // Check BitLocker
-    let bl_status = engine.check_bitlocker();
-    if bl_status.is_locked {
+    let bl_status = engine.bitlocker_for_result();
+    if bl_status.as_ref().is_some_and(|s| s.is_locked) {
         return RecoveryScanResult {
             success: false,
             message: format!("Drive is BitLocker encrypted and locked. Please unlock with password or recovery key."),
             scan_mode: mode.to_string(),
             drive: drive_letter.to_string(),
-            bitlocker_status: Some(bl_status),
+            bitlocker_status: bl_status,
             mft_entries: Vec::new(),
             carved_files: Vec::new(),
             orphan_files: Vec::new(),
@@ -973,6 +2592,7 @@ pub fn perform_scan(drive_letter: &str, mode: &str) -> RecoveryScanResult {
             mft_records_scanned: 0,
             orphan_records_found: 0,
             requires_admin: false,
+            directory_tree: None,
         };
     }
     
@@ -983,7 +2603,29 @@ pub fn perform_scan(drive_letter: &str, mode: &str) -> RecoveryScanResult {
                 message: e,
                 scan_mode: "Quick".to_string(),
                 drive: drive_letter.to_string(),
-                bitlocker_status: Some(bl_status),
+                bitlocker_status: bl_status,
+                mft_entries: Vec::new(),
+                carved_files: Vec::new(),
+                orphan_files: Vec::new(),
+                total_files: 0,
+                total_recoverable_size: 0,
+                scan_duration_ms: 0,
+                sectors_scanned: 0,
+                mft_records_scanned: 0,
+                orphan_records_found: 0,
+                requires_admin: false,
+                directory_tree: None,
+            })
+        }
+        "mft-rebuild" => {
+            // Map the $MFT from FILE record sightings instead of record 0
+            engine.enable_mft_rebuild();
+            engine.quick_scan().unwrap_or_else(|e| RecoveryScanResult {
+                success: false,
+                message: e,
+                scan_mode: "Quick".to_string(),
+                drive: drive_letter.to_string(),
+                bitlocker_status: bl_status,
                 mft_entries: Vec::new(),
                 carved_files: Vec::new(),
                 orphan_files: Vec::new(),
@@ -994,15 +2636,22 @@ pub fn perform_scan(drive_letter: &str, mode: &str) -> RecoveryScanResult {
                 mft_records_scanned: 0,
                 orphan_records_found: 0,
                 requires_admin: false,
+                directory_tree: None,
             })
         }
-        "deep" => {
-            engine.deep_scan(None).unwrap_or_else(|e| RecoveryScanResult {
+        "deep" | "deep-free" => {
+            // deep-free skips clusters $Bitmap says are still in use
+            let carve_mode = if mode.eq_ignore_ascii_case("deep-free") {
+                CarveMode::UnallocatedOnly
+            } else {
+                CarveMode::AllSectors
+            };
+            engine.deep_scan(None, carve_mode).unwrap_or_else(|e| RecoveryScanResult {
                 success: false,
                 message: e,
                 scan_mode: "Deep".to_string(),
                 drive: drive_letter.to_string(),
-                bitlocker_status: Some(bl_status),
+                bitlocker_status: bl_status,
                 mft_entries: Vec::new(),
                 carved_files: Vec::new(),
                 orphan_files: Vec::new(),
@@ -1013,6 +2662,7 @@ pub fn perform_scan(drive_letter: &str, mode: &str) -> RecoveryScanResult {
                 mft_records_scanned: 0,
                 orphan_records_found: 0,
                 requires_admin: false,
+                directory_tree: None,
             })
         }
         _ => RecoveryScanResult {
@@ -1020,7 +2670,7 @@ pub fn perform_scan(drive_letter: &str, mode: &str) -> RecoveryScanResult {
             message: format!("Unknown scan mode: {}", mode),
             scan_mode: mode.to_string(),
             drive: drive_letter.to_string(),
-            bitlocker_status: Some(bl_status),
+            bitlocker_status: bl_status,
             mft_entries: Vec::new(),
             carved_files: Vec::new(),
             orphan_files: Vec::new(),
@@ -1031,15 +2681,54 @@ pub fn perform_scan(drive_letter: &str, mode: &str) -> RecoveryScanResult {
             mft_records_scanned: 0,
             orphan_records_found: 0,
             requires_admin: false,
+            directory_tree: None,
         },
     }
 }
 
+/// Restore a deleted directory tree from a live volume
+pub fn recover_tree(drive_letter: &str, record_number: u64, destination: &str) -> TreeRecoveryResult {
+    recover_tree_from_source(&ScanSource::Volume(drive_letter.to_string()), record_number, destination)
+}
+
+/// Restore the directory in MFT record `record_number`, with every recoverable
+/// file below it, from a live volume or a disk image
+pub fn recover_tree_from_source(source: &ScanSource, record_number: u64, destination: &str) -> TreeRecoveryResult {
+    let mut engine = RecoveryEngine::with_source(source.clone());
+    engine.initialize()
+        .and_then(|_| engine.recover_tree(record_number, destination))
+        .unwrap_or_else(|e| TreeRecoveryResult {
+            success: false,
+            directory: String::new(),
+            destination_path: destination.to_string(),
+            files_recovered: 0,
+            files_failed: 0,
+            bytes_recovered: 0,
+            files: Vec::new(),
+            message: e,
+        })
+}
+
 /// Recover a single file
 pub fn recover_file(
     drive_letter: &str,
     file_json: &str,
     destination: &str,
+    with_streams: bool,
+    with_hard_links: bool,
+) -> FileRecoveryResult {
+    recover_file_from_source(&ScanSource::Volume(drive_letter.to_string()), file_json, destination, with_streams, with_hard_links)
+}
+
+/// Recover a single file from a live volume or a disk image. With
+/// `with_streams`, its alternate data streams are restored alongside it;
+/// with `with_hard_links`, it is also restored under each of its original paths.
+pub fn recover_file_from_source(
+    source: &ScanSource,
+    file_json: &str,
+    destination: &str,
+    with_streams: bool,
+    with_hard_links: bool,
 ) -> FileRecoveryResult {
     let file: RecoverableFile = match serde_json::from_str(file_json) {
         Ok(f) => f,
@@ -1054,7 +2743,7 @@ pub fn recover_file(
         }
     };
     
-    let mut engine = RecoveryEngine::new(drive_letter);
+    let mut engine = RecoveryEngine::with_source(source.clone());
     
     if let Err(e) = engine.initialize() {
         return FileRecoveryResult {
@@ -1067,10 +2756,49 @@ pub fn recover_file(
     }
     
     match file.source.as_str() {
-        "mft" | "mft_orphan" => engine.recover_from_mft(&file, destination).unwrap_or_else(|e| {
+        "mft" | "mft_orphan" | "index_slack" | "logfile" => {
+            let mut result = engine.recover_from_mft(&file, destination).unwrap_or_else(|e| {
+                FileRecoveryResult {
+                    success: false,
+                    source_path: file.path.clone(),
+                    destination_path: destination.to_string(),
+                    bytes_recovered: 0,
+                    message: e,
+                }
+            });
+            if with_streams && result.success && file.stream_name.is_none() {
+                match engine.recover_streams(&file, destination) {
+                    Ok(streams) if streams.is_empty() => {}
+                    Ok(streams) => {
+                        let restored: Vec<&FileRecoveryResult> = streams.iter().filter(|s| s.success).collect();
+                        result.message = format!(
+                            "{}. Restored {} of {} alternate data stream(s) ({} bytes) to {}.streams",
+                            result.message,
+                            restored.len(),
+                            streams.len(),
+                            restored.iter().map(|s| s.bytes_recovered).sum::<u64>(),
+                            destination
+                        );
+                    }
+                    Err(e) => result.message = format!("{}. Streams not restored: {}", result.message, e),
+                }
+            }
+            if with_hard_links && result.success {
+                let links = restore_hard_links(&file, destination);
+                if !links.is_empty() {
+                    let restored = links.iter().filter(|l| l.success).count();
+                    result.message = format!(
+                        "{}. Restored {} of {} hard link path(s) under {}.links",
+                        result.message, restored, links.len(), destination
+                    );
+                }
+            }
+            result
+        }
+        "fat" | "exfat" => engine.recover_fat(&file, destination).unwrap_or_else(|e| {
             FileRecoveryResult {
                 success: false,
-                source_path: file.path,
+                source_path: file.path.clone(),
                 destination_path: destination.to_string(),
                 bytes_recovered: 0,
                 message: e,
@@ -1085,7 +2813,31 @@ pub fn recover_file(
                 message: e,
             }
         }),
+        // The journal keeps names and reasons, never content
+        "usn_journal" => FileRecoveryResult {
+            success: false,
+            source_path: file.path.clone(),
+            destination_path: destination.to_string(),
+            bytes_recovered: 0,
+            message: format!(
+                "'{}' is only known by name from the USN journal ({}); its content was not found. Try deep scan for file carving.",
+                file.name,
+                file.usn_reasons.as_deref().unwrap_or_default().join(" | ")
+            ),
+        },
         "USN" | "mft_filesystem" => {
+            let drive_letter = match source.drive_letter() {
+                Some(letter) => letter,
+                None => {
+                    return FileRecoveryResult {
+                        success: false,
+                        source_path: file.path,
+                        destination_path: destination.to_string(),
+                        bytes_recovered: 0,
+                        message: format!("Files from source '{}' can only be recovered from a live volume", file.source),
+                    };
+                }
+            };
             // USN and filesystem MFT files were scanned through the FileSystem
             // API (Windows volume handle with BitLocker auto-decryption).
             // Their data-run cluster offsets are logical volume offsets, NOT raw
@@ -0,0 +1,322 @@
+//! USN Change Journal Module
+//! Parses $Extend\$UsnJrnl:$J records (USN_RECORD_V2, V3 and V4) read
+//! straight from a raw volume or image, and carves stray records from
+//! unallocated space
+//!
+//! Works without FSCTL_READ_USN_JOURNAL, so the journal's deletion history
+//! is available for images and on non-Windows hosts. Once the journal wraps,
+//! purged records are still found in clusters it gave back.
+
+use crate::filesystem_disk_reader::UsnDeletedFile;
+use crate::ntfs_parser::filetime_to_unix;
+
+pub const USN_JOURNAL_NAME: &str = "$UsnJrnl";
+pub const USN_STREAM_NAME: &str = "$J";
+
+// Reason flags
+pub const USN_REASON_FILE_CREATE: u32 = 0x0000_0100;
+pub const USN_REASON_FILE_DELETE: u32 = 0x0000_0200;
+pub const USN_REASON_CLOSE: u32 = 0x8000_0000;
+
+/// Names of the reason flags, in bit order
+const REASON_NAMES: &[(u32, &str)] = &[
+    (0x0000_0001, "DATA_OVERWRITE"),
+    (0x0000_0002, "DATA_EXTEND"),
+    (0x0000_0004, "DATA_TRUNCATION"),
+    (0x0000_0010, "NAMED_DATA_OVERWRITE"),
+    (0x0000_0020, "NAMED_DATA_EXTEND"),
+    (0x0000_0040, "NAMED_DATA_TRUNCATION"),
+    (USN_REASON_FILE_CREATE, "FILE_CREATE"),
+    (USN_REASON_FILE_DELETE, "FILE_DELETE"),
+    (0x0000_0400, "EA_CHANGE"),
+    (0x0000_0800, "SECURITY_CHANGE"),
+    (0x0000_1000, "RENAME_OLD_NAME"),
+    (0x0000_2000, "RENAME_NEW_NAME"),
+    (0x0000_4000, "INDEXABLE_CHANGE"),
+    (0x0000_8000, "BASIC_INFO_CHANGE"),
+    (0x0001_0000, "HARD_LINK_CHANGE"),
+    (0x0002_0000, "COMPRESSION_CHANGE"),
+    (0x0004_0000, "ENCRYPTION_CHANGE"),
+    (0x0008_0000, "OBJECT_ID_CHANGE"),
+    (0x0010_0000, "REPARSE_POINT_CHANGE"),
+    (0x0020_0000, "STREAM_CHANGE"),
+    (0x0040_0000, "TRANSACTED_CHANGE"),
+    (0x0080_0000, "INTEGRITY_CHANGE"),
+    (0x0100_0000, "DESIRED_STORAGE_CLASS_CHANGE"),
+    (USN_REASON_CLOSE, "CLOSE"),
+];
+
+const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x10;
+
+/// Journal pages: records never straddle one, the tail is zero padding
+const USN_PAGE_SIZE: usize = 4096;
+
+/// Timestamps accepted for carved records (FILETIME 1980-01-01 .. 2100-01-01)
+const FILETIME_MIN: i64 = 119_600_064_000_000_000;
+const FILETIME_MAX: i64 = 157_469_184_000_000_000;
+
+/// One USN record, whatever its version. V3/V4 carry 128-bit file IDs;
+/// on NTFS the low 64 bits are the MFT reference.
+#[derive(Debug, Clone)]
+pub struct UsnRecord {
+    pub usn: i64,
+    pub file_reference: u64,   // MFT record (low 48 bits) + sequence number
+    pub parent_reference: u64,
+    pub timestamp: i64,        // Raw FILETIME, 0 for V4 range records
+    pub reason: u32,
+    pub file_attributes: u32,
+    pub file_name: String,     // Empty for V4 range records
+}
+
+impl UsnRecord {
+    pub fn is_deletion(&self) -> bool {
+        self.reason & USN_REASON_FILE_DELETE != 0
+    }
+
+    pub fn is_directory(&self) -> bool {
+        self.file_attributes & FILE_ATTRIBUTE_DIRECTORY != 0
+    }
+
+    /// Reason flags by name ("FILE_DELETE", "CLOSE", ...)
+    pub fn reason_names(&self) -> Vec<&'static str> {
+        REASON_NAMES.iter().filter(|(flag, _)| self.reason & flag != 0).map(|&(_, name)| name).collect()
+    }
+
+    /// Same shape as the records FSCTL_READ_USN_JOURNAL yields
+    pub fn to_deleted_file(&self) -> UsnDeletedFile {
+        UsnDeletedFile {
+            file_name: self.file_name.clone(),
+            mft_record: self.file_reference & 0x0000_FFFF_FFFF_FFFF,
+            parent_mft_record: self.parent_reference & 0x0000_FFFF_FFFF_FFFF,
+            timestamp: filetime_to_unix(self.timestamp),
+            file_attributes: self.file_attributes,
+            reason: self.reason,
+        }
+    }
+}
+
+fn read_u16(data: &[u8], at: usize) -> usize {
+    u16::from_le_bytes([data[at], data[at + 1]]) as usize
+}
+
+fn read_u32(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(data[at..at + 8].try_into().unwrap())
+}
+
+fn read_name(record: &[u8], offset: usize, length: usize) -> Option<String> {
+    if length == 0 || !length.is_multiple_of(2) {
+        return None;
+    }
+    let bytes = record.get(offset..offset + length)?;
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+    String::from_utf16(&units).ok().filter(|n| !n.chars().any(char::is_control))
+}
+
+/// Parse the USN record at the start of `data`
+pub fn parse_usn_record(data: &[u8]) -> Option<UsnRecord> {
+    if data.len() < 8 {
+        return None;
+    }
+    let length = read_u32(data, 0) as usize;
+    if length < 0x40 || !length.is_multiple_of(8) || length > data.len() {
+        return None;
+    }
+    let record = &data[..length];
+    let major_version = read_u16(record, 4) as u16;
+
+    match major_version {
+        2 => {
+            let name_length = read_u16(record, 56);
+            let name_offset = read_u16(record, 58);
+            Some(UsnRecord {
+                usn: read_u64(record, 24) as i64,
+                file_reference: read_u64(record, 8),
+                parent_reference: read_u64(record, 16),
+                timestamp: read_u64(record, 32) as i64,
+                reason: read_u32(record, 40),
+                file_attributes: read_u32(record, 52),
+                file_name: (name_offset == 60).then(|| read_name(record, name_offset, name_length)).flatten()?,
+            })
+        }
+        3 if length >= 0x50 => {
+            let name_length = read_u16(record, 72);
+            let name_offset = read_u16(record, 74);
+            Some(UsnRecord {
+                usn: read_u64(record, 40) as i64,
+                file_reference: read_u64(record, 8),
+                parent_reference: read_u64(record, 24),
+                timestamp: read_u64(record, 48) as i64,
+                reason: read_u32(record, 56),
+                file_attributes: read_u32(record, 68),
+                file_name: (name_offset == 76).then(|| read_name(record, name_offset, name_length)).flatten()?,
+            })
+        }
+        4 => {
+            // Range records: only the changed extents follow the header, and
+            // they must fit in the record
+            let count = read_u16(record, 60);
+            let extent_size = read_u16(record, 62).max(16);
+            if 64 + count * extent_size > length {
+                return None;
+            }
+            Some(UsnRecord {
+                usn: read_u64(record, 40) as i64,
+                file_reference: read_u64(record, 8),
+                parent_reference: read_u64(record, 24),
+                timestamp: 0,
+                reason: read_u32(record, 48),
+                file_attributes: 0,
+                file_name: String::new(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Walk a block of $J that starts at journal offset `base`. Each record's
+/// USN is its own offset in the stream, which rejects leftovers from
+/// before the block was last written.
+pub fn parse_usn_journal(data: &[u8], base: u64) -> Vec<UsnRecord> {
+    let mut records = Vec::new();
+    let mut offset = 0usize;
+
+    while offset + 8 <= data.len() {
+        if read_u32(data, offset) == 0 {
+            // Padding to the end of the journal page
+            offset = ((base + offset as u64 + 1).next_multiple_of(USN_PAGE_SIZE as u64) - base) as usize;
+            continue;
+        }
+        match parse_usn_record(&data[offset..]).filter(|r| r.usn as u64 == base + offset as u64) {
+            Some(record) => {
+                offset += read_u32(data, offset) as usize;
+                records.push(record);
+            }
+            None => offset += 8,
+        }
+    }
+
+    records
+}
+
+/// Carve V2/V3 records from arbitrary data. V4 range records carry no name
+/// or timestamp, so they are too weak to trust outside the journal.
+pub fn carve_usn_records(data: &[u8]) -> Vec<UsnRecord> {
+    let mut records = Vec::new();
+    let mut offset = 0usize;
+
+    while offset + 0x40 <= data.len() {
+        // Cheap pre-check before parsing: version 2 or 3, minor version 0
+        let plausible = matches!(read_u16(data, offset + 4), 2 | 3) && read_u16(data, offset + 6) == 0;
+        let record = plausible
+            .then(|| parse_usn_record(&data[offset..]))
+            .flatten()
+            .filter(|r| r.usn >= 0 && r.reason != 0 && (FILETIME_MIN..FILETIME_MAX).contains(&r.timestamp));
+        match record {
+            Some(record) => {
+                offset += read_u32(data, offset) as usize;
+                records.push(record);
+            }
+            None => offset += 8,
+        }
+    }
+
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FT_2020: i64 = 132_224_352_000_000_000;
+
+    fn v2(usn: i64, record: u64, reason: u32, name: &str) -> Vec<u8> {
+        let name: Vec<u8> = name.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        let length = (60 + name.len()).next_multiple_of(8);
+        let mut r = vec![0u8; length];
+        r[0..4].copy_from_slice(&(length as u32).to_le_bytes());
+        r[4..6].copy_from_slice(&2u16.to_le_bytes());
+        r[8..16].copy_from_slice(&(record | 3 << 48).to_le_bytes());
+        r[16..24].copy_from_slice(&(5u64 | 5 << 48).to_le_bytes());
+        r[24..32].copy_from_slice(&usn.to_le_bytes());
+        r[32..40].copy_from_slice(&FT_2020.to_le_bytes());
+        r[40..44].copy_from_slice(&reason.to_le_bytes());
+        r[52..56].copy_from_slice(&0x20u32.to_le_bytes());
+        r[56..58].copy_from_slice(&(name.len() as u16).to_le_bytes());
+        r[58..60].copy_from_slice(&60u16.to_le_bytes());
+        r[60..60 + name.len()].copy_from_slice(&name);
+        r
+    }
+
+    fn v3(usn: i64, record: u64, reason: u32, name: &str) -> Vec<u8> {
+        let name: Vec<u8> = name.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        let length = (76 + name.len()).next_multiple_of(8);
+        let mut r = vec![0u8; length];
+        r[0..4].copy_from_slice(&(length as u32).to_le_bytes());
+        r[4..6].copy_from_slice(&3u16.to_le_bytes());
+        r[8..16].copy_from_slice(&(record | 1 << 48).to_le_bytes());
+        r[24..32].copy_from_slice(&40u64.to_le_bytes());
+        r[40..48].copy_from_slice(&usn.to_le_bytes());
+        r[48..56].copy_from_slice(&FT_2020.to_le_bytes());
+        r[56..60].copy_from_slice(&reason.to_le_bytes());
+        r[72..74].copy_from_slice(&(name.len() as u16).to_le_bytes());
+        r[74..76].copy_from_slice(&76u16.to_le_bytes());
+        r[76..76 + name.len()].copy_from_slice(&name);
+        r
+    }
+
+    fn v4(usn: i64, record: u64) -> Vec<u8> {
+        let mut r = vec![0u8; 80];
+        r[0..4].copy_from_slice(&80u32.to_le_bytes());
+        r[4..6].copy_from_slice(&4u16.to_le_bytes());
+        r[8..16].copy_from_slice(&(record | 1 << 48).to_le_bytes());
+        r[40..48].copy_from_slice(&usn.to_le_bytes());
+        r[48..52].copy_from_slice(&USN_REASON_CLOSE.to_le_bytes());
+        r[60..62].copy_from_slice(&1u16.to_le_bytes());
+        r[62..64].copy_from_slice(&16u16.to_le_bytes());
+        r[64..72].copy_from_slice(&4096i64.to_le_bytes());
+        r[72..80].copy_from_slice(&8192i64.to_le_bytes());
+        r
+    }
+
+    #[test]
+    fn test_journal_block_with_all_record_versions() {
+        let base = 0x10000u64;
+        let mut block = Vec::new();
+        block.extend(v2(base as i64, 40, USN_REASON_FILE_CREATE, "a.txt"));
+        block.extend(v3(base as i64 + block.len() as i64, 41, USN_REASON_FILE_DELETE | USN_REASON_CLOSE, "report.pdf"));
+        block.extend(v4(base as i64 + block.len() as i64, 41));
+        // A stale record whose USN does not match its position is ignored
+        block.extend(v2(7, 42, USN_REASON_FILE_DELETE, "stale.doc"));
+        block.resize(USN_PAGE_SIZE, 0);
+        block.extend(v2(base as i64 + USN_PAGE_SIZE as i64, 43, USN_REASON_FILE_DELETE, "next.bin"));
+
+        let records = parse_usn_journal(&block, base);
+        let names: Vec<&str> = records.iter().map(|r| r.file_name.as_str()).collect();
+        assert_eq!(names, vec!["a.txt", "report.pdf", "", "next.bin"]);
+        assert_eq!((records[2].file_reference & 0xFFFF, records[2].reason_names()), (41, vec!["CLOSE"]));
+        assert_eq!(records[1].reason_names(), vec!["FILE_DELETE", "CLOSE"]);
+
+        let deleted = records[1].to_deleted_file();
+        assert!(records[1].is_deletion());
+        assert_eq!(deleted.mft_record, 41);
+        assert_eq!(deleted.parent_mft_record, 40);
+        assert_eq!(deleted.timestamp, filetime_to_unix(FT_2020));
+    }
+
+    #[test]
+    fn test_carve_records_from_unallocated_space() {
+        let mut cluster = vec![0xA5u8; 24];
+        cluster.extend(v2(123_456, 50, USN_REASON_FILE_DELETE, "lost.xlsx"));
+        cluster.extend(v4(123_600, 50));
+        cluster.resize(4096, 0);
+
+        let carved = carve_usn_records(&cluster);
+        assert_eq!(carved.len(), 1);
+        assert_eq!(carved[0].file_name, "lost.xlsx");
+        assert_eq!(carved[0].usn, 123_456);
+    }
+}
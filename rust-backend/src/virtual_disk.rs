@@ -0,0 +1,1956 @@
+//! Virtual Disk Container Module
+//! Presents VM disk images as a linear, seekable disk so the raw-disk engine can
+//! scan a volume inside them without converting the image first
+//!
+//! Supports:
+//! - VHD: fixed, dynamic and differencing
+//! - VHDX: BAT lookup, sector bitmaps, differencing, log replay (in memory only)
+//! - VMDK: monolithic/split sparse and flat extents, streamOptimized grains, delta links
+//! - QCOW2: v2/v3 L1/L2 tables, zero and compressed clusters, backing files
+//! - VDI: fixed, dynamic and differencing block maps
+//!
+//! Images are never written to: VHDX log replay is applied to an in-memory overlay.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use flate2::read::{DeflateDecoder, ZlibDecoder};
+use serde::{Deserialize, Serialize};
+
+const SECTOR: u64 = 512;
+const MAX_PARENT_DEPTH: usize = 32;
+
+/// Description of an opened virtual disk and its parent chain
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VirtualDiskInfo {
+    pub format: String,
+    pub variant: String,  // "fixed", "dynamic", "differencing", "monolithicSparse", ...
+    pub path: String,
+    pub virtual_size: u64,
+    pub sector_size: u32,
+    pub parent: Option<Box<VirtualDiskInfo>>,
+}
+
+/// One layer of a virtual disk chain: maps virtual offsets onto its container file(s)
+trait DiskLayer: Send {
+    fn size(&self) -> u64;
+
+    /// Read at `offset`. May stop early at an allocation boundary, but returns at
+    /// least one byte unless `offset` is at or past the end of the disk.
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize>;
+
+    fn info(&self) -> VirtualDiskInfo;
+}
+
+/// Seekable linear view over a virtual disk (and its parents)
+pub struct VirtualDisk {
+    layer: Box<dyn DiskLayer>,
+    position: u64,
+}
+
+impl VirtualDisk {
+    /// Open a VM disk image. Returns Ok(None) when the file is not a recognised container.
+    pub fn open(path: &str) -> Result<Option<Self>, String> {
+        let layer = open_layer(Path::new(path), 0)?;
+        Ok(layer.map(|layer| {
+            let info = layer.info();
+            eprintln!(
+                "[VDISK] Opened {} {} image {}: {} bytes",
+                info.variant, info.format, path, info.virtual_size
+            );
+            VirtualDisk { layer, position: 0 }
+        }))
+    }
+
+    /// Virtual disk size in bytes
+    pub fn size(&self) -> u64 {
+        self.layer.size()
+    }
+
+    /// Logical sector size presented to the guest
+    pub fn sector_size(&self) -> usize {
+        self.layer.info().sector_size as usize
+    }
+
+    /// Format, variant and parent chain
+    pub fn info(&self) -> VirtualDiskInfo {
+        self.layer.info()
+    }
+}
+
+impl Read for VirtualDisk {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut written = 0;
+        while written < buf.len() && self.position < self.layer.size() {
+            let n = self.layer.read_at(self.position, &mut buf[written..])?;
+            if n == 0 {
+                break;
+            }
+            written += n;
+            self.position += n as u64;
+        }
+        Ok(written)
+    }
+}
+
+impl Seek for VirtualDisk {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => offset as i128,
+            SeekFrom::End(delta) => self.layer.size() as i128 + delta as i128,
+            SeekFrom::Current(delta) => self.position as i128 + delta as i128,
+        };
+        if target < 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "seek before start of virtual disk",
+            ));
+        }
+        self.position = target as u64;
+        Ok(self.position)
+    }
+}
+
+// ============================================================================
+// FORMAT DETECTION
+// ============================================================================
+
+/// Sniff the container format and open it. Ok(None) = not a virtual disk container.
+fn open_layer(path: &Path, depth: usize) -> Result<Option<Box<dyn DiskLayer>>, String> {
+    if depth > MAX_PARENT_DEPTH {
+        return Err(format!("Parent chain deeper than {} images at {}", MAX_PARENT_DEPTH, path.display()));
+    }
+
+    let mut file = File::open(path)
+        .map_err(|e| format!("Failed to open image {}: {}", path.display(), e))?;
+    let file_size = file.metadata().map_err(|e| e.to_string())?.len();
+
+    let mut head = vec![0u8; 512];
+    let head_len = read_up_to(&mut file, 0, &mut head)?;
+    head.truncate(head_len);
+
+    if head.starts_with(b"vhdxfile") {
+        return Ok(Some(Box::new(VhdxLayer::open(path, file, depth)?)));
+    }
+    if head.starts_with(b"KDMV") || head.starts_with(b"# Disk DescriptorFile") {
+        return Ok(Some(Box::new(VmdkLayer::open(path, depth)?)));
+    }
+    if head.starts_with(b"QFI\xfb") {
+        return Ok(Some(Box::new(Qcow2Layer::open(path, file, depth)?)));
+    }
+    if head.len() >= 68 && le32(&head, 64) == VDI_SIGNATURE {
+        return Ok(Some(Box::new(VdiLayer::open(path, file, depth)?)));
+    }
+    if head.starts_with(b"conectix") {
+        return Ok(Some(Box::new(VhdLayer::open(path, file, depth)?)));
+    }
+    if file_size >= 512 {
+        // Fixed VHDs only carry the footer at the end (511 bytes on very old images)
+        let mut tail = [0u8; 512];
+        read_up_to(&mut file, file_size - 512, &mut tail)?;
+        if tail.starts_with(b"conectix") || tail[1..].starts_with(b"conectix") {
+            return Ok(Some(Box::new(VhdLayer::open(path, file, depth)?)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Open a parent/backing image: any supported container, or a raw image
+fn open_parent(path: &Path, depth: usize) -> Result<Box<dyn DiskLayer>, String> {
+    match open_layer(path, depth + 1)? {
+        Some(layer) => Ok(layer),
+        None => Ok(Box::new(RawLayer::open(path)?)),
+    }
+}
+
+/// Find a parent image from the locations recorded in a child image.
+/// Paths are tried as stored, relative to the child, and by file name next to the child.
+fn resolve_parent(child: &Path, candidates: &[String]) -> Option<PathBuf> {
+    let child_dir = child.parent().unwrap_or_else(|| Path::new("."));
+
+    for candidate in candidates.iter().filter(|c| !c.is_empty()) {
+        let normalized = candidate.trim_start_matches("file://").replace('\\', "/");
+        let normalized = normalized.trim_start_matches("./");
+
+        let direct = PathBuf::from(normalized);
+        if direct.is_absolute() && direct.is_file() {
+            return Some(direct);
+        }
+        let relative = child_dir.join(normalized);
+        if relative.is_file() {
+            return Some(relative);
+        }
+        if let Some(name) = Path::new(normalized).file_name() {
+            let sibling = child_dir.join(name);
+            if sibling.is_file() {
+                return Some(sibling);
+            }
+        }
+    }
+    None
+}
+
+/// Read from the parent layer, or zero-fill when there is none (or it is shorter)
+fn read_parent(parent: &mut Option<Box<dyn DiskLayer>>, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+    buf.fill(0);
+    if let Some(parent) = parent {
+        let mut filled = 0;
+        while filled < buf.len() && offset + (filled as u64) < parent.size() {
+            let n = parent.read_at(offset + filled as u64, &mut buf[filled..])?;
+            if n == 0 {
+                break;
+            }
+            filled += n;
+        }
+    }
+    Ok(buf.len())
+}
+
+// ============================================================================
+// RAW (parent images only)
+// ============================================================================
+
+struct RawLayer {
+    file: File,
+    path: String,
+    size: u64,
+}
+
+impl RawLayer {
+    fn open(path: &Path) -> Result<Self, String> {
+        let file = File::open(path)
+            .map_err(|e| format!("Failed to open parent image {}: {}", path.display(), e))?;
+        let size = file.metadata().map_err(|e| e.to_string())?.len();
+        Ok(RawLayer { file, path: path.display().to_string(), size })
+    }
+}
+
+impl DiskLayer for RawLayer {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(self.size.saturating_sub(offset) as usize);
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut buf[..len])?;
+        Ok(len)
+    }
+
+    fn info(&self) -> VirtualDiskInfo {
+        VirtualDiskInfo {
+            format: "raw".to_string(),
+            variant: "flat".to_string(),
+            path: self.path.clone(),
+            virtual_size: self.size,
+            sector_size: SECTOR as u32,
+            parent: None,
+        }
+    }
+}
+
+// ============================================================================
+// VHD
+// ============================================================================
+
+const VHD_TYPE_FIXED: u32 = 2;
+const VHD_TYPE_DYNAMIC: u32 = 3;
+const VHD_TYPE_DIFFERENCING: u32 = 4;
+const VHD_UNUSED_BLOCK: u32 = 0xFFFF_FFFF;
+
+struct VhdLayer {
+    file: File,
+    path: String,
+    size: u64,
+    disk_type: u32,
+    block_size: u64,
+    bitmap_size: u64,
+    bat: Vec<u32>,
+    bitmap_cache: Option<(usize, Vec<u8>)>,
+    parent: Option<Box<dyn DiskLayer>>,
+}
+
+impl VhdLayer {
+    fn open(path: &Path, mut file: File, depth: usize) -> Result<Self, String> {
+        let file_size = file.metadata().map_err(|e| e.to_string())?.len();
+
+        // Prefer the footer at the end; dynamic disks keep a copy at offset 0
+        let mut footer = vec![0u8; 512];
+        read_up_to(&mut file, file_size.saturating_sub(512), &mut footer)?;
+        if !footer.starts_with(b"conectix") {
+            if footer[1..].starts_with(b"conectix") {
+                footer.remove(0);
+                footer.push(0);
+            } else {
+                read_exact_at(&mut file, 0, &mut footer)?;
+                eprintln!("[VDISK] WARNING: VHD footer missing at end of {}, using header copy", path.display());
+            }
+        }
+        if !vhd_checksum_ok(&footer, 64) {
+            eprintln!("[VDISK] WARNING: VHD footer checksum mismatch in {}", path.display());
+        }
+
+        let size = be64(&footer, 48);
+        let disk_type = be32(&footer, 60);
+        let mut layer = VhdLayer {
+            file,
+            path: path.display().to_string(),
+            size,
+            disk_type,
+            block_size: 0,
+            bitmap_size: 0,
+            bat: Vec::new(),
+            bitmap_cache: None,
+            parent: None,
+        };
+
+        match disk_type {
+            VHD_TYPE_FIXED => {
+                if size > file_size.saturating_sub(511) {
+                    eprintln!("[VDISK] WARNING: fixed VHD {} is shorter than its virtual size", path.display());
+                }
+            }
+            VHD_TYPE_DYNAMIC | VHD_TYPE_DIFFERENCING => {
+                let header_offset = be64(&footer, 16);
+                let mut header = vec![0u8; 1024];
+                read_exact_at(&mut layer.file, header_offset, &mut header)?;
+                if !header.starts_with(b"cxsparse") {
+                    return Err(format!("VHD {} has no dynamic disk header", path.display()));
+                }
+                if !vhd_checksum_ok(&header, 36) {
+                    eprintln!("[VDISK] WARNING: VHD dynamic header checksum mismatch in {}", path.display());
+                }
+
+                let table_offset = be64(&header, 16);
+                let entries = be32(&header, 28) as u64;
+                layer.block_size = be32(&header, 32) as u64;
+                if layer.block_size == 0 || !layer.block_size.is_multiple_of(SECTOR) {
+                    return Err(format!("VHD {} has invalid block size {}", path.display(), layer.block_size));
+                }
+                let sectors_per_block = layer.block_size / SECTOR;
+                layer.bitmap_size = sectors_per_block.div_ceil(8).div_ceil(SECTOR) * SECTOR;
+
+                let needed = size.div_ceil(layer.block_size);
+                let mut raw = vec![0u8; table_length(path, "VHD block table", entries, needed, 4, file_size)?];
+                read_exact_at(&mut layer.file, table_offset, &mut raw)?;
+                layer.bat = raw.chunks_exact(4).map(|e| be32(e, 0)).collect();
+
+                if disk_type == VHD_TYPE_DIFFERENCING {
+                    let parent_path = resolve_parent(path, &vhd_parent_candidates(&mut layer.file, &header))
+                        .ok_or_else(|| format!("Parent disk of differencing VHD {} not found", path.display()))?;
+                    layer.parent = Some(open_parent(&parent_path, depth)?);
+                }
+            }
+            other => return Err(format!("Unsupported VHD disk type {} in {}", other, path.display())),
+        }
+
+        Ok(layer)
+    }
+
+    /// Sector bitmap of an allocated block (bit set = sector present in this file)
+    fn block_bitmap(&mut self, block: usize) -> std::io::Result<&[u8]> {
+        if self.bitmap_cache.as_ref().map(|(b, _)| *b) != Some(block) {
+            let mut bitmap = vec![0u8; self.bitmap_size as usize];
+            read_exact_at(&mut self.file, self.bat[block] as u64 * SECTOR, &mut bitmap).map_err(io_error)?;
+            self.bitmap_cache = Some((block, bitmap));
+        }
+        Ok(&self.bitmap_cache.as_ref().unwrap().1)
+    }
+}
+
+impl DiskLayer for VhdLayer {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        if offset >= self.size {
+            return Ok(0);
+        }
+        let max = buf.len().min((self.size - offset) as usize);
+
+        if self.disk_type == VHD_TYPE_FIXED {
+            self.file.seek(SeekFrom::Start(offset))?;
+            self.file.read_exact(&mut buf[..max])?;
+            return Ok(max);
+        }
+
+        let block = (offset / self.block_size) as usize;
+        let within = offset % self.block_size;
+        let len = max.min((self.block_size - within) as usize);
+        let block_sector = self.bat.get(block).copied().unwrap_or(VHD_UNUSED_BLOCK);
+
+        if block_sector == VHD_UNUSED_BLOCK {
+            return read_parent(&mut self.parent, offset, &mut buf[..len]);
+        }
+
+        let data_start = block_sector as u64 * SECTOR + self.bitmap_size;
+        if self.disk_type == VHD_TYPE_DYNAMIC {
+            self.file.seek(SeekFrom::Start(data_start + within))?;
+            self.file.read_exact(&mut buf[..len])?;
+            return Ok(len);
+        }
+
+        // Differencing: consecutive sectors with the same bitmap state come from the same layer
+        let first_sector = within / SECTOR;
+        let bitmap = self.block_bitmap(block)?;
+        let present = |s: u64| bitmap[(s / 8) as usize] & (0x80 >> (s % 8)) != 0;
+        let state = present(first_sector);
+        let mut end = (first_sector + 1) * SECTOR;
+        while end < within + len as u64 && present(end / SECTOR) == state {
+            end += SECTOR;
+        }
+        let run = (end.min(within + len as u64) - within) as usize;
+
+        if state {
+            self.file.seek(SeekFrom::Start(data_start + within))?;
+            self.file.read_exact(&mut buf[..run])?;
+            Ok(run)
+        } else {
+            read_parent(&mut self.parent, offset, &mut buf[..run])
+        }
+    }
+
+    fn info(&self) -> VirtualDiskInfo {
+        VirtualDiskInfo {
+            format: "vhd".to_string(),
+            variant: match self.disk_type {
+                VHD_TYPE_FIXED => "fixed",
+                VHD_TYPE_DYNAMIC => "dynamic",
+                _ => "differencing",
+            }
+            .to_string(),
+            path: self.path.clone(),
+            virtual_size: self.size,
+            sector_size: SECTOR as u32,
+            parent: self.parent.as_ref().map(|p| Box::new(p.info())),
+        }
+    }
+}
+
+/// VHD checksum: one's complement of the byte sum with the checksum field zeroed
+fn vhd_checksum_ok(data: &[u8], checksum_offset: usize) -> bool {
+    let stored = be32(data, checksum_offset);
+    let sum = data
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !(checksum_offset..checksum_offset + 4).contains(i))
+        .fold(0u32, |acc, (_, &b)| acc.wrapping_add(b as u32));
+    !sum == stored
+}
+
+/// Parent paths from the dynamic header's locator entries and parent name
+fn vhd_parent_candidates(file: &mut File, header: &[u8]) -> Vec<String> {
+    let mut candidates = Vec::new();
+
+    for i in 0..8 {
+        let entry = &header[576 + i * 24..576 + (i + 1) * 24];
+        let code = &entry[0..4];
+        let length = be32(entry, 8) as usize;
+        let offset = be64(entry, 16);
+        if length == 0 || length > 64 * 1024 {
+            continue;
+        }
+        let mut raw = vec![0u8; length];
+        if read_exact_at(file, offset, &mut raw).is_err() {
+            continue;
+        }
+        let text = match code {
+            b"W2ru" | b"W2ku" => utf16le(&raw),
+            b"MacX" | b"Mac " => String::from_utf8_lossy(&raw).to_string(),
+            _ => continue,
+        };
+        // Relative locators first: they survive moving the image set
+        if code == b"W2ru" {
+            candidates.insert(0, text);
+        } else {
+            candidates.push(text);
+        }
+    }
+
+    let units: Vec<u16> = header[64..576].chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+    candidates.push(String::from_utf16_lossy(&units).trim_end_matches('\0').to_string());
+    candidates
+}
+
+// ============================================================================
+// VHDX
+// ============================================================================
+
+const VHDX_BAT_GUID: &str = "2DC27766-F623-4200-9D64-115E9BFD4A08";
+const VHDX_METADATA_GUID: &str = "8B7CA206-4790-4B9A-B8FE-575F050F886E";
+const VHDX_FILE_PARAMETERS_GUID: &str = "CAA16737-FA36-4D43-B3B6-33F0AA44E76B";
+const VHDX_VIRTUAL_DISK_SIZE_GUID: &str = "2FA54224-CD1B-4876-B211-5DBED83BF4B8";
+const VHDX_LOGICAL_SECTOR_SIZE_GUID: &str = "8141BF1D-A96F-4709-BA47-F233A8FAAB5F";
+const VHDX_PARENT_LOCATOR_GUID: &str = "A8D35F2D-B30B-454D-ABF7-D3D84834AB0C";
+
+const VHDX_LOG_SECTOR: usize = 4096;
+const VHDX_BLOCK_NOT_PRESENT: u64 = 0;
+const VHDX_BLOCK_UNDEFINED: u64 = 1;
+const VHDX_BLOCK_FULLY_PRESENT: u64 = 6;
+const VHDX_BLOCK_PARTIALLY_PRESENT: u64 = 7;
+const VHDX_OFFSET_MASK: u64 = !0xF_FFFF;
+
+struct VhdxLayer {
+    file: File,
+    overlay: BTreeMap<u64, Vec<u8>>, // Replayed log pages (4 KB aligned file offsets)
+    path: String,
+    size: u64,
+    block_size: u64,
+    logical_sector_size: u64,
+    chunk_ratio: u64,
+    bat: Vec<u64>,
+    bitmap_cache: Option<(u64, Vec<u8>)>,
+    parent: Option<Box<dyn DiskLayer>>,
+}
+
+impl VhdxLayer {
+    fn open(path: &Path, file: File, depth: usize) -> Result<Self, String> {
+        let file_size = file.metadata().map_err(|e| e.to_string())?.len();
+        let mut layer = VhdxLayer {
+            file,
+            overlay: BTreeMap::new(),
+            path: path.display().to_string(),
+            size: 0,
+            block_size: 0,
+            logical_sector_size: SECTOR,
+            chunk_ratio: 1,
+            bat: Vec::new(),
+            bitmap_cache: None,
+            parent: None,
+        };
+
+        // Two header copies; the valid one with the higher sequence number is current
+        let mut current: Option<Vec<u8>> = None;
+        for offset in [64 * 1024u64, 128 * 1024] {
+            let mut header = vec![0u8; 4096];
+            read_exact_at(&mut layer.file, offset, &mut header)?;
+            if !header.starts_with(b"head") || !crc32c_ok(&header, 4) {
+                continue;
+            }
+            if current.as_ref().is_none_or(|c| le64(&header, 8) > le64(c, 8)) {
+                current = Some(header);
+            }
+        }
+        let header = current.ok_or_else(|| format!("VHDX {} has no valid header", path.display()))?;
+
+        let log_guid = &header[48..64];
+        if log_guid.iter().any(|&b| b != 0) {
+            let log_length = le32(&header, 68) as u64;
+            let log_offset = le64(&header, 72);
+            let mut log = vec![0u8; table_length(path, "VHDX log", log_length, log_length, 1, file_size)?];
+            read_exact_at(&mut layer.file, log_offset, &mut log)?;
+            layer.overlay = replay_vhdx_log(&log, log_guid);
+            eprintln!("[VDISK] VHDX log replayed in memory: {} pages", layer.overlay.len());
+        }
+
+        // Region table (two copies)
+        let mut regions: Option<Vec<u8>> = None;
+        for offset in [192 * 1024u64, 256 * 1024] {
+            let mut table = vec![0u8; 64 * 1024];
+            layer.read_file(offset, &mut table).map_err(|e| e.to_string())?;
+            if table.starts_with(b"regi") && crc32c_ok(&table, 4) {
+                regions = Some(table);
+                break;
+            }
+        }
+        let regions = regions.ok_or_else(|| format!("VHDX {} has no valid region table", path.display()))?;
+
+        let mut bat_region = None;
+        let mut metadata_region = None;
+        for i in 0..le32(&regions, 8).min(2047) as usize {
+            let entry = &regions[16 + i * 32..16 + (i + 1) * 32];
+            let region = (le64(entry, 16), le32(entry, 24) as u64);
+            if entry[..16] == guid_bytes(VHDX_BAT_GUID) {
+                bat_region = Some(region);
+            } else if entry[..16] == guid_bytes(VHDX_METADATA_GUID) {
+                metadata_region = Some(region);
+            }
+        }
+        let (bat_offset, bat_length) = bat_region.ok_or("VHDX has no BAT region")?;
+        let (meta_offset, meta_length) = metadata_region.ok_or("VHDX has no metadata region")?;
+
+        let mut metadata = vec![0u8; table_length(path, "VHDX metadata region", meta_length, meta_length, 1, file_size)?];
+        layer.read_file(meta_offset, &mut metadata).map_err(|e| e.to_string())?;
+        if !metadata.starts_with(b"metadata") {
+            return Err(format!("VHDX {} has an invalid metadata region", path.display()));
+        }
+
+        let item = |guid: &str| -> Option<&[u8]> {
+            let wanted = guid_bytes(guid);
+            (0..le16(&metadata, 10) as usize).find_map(|i| {
+                let entry = metadata.get(32 + i * 32..32 + (i + 1) * 32)?;
+                if entry[..16] != wanted {
+                    return None;
+                }
+                let offset = le32(entry, 16) as usize;
+                metadata.get(offset..offset + le32(entry, 20) as usize)
+            })
+        };
+
+        let parameters = item(VHDX_FILE_PARAMETERS_GUID).ok_or("VHDX is missing file parameters")?;
+        layer.block_size = le32(parameters, 0) as u64;
+        let has_parent = le32(parameters, 4) & 2 != 0;
+        layer.size = le64(item(VHDX_VIRTUAL_DISK_SIZE_GUID).ok_or("VHDX is missing virtual disk size")?, 0);
+        layer.logical_sector_size = item(VHDX_LOGICAL_SECTOR_SIZE_GUID).map(|v| le32(v, 0) as u64).unwrap_or(SECTOR);
+        if layer.block_size == 0 || layer.logical_sector_size == 0 {
+            return Err(format!("VHDX {} has invalid block or sector size", path.display()));
+        }
+        layer.chunk_ratio = ((1u64 << 23) * layer.logical_sector_size / layer.block_size).max(1);
+
+        let parent_candidates = if has_parent {
+            let locator = item(VHDX_PARENT_LOCATOR_GUID).ok_or("Differencing VHDX has no parent locator")?;
+            vhdx_parent_candidates(locator)
+        } else {
+            Vec::new()
+        };
+
+        // Payload blocks plus one sector bitmap block per chunk
+        let chunks = layer.size.div_ceil(layer.block_size).div_ceil(layer.chunk_ratio);
+        let needed = chunks.saturating_mul(layer.chunk_ratio + 1);
+        let mut raw = vec![0u8; table_length(path, "VHDX BAT", bat_length / 8, needed, 8, file_size)?];
+        layer.read_file(bat_offset, &mut raw).map_err(|e| e.to_string())?;
+        layer.bat = raw.chunks_exact(8).map(|e| le64(e, 0)).collect();
+
+        if has_parent {
+            let parent_path = resolve_parent(path, &parent_candidates)
+                .ok_or_else(|| format!("Parent disk of differencing VHDX {} not found", path.display()))?;
+            layer.parent = Some(open_parent(&parent_path, depth)?);
+        }
+
+        Ok(layer)
+    }
+
+    /// Read from the container file with replayed log pages applied
+    fn read_file(&mut self, offset: u64, buf: &mut [u8]) -> std::io::Result<()> {
+        buf.fill(0);
+        read_up_to(&mut self.file, offset, buf).map_err(io_error)?;
+
+        let end = offset + buf.len() as u64;
+        let first_page = offset / VHDX_LOG_SECTOR as u64 * VHDX_LOG_SECTOR as u64;
+        for (&page, data) in self.overlay.range(first_page..end) {
+            let from = page.max(offset);
+            let to = (page + VHDX_LOG_SECTOR as u64).min(end);
+            buf[(from - offset) as usize..(to - offset) as usize]
+                .copy_from_slice(&data[(from - page) as usize..(to - page) as usize]);
+        }
+        Ok(())
+    }
+
+    /// Is the given sector of a partially present block stored in this file?
+    fn sector_present(&mut self, block: u64, sector_in_block: u64) -> std::io::Result<bool> {
+        let chunk = block / self.chunk_ratio;
+        let bitmap_entry = self
+            .bat
+            .get((chunk * (self.chunk_ratio + 1) + self.chunk_ratio) as usize)
+            .copied()
+            .unwrap_or(0);
+        if bitmap_entry & 7 != VHDX_BLOCK_FULLY_PRESENT {
+            return Ok(false);
+        }
+
+        if self.bitmap_cache.as_ref().map(|(c, _)| *c) != Some(chunk) {
+            let mut bitmap = vec![0u8; 1024 * 1024];
+            self.read_file(bitmap_entry & VHDX_OFFSET_MASK, &mut bitmap)?;
+            self.bitmap_cache = Some((chunk, bitmap));
+        }
+        let sectors_per_block = self.block_size / self.logical_sector_size;
+        let bit = (block % self.chunk_ratio) * sectors_per_block + sector_in_block;
+        let bitmap = &self.bitmap_cache.as_ref().unwrap().1;
+        Ok(bitmap[(bit / 8) as usize] & (1 << (bit % 8)) != 0)
+    }
+}
+
+impl DiskLayer for VhdxLayer {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        if offset >= self.size {
+            return Ok(0);
+        }
+        let block = offset / self.block_size;
+        let within = offset % self.block_size;
+        let len = buf.len().min((self.block_size - within) as usize).min((self.size - offset) as usize);
+
+        let entry = self.bat.get((block + block / self.chunk_ratio) as usize).copied().unwrap_or(0);
+        let file_offset = entry & VHDX_OFFSET_MASK;
+
+        match entry & 7 {
+            VHDX_BLOCK_FULLY_PRESENT => {
+                self.read_file(file_offset + within, &mut buf[..len])?;
+                Ok(len)
+            }
+            VHDX_BLOCK_PARTIALLY_PRESENT => {
+                let sector = self.logical_sector_size;
+                let first = within / sector;
+                let state = self.sector_present(block, first)?;
+                let mut end = (first + 1) * sector;
+                while end < within + len as u64 && self.sector_present(block, end / sector)? == state {
+                    end += sector;
+                }
+                let run = (end.min(within + len as u64) - within) as usize;
+                if state {
+                    self.read_file(file_offset + within, &mut buf[..run])?;
+                    Ok(run)
+                } else {
+                    read_parent(&mut self.parent, offset, &mut buf[..run])
+                }
+            }
+            VHDX_BLOCK_NOT_PRESENT | VHDX_BLOCK_UNDEFINED => read_parent(&mut self.parent, offset, &mut buf[..len]),
+            _ => {
+                // ZERO / UNMAPPED
+                buf[..len].fill(0);
+                Ok(len)
+            }
+        }
+    }
+
+    fn info(&self) -> VirtualDiskInfo {
+        VirtualDiskInfo {
+            format: "vhdx".to_string(),
+            variant: if self.parent.is_some() { "differencing" } else { "dynamic" }.to_string(),
+            path: self.path.clone(),
+            virtual_size: self.size,
+            sector_size: self.logical_sector_size as u32,
+            parent: self.parent.as_ref().map(|p| Box::new(p.info())),
+        }
+    }
+}
+
+/// Parent paths from a VHDX parent locator (relative first)
+fn vhdx_parent_candidates(locator: &[u8]) -> Vec<String> {
+    let mut values: BTreeMap<String, String> = BTreeMap::new();
+    for i in 0..le16(locator, 18) as usize {
+        let Some(entry) = locator.get(20 + i * 12..20 + (i + 1) * 12) else { break };
+        let (key_offset, value_offset) = (le32(entry, 0) as usize, le32(entry, 4) as usize);
+        let (key_length, value_length) = (le16(entry, 8) as usize, le16(entry, 10) as usize);
+        if let (Some(key), Some(value)) = (
+            locator.get(key_offset..key_offset + key_length),
+            locator.get(value_offset..value_offset + value_length),
+        ) {
+            values.insert(utf16le(key), utf16le(value));
+        }
+    }
+    ["relative_path", "absolute_win32_path", "volume_path"]
+        .iter()
+        .filter_map(|k| values.get(*k).cloned())
+        .collect()
+}
+
+/// A parsed, checksum-verified VHDX log entry
+struct VhdxLogEntry {
+    sequence: u64,
+    tail: usize,
+    data: Vec<u8>,
+}
+
+/// Replay the active VHDX log sequence into an overlay of 4 KB file pages
+fn replay_vhdx_log(log: &[u8], log_guid: &[u8]) -> BTreeMap<u64, Vec<u8>> {
+    let mut overlay = BTreeMap::new();
+    if log.is_empty() || !log.len().is_multiple_of(VHDX_LOG_SECTOR) {
+        return overlay;
+    }
+
+    // Entries may wrap around the end of the circular log
+    let circular = |start: usize, len: usize| -> Vec<u8> {
+        (0..len).map(|i| log[(start + i) % log.len()]).collect()
+    };
+
+    let mut entries: BTreeMap<usize, VhdxLogEntry> = BTreeMap::new();
+    for start in (0..log.len()).step_by(VHDX_LOG_SECTOR) {
+        if &log[start..start + 4] != b"loge" || &log[start + 32..start + 48] != log_guid {
+            continue;
+        }
+        let length = le32(log, start + 8) as usize;
+        if length == 0 || !length.is_multiple_of(VHDX_LOG_SECTOR) || length > log.len() {
+            continue;
+        }
+        let data = circular(start, length);
+        if !crc32c_ok(&data, 4) {
+            continue;
+        }
+        entries.insert(start, VhdxLogEntry {
+            sequence: le64(&data, 16),
+            tail: le32(&data, 12) as usize,
+            data,
+        });
+    }
+
+    // The newest entry names the start (tail) of the active sequence
+    let Some(head) = entries.values().max_by_key(|e| e.sequence) else { return overlay };
+    let head_sequence = head.sequence;
+    let mut position = head.tail;
+    let mut sequence: Vec<&VhdxLogEntry> = Vec::new();
+    while let Some(entry) = entries.get(&position) {
+        if let Some(previous) = sequence.last() {
+            if entry.sequence != previous.sequence + 1 {
+                break;
+            }
+        }
+        sequence.push(entry);
+        if entry.sequence == head_sequence {
+            break;
+        }
+        position = (position + entry.data.len()) % log.len();
+    }
+    if sequence.last().map(|e| e.sequence) != Some(head_sequence) {
+        eprintln!("[VDISK] WARNING: VHDX log sequence is broken, using image without replay");
+        return overlay;
+    }
+
+    for entry in sequence {
+        let data = &entry.data;
+        let descriptor_count = le32(data, 24) as usize;
+        let mut data_sector = (64 + descriptor_count * 32).div_ceil(VHDX_LOG_SECTOR) * VHDX_LOG_SECTOR;
+
+        for d in 0..descriptor_count {
+            let Some(descriptor) = data.get(64 + d * 32..64 + (d + 1) * 32) else { break };
+            let file_offset = le64(descriptor, 16);
+            match &descriptor[..4] {
+                b"zero" => {
+                    let length = le64(descriptor, 8);
+                    for page in (0..length).step_by(VHDX_LOG_SECTOR) {
+                        overlay.insert(file_offset + page, vec![0u8; VHDX_LOG_SECTOR]);
+                    }
+                }
+                b"desc" => {
+                    let Some(sector) = data.get(data_sector..data_sector + VHDX_LOG_SECTOR) else { break };
+                    data_sector += VHDX_LOG_SECTOR;
+                    let sequence = (le32(sector, 4) as u64) << 32 | le32(sector, 4092) as u64;
+                    if &sector[..4] != b"data" || sequence != le64(descriptor, 24) {
+                        continue;
+                    }
+                    let mut page = Vec::with_capacity(VHDX_LOG_SECTOR);
+                    page.extend_from_slice(&descriptor[8..16]); // leading bytes
+                    page.extend_from_slice(&sector[8..4092]);
+                    page.extend_from_slice(&descriptor[4..8]); // trailing bytes
+                    overlay.insert(file_offset, page);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    overlay
+}
+
+/// Verify a CRC-32C stored little-endian at `checksum_offset` (computed with that field zeroed)
+fn crc32c_ok(data: &[u8], checksum_offset: usize) -> bool {
+    let stored = le32(data, checksum_offset);
+    let mut copy = data.to_vec();
+    copy[checksum_offset..checksum_offset + 4].fill(0);
+    crc32c(&copy) == stored
+}
+
+/// CRC-32C (Castagnoli), as used by VHDX
+fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0x82F6_3B78 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// On-disk (mixed-endian) bytes of a GUID string
+fn guid_bytes(guid: &str) -> [u8; 16] {
+    let hex: String = guid.chars().filter(|c| c.is_ascii_hexdigit()).collect();
+    let mut bytes = [0u8; 16];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap_or(0);
+    }
+    bytes[0..4].reverse();
+    bytes[4..6].reverse();
+    bytes[6..8].reverse();
+    bytes
+}
+
+// ============================================================================
+// VMDK
+// ============================================================================
+
+const VMDK_GD_AT_END: u64 = u64::MAX;
+const VMDK_FLAG_COMPRESSED: u32 = 1 << 16;
+
+enum VmdkExtentKind {
+    Flat { file: File, offset: u64 },
+    Sparse(SparseExtent),
+    Zero,
+}
+
+struct VmdkExtent {
+    start: u64,
+    length: u64,
+    kind: VmdkExtentKind,
+}
+
+/// Hosted sparse extent ("KDMV"): grain directory → grain tables → grains
+struct SparseExtent {
+    file: File,
+    grain_size: u64,
+    gtes_per_gt: u64,
+    directory: Vec<u32>,
+    compressed: bool,
+    table_cache: Option<(usize, Vec<u32>)>,
+    grain_cache: Option<(u64, Vec<u8>)>,
+}
+
+/// Where a sparse extent read was served from
+enum GrainRead {
+    Data(usize),
+    Unallocated(usize),
+}
+
+impl SparseExtent {
+    fn open(path: &Path) -> Result<(Self, u64, Option<String>), String> {
+        let mut file = File::open(path)
+            .map_err(|e| format!("Failed to open VMDK extent {}: {}", path.display(), e))?;
+        let mut header = vec![0u8; 512];
+        read_exact_at(&mut file, 0, &mut header)?;
+        if !header.starts_with(b"KDMV") {
+            return Err(format!("{} is not a hosted sparse VMDK extent", path.display()));
+        }
+
+        // Embedded descriptor (monolithicSparse)
+        let descriptor = {
+            let (offset, size) = (le64(&header, 28), le64(&header, 36));
+            if offset > 0 && size > 0 && size < 2048 {
+                let mut raw = vec![0u8; (size * SECTOR) as usize];
+                read_exact_at(&mut file, offset * SECTOR, &mut raw)?;
+                let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+                Some(String::from_utf8_lossy(&raw[..end]).to_string())
+            } else {
+                None
+            }
+        };
+
+        // streamOptimized images write the real header as a footer
+        if le64(&header, 56) == VMDK_GD_AT_END {
+            let file_size = file.metadata().map_err(|e| e.to_string())?.len();
+            read_exact_at(&mut file, file_size.saturating_sub(1024), &mut header)?;
+            if !header.starts_with(b"KDMV") {
+                return Err(format!("VMDK {} footer is missing", path.display()));
+            }
+        }
+
+        let file_size = file.metadata().map_err(|e| e.to_string())?.len();
+        let flags = le32(&header, 8);
+        let sectors = |offset: usize| le64(&header, offset).checked_mul(SECTOR);
+        let (Some(capacity), Some(grain_size), Some(gd_offset)) = (sectors(12), sectors(20), sectors(56)) else {
+            return Err(format!("VMDK {} has out-of-range geometry", path.display()));
+        };
+        let gtes_per_gt = le32(&header, 44) as u64;
+        if grain_size == 0 || gtes_per_gt == 0 || gtes_per_gt * 4 > file_size {
+            return Err(format!("VMDK {} has invalid grain geometry", path.display()));
+        }
+        if flags & VMDK_FLAG_COMPRESSED != 0 && le16(&header, 77) != 1 {
+            return Err(format!("VMDK {} uses an unsupported compression algorithm", path.display()));
+        }
+
+        let tables = capacity.div_ceil(grain_size).div_ceil(gtes_per_gt);
+        let mut raw = vec![0u8; table_length(path, "VMDK grain directory", tables, tables, 4, file_size)?];
+        read_exact_at(&mut file, gd_offset, &mut raw)?;
+
+        Ok((
+            SparseExtent {
+                file,
+                grain_size,
+                gtes_per_gt,
+                directory: raw.chunks_exact(4).map(|e| le32(e, 0)).collect(),
+                compressed: flags & VMDK_FLAG_COMPRESSED != 0,
+                table_cache: None,
+                grain_cache: None,
+            },
+            capacity,
+            descriptor,
+        ))
+    }
+
+    fn read(&mut self, offset: u64, buf: &mut [u8]) -> std::io::Result<GrainRead> {
+        let grain = offset / self.grain_size;
+        let within = offset % self.grain_size;
+        let len = buf.len().min((self.grain_size - within) as usize);
+
+        let table_index = (grain / self.gtes_per_gt) as usize;
+        let table_sector = self.directory.get(table_index).copied().unwrap_or(0);
+        if table_sector == 0 {
+            return Ok(GrainRead::Unallocated(len));
+        }
+        if self.table_cache.as_ref().map(|(i, _)| *i) != Some(table_index) {
+            let mut raw = vec![0u8; (self.gtes_per_gt * 4) as usize];
+            read_exact_at(&mut self.file, table_sector as u64 * SECTOR, &mut raw).map_err(io_error)?;
+            self.table_cache = Some((table_index, raw.chunks_exact(4).map(|e| le32(e, 0)).collect()));
+        }
+        let grain_sector = self.table_cache.as_ref().unwrap().1[(grain % self.gtes_per_gt) as usize] as u64;
+
+        match grain_sector {
+            0 => Ok(GrainRead::Unallocated(len)),
+            1 => {
+                // Zeroed grain (version 2+)
+                buf[..len].fill(0);
+                Ok(GrainRead::Data(len))
+            }
+            _ if self.compressed => {
+                if self.grain_cache.as_ref().map(|(s, _)| *s) != Some(grain_sector) {
+                    let mut marker = [0u8; 12];
+                    read_exact_at(&mut self.file, grain_sector * SECTOR, &mut marker).map_err(io_error)?;
+                    let mut compressed = vec![0u8; le32(&marker, 8) as usize];
+                    read_exact_at(&mut self.file, grain_sector * SECTOR + 12, &mut compressed).map_err(io_error)?;
+                    let mut grain_data = Vec::with_capacity(self.grain_size as usize);
+                    ZlibDecoder::new(&compressed[..]).read_to_end(&mut grain_data)?;
+                    grain_data.resize(self.grain_size as usize, 0);
+                    self.grain_cache = Some((grain_sector, grain_data));
+                }
+                let grain_data = &self.grain_cache.as_ref().unwrap().1;
+                buf[..len].copy_from_slice(&grain_data[within as usize..within as usize + len]);
+                Ok(GrainRead::Data(len))
+            }
+            _ => {
+                self.file.seek(SeekFrom::Start(grain_sector * SECTOR + within))?;
+                self.file.read_exact(&mut buf[..len])?;
+                Ok(GrainRead::Data(len))
+            }
+        }
+    }
+}
+
+struct VmdkLayer {
+    path: String,
+    size: u64,
+    create_type: String,
+    extents: Vec<VmdkExtent>,
+    parent: Option<Box<dyn DiskLayer>>,
+}
+
+impl VmdkLayer {
+    fn open(path: &Path, depth: usize) -> Result<Self, String> {
+        let mut head = [0u8; 4];
+        let mut file = File::open(path).map_err(|e| e.to_string())?;
+        read_exact_at(&mut file, 0, &mut head)?;
+
+        let mut layer = VmdkLayer {
+            path: path.display().to_string(),
+            size: 0,
+            create_type: String::new(),
+            extents: Vec::new(),
+            parent: None,
+        };
+
+        let descriptor = if &head == b"KDMV" {
+            let (extent, capacity, descriptor) = SparseExtent::open(path)?;
+            match descriptor {
+                // The embedded descriptor lists this file as its (only) extent
+                Some(text) => {
+                    drop(extent);
+                    text
+                }
+                None => {
+                    layer.create_type = "monolithicSparse".to_string();
+                    layer.size = capacity;
+                    layer.extents.push(VmdkExtent { start: 0, length: capacity, kind: VmdkExtentKind::Sparse(extent) });
+                    return Ok(layer);
+                }
+            }
+        } else {
+            let mut text = String::new();
+            file.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+            file.take(1024 * 1024).read_to_string(&mut text)
+                .map_err(|e| format!("Failed to read VMDK descriptor {}: {}", path.display(), e))?;
+            text
+        };
+
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let mut parent_hint = None;
+
+        for line in descriptor.lines().map(str::trim) {
+            if let Some(value) = descriptor_value(line, "createType") {
+                layer.create_type = value;
+            } else if let Some(value) = descriptor_value(line, "parentFileNameHint") {
+                parent_hint = Some(value);
+            } else if let Some((sectors, kind, file_name, offset)) = parse_extent_line(line) {
+                let length = sectors * SECTOR;
+                let kind = match kind.as_str() {
+                    "FLAT" | "VMFS" => {
+                        let extent_path = dir.join(&file_name);
+                        let file = File::open(&extent_path)
+                            .map_err(|e| format!("Failed to open VMDK extent {}: {}", extent_path.display(), e))?;
+                        VmdkExtentKind::Flat { file, offset: offset * SECTOR }
+                    }
+                    "SPARSE" => VmdkExtentKind::Sparse(SparseExtent::open(&dir.join(&file_name))?.0),
+                    "ZERO" => VmdkExtentKind::Zero,
+                    other => return Err(format!("Unsupported VMDK extent type {} in {}", other, path.display())),
+                };
+                layer.extents.push(VmdkExtent { start: layer.size, length, kind });
+                layer.size += length;
+            }
+        }
+
+        if layer.extents.is_empty() {
+            return Err(format!("VMDK descriptor {} lists no extents", path.display()));
+        }
+        if let Some(hint) = parent_hint {
+            let parent_path = resolve_parent(path, &[hint])
+                .ok_or_else(|| format!("Parent disk of VMDK {} not found", path.display()))?;
+            layer.parent = Some(open_parent(&parent_path, depth)?);
+        }
+
+        Ok(layer)
+    }
+}
+
+impl DiskLayer for VmdkLayer {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        let Some(index) = self.extents.iter().position(|e| offset >= e.start && offset < e.start + e.length) else {
+            return Ok(0);
+        };
+        let extent = &mut self.extents[index];
+        let within = offset - extent.start;
+        let len = buf.len().min((extent.length - within) as usize);
+
+        let result = match &mut extent.kind {
+            VmdkExtentKind::Flat { file, offset: base } => {
+                file.seek(SeekFrom::Start(*base + within))?;
+                file.read_exact(&mut buf[..len])?;
+                GrainRead::Data(len)
+            }
+            VmdkExtentKind::Sparse(sparse) => sparse.read(within, &mut buf[..len])?,
+            VmdkExtentKind::Zero => GrainRead::Unallocated(len),
+        };
+
+        match result {
+            GrainRead::Data(n) => Ok(n),
+            GrainRead::Unallocated(n) => read_parent(&mut self.parent, offset, &mut buf[..n]),
+        }
+    }
+
+    fn info(&self) -> VirtualDiskInfo {
+        VirtualDiskInfo {
+            format: "vmdk".to_string(),
+            variant: self.create_type.clone(),
+            path: self.path.clone(),
+            virtual_size: self.size,
+            sector_size: SECTOR as u32,
+            parent: self.parent.as_ref().map(|p| Box::new(p.info())),
+        }
+    }
+}
+
+/// `key="value"` from a VMDK descriptor line
+fn descriptor_value(line: &str, key: &str) -> Option<String> {
+    let (k, v) = line.split_once('=')?;
+    (k.trim() == key).then(|| v.trim().trim_matches('"').to_string())
+}
+
+/// `RW 2097152 SPARSE "disk-s001.vmdk" [offset]` → (sectors, type, file, offset)
+fn parse_extent_line(line: &str) -> Option<(u64, String, String, u64)> {
+    let mut parts = line.splitn(3, char::is_whitespace);
+    let access = parts.next()?;
+    if !matches!(access, "RW" | "RDONLY" | "NOACCESS") {
+        return None;
+    }
+    let sectors = parts.next()?.parse::<u64>().ok()?;
+    let rest = parts.next()?.trim();
+    let (kind, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+
+    let rest = rest.trim();
+    let (file_name, tail) = match rest.strip_prefix('"') {
+        Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+        None => ("", rest),
+    };
+    let offset = tail.split_whitespace().next().and_then(|o| o.parse().ok()).unwrap_or(0);
+    Some((sectors, kind.to_string(), file_name.to_string(), offset))
+}
+
+// ============================================================================
+// QCOW2
+// ============================================================================
+
+const QCOW2_OFFSET_MASK: u64 = 0x00FF_FFFF_FFFF_FE00;
+const QCOW2_COMPRESSED: u64 = 1 << 62;
+const QCOW2_ZERO_CLUSTER: u64 = 1;
+const QCOW2_INCOMPAT_COMPRESSION_TYPE: u64 = 1 << 3;
+const QCOW2_INCOMPAT_EXTENDED_L2: u64 = 1 << 4;
+
+struct Qcow2Layer {
+    file: File,
+    path: String,
+    version: u32,
+    size: u64,
+    cluster_bits: u32,
+    l1: Vec<u64>,
+    l2_cache: Option<(usize, Vec<u64>)>,
+    cluster_cache: Option<(u64, Vec<u8>)>,
+    backing: Option<Box<dyn DiskLayer>>,
+}
+
+impl Qcow2Layer {
+    fn open(path: &Path, mut file: File, depth: usize) -> Result<Self, String> {
+        let file_size = file.metadata().map_err(|e| e.to_string())?.len();
+        let mut header = vec![0u8; 104];
+        read_exact_at(&mut file, 0, &mut header)?;
+
+        let version = be32(&header, 4);
+        if version != 2 && version != 3 {
+            return Err(format!("Unsupported QCOW version {} in {}", version, path.display()));
+        }
+        let cluster_bits = be32(&header, 20);
+        if !(9..=21).contains(&cluster_bits) {
+            return Err(format!("QCOW2 {} has invalid cluster size", path.display()));
+        }
+        if be32(&header, 32) != 0 {
+            return Err(format!("QCOW2 {} is encrypted", path.display()));
+        }
+        if version == 3 {
+            let incompatible = be64(&header, 72);
+            if incompatible & (QCOW2_INCOMPAT_EXTENDED_L2 | QCOW2_INCOMPAT_COMPRESSION_TYPE) != 0 {
+                return Err(format!("QCOW2 {} uses unsupported features ({:#x})", path.display(), incompatible));
+            }
+        }
+
+        // Each L1 entry maps one L2 table of cluster_size / 8 clusters
+        let size = be64(&header, 24);
+        let needed = size.div_ceil(1u64 << (2 * cluster_bits - 3));
+        let mut raw = vec![0u8; table_length(path, "QCOW2 L1 table", be32(&header, 36) as u64, needed, 8, file_size)?];
+        read_exact_at(&mut file, be64(&header, 40), &mut raw)?;
+
+        let backing = match (be64(&header, 8), be32(&header, 16) as usize) {
+            (offset, length) if offset != 0 && length > 0 && length < 4096 => {
+                let mut name = vec![0u8; length];
+                read_exact_at(&mut file, offset, &mut name)?;
+                let name = String::from_utf8_lossy(&name).to_string();
+                let backing_path = resolve_parent(path, std::slice::from_ref(&name))
+                    .ok_or_else(|| format!("Backing file {} of {} not found", name, path.display()))?;
+                Some(open_parent(&backing_path, depth)?)
+            }
+            _ => None,
+        };
+
+        Ok(Qcow2Layer {
+            file,
+            path: path.display().to_string(),
+            version,
+            size,
+            cluster_bits,
+            l1: raw.chunks_exact(8).map(|e| be64(e, 0)).collect(),
+            l2_cache: None,
+            cluster_cache: None,
+            backing,
+        })
+    }
+
+    /// L2 entry for a virtual cluster (0 = unallocated)
+    fn l2_entry(&mut self, cluster: u64) -> std::io::Result<u64> {
+        let l2_entries = 1u64 << (self.cluster_bits - 3);
+        let l1_index = (cluster / l2_entries) as usize;
+        let l2_offset = self.l1.get(l1_index).copied().unwrap_or(0) & QCOW2_OFFSET_MASK;
+        if l2_offset == 0 {
+            return Ok(0);
+        }
+        if self.l2_cache.as_ref().map(|(i, _)| *i) != Some(l1_index) {
+            let mut raw = vec![0u8; (l2_entries * 8) as usize];
+            read_exact_at(&mut self.file, l2_offset, &mut raw).map_err(io_error)?;
+            self.l2_cache = Some((l1_index, raw.chunks_exact(8).map(|e| be64(e, 0)).collect()));
+        }
+        Ok(self.l2_cache.as_ref().unwrap().1[(cluster % l2_entries) as usize])
+    }
+}
+
+impl DiskLayer for Qcow2Layer {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        if offset >= self.size {
+            return Ok(0);
+        }
+        let cluster_size = 1u64 << self.cluster_bits;
+        let within = offset % cluster_size;
+        let len = buf.len().min((cluster_size - within) as usize).min((self.size - offset) as usize);
+        let entry = self.l2_entry(offset / cluster_size)?;
+
+        if entry & QCOW2_COMPRESSED != 0 {
+            let shift = 62 - (self.cluster_bits - 8);
+            let host = entry & ((1u64 << shift) - 1);
+            if self.cluster_cache.as_ref().map(|(h, _)| *h) != Some(host) {
+                let sectors = ((entry >> shift) & ((1u64 << (self.cluster_bits - 8)) - 1)) + 1;
+                let mut compressed = vec![0u8; (sectors * SECTOR - (host % SECTOR)) as usize];
+                let read = read_up_to(&mut self.file, host, &mut compressed).map_err(io_error)?;
+                compressed.truncate(read);
+                let mut cluster = Vec::with_capacity(cluster_size as usize);
+                DeflateDecoder::new(&compressed[..])
+                    .take(cluster_size)
+                    .read_to_end(&mut cluster)?;
+                cluster.resize(cluster_size as usize, 0);
+                self.cluster_cache = Some((host, cluster));
+            }
+            let cluster = &self.cluster_cache.as_ref().unwrap().1;
+            buf[..len].copy_from_slice(&cluster[within as usize..within as usize + len]);
+            return Ok(len);
+        }
+
+        if self.version >= 3 && entry & QCOW2_ZERO_CLUSTER != 0 {
+            buf[..len].fill(0);
+            return Ok(len);
+        }
+
+        let host = entry & QCOW2_OFFSET_MASK;
+        if host == 0 {
+            return read_parent(&mut self.backing, offset, &mut buf[..len]);
+        }
+        self.file.seek(SeekFrom::Start(host + within))?;
+        self.file.read_exact(&mut buf[..len])?;
+        Ok(len)
+    }
+
+    fn info(&self) -> VirtualDiskInfo {
+        VirtualDiskInfo {
+            format: "qcow2".to_string(),
+            variant: if self.backing.is_some() { "overlay" } else { "standalone" }.to_string(),
+            path: self.path.clone(),
+            virtual_size: self.size,
+            sector_size: SECTOR as u32,
+            parent: self.backing.as_ref().map(|p| Box::new(p.info())),
+        }
+    }
+}
+
+// ============================================================================
+// VDI
+// ============================================================================
+
+const VDI_SIGNATURE: u32 = 0xBEDA_107F;
+const VDI_TYPE_FIXED: u32 = 2;
+const VDI_TYPE_DIFFERENCING: u32 = 4;
+const VDI_BLOCK_FREE: u32 = 0xFFFF_FFFF;
+const VDI_BLOCK_ZERO: u32 = 0xFFFF_FFFE;
+
+struct VdiLayer {
+    file: File,
+    path: String,
+    image_type: u32,
+    size: u64,
+    block_size: u64,
+    block_extra: u64,
+    data_offset: u64,
+    blocks: Vec<u32>,
+    parent: Option<Box<dyn DiskLayer>>,
+}
+
+impl VdiLayer {
+    fn open(path: &Path, mut file: File, depth: usize) -> Result<Self, String> {
+        let mut header = vec![0u8; 512];
+        read_exact_at(&mut file, 0, &mut header)?;
+        if le32(&header, 68) >> 16 != 1 {
+            return Err(format!("Unsupported VDI version {:#x} in {}", le32(&header, 68), path.display()));
+        }
+
+        let image_type = le32(&header, 76);
+        let size = le64(&header, 368);
+        let block_size = le32(&header, 376) as u64;
+        if block_size == 0 {
+            return Err(format!("VDI {} has zero block size", path.display()));
+        }
+        let file_size = file.metadata().map_err(|e| e.to_string())?.len();
+        let block_count = le32(&header, 384) as u64;
+        let mut raw = vec![0u8; table_length(path, "VDI block map", block_count, size.div_ceil(block_size), 4, file_size)?];
+        read_exact_at(&mut file, le32(&header, 340) as u64, &mut raw)?;
+
+        let mut layer = VdiLayer {
+            file,
+            path: path.display().to_string(),
+            image_type,
+            size,
+            block_size,
+            block_extra: le32(&header, 380) as u64,
+            data_offset: le32(&header, 344) as u64,
+            blocks: raw.chunks_exact(4).map(|e| le32(e, 0)).collect(),
+            parent: None,
+        };
+
+        if image_type == VDI_TYPE_DIFFERENCING {
+            let parent_uuid = header[424..440].to_vec();
+            let parent_path = find_vdi_by_uuid(path, &parent_uuid)
+                .ok_or_else(|| format!("Parent disk of differencing VDI {} not found", path.display()))?;
+            layer.parent = Some(open_parent(&parent_path, depth)?);
+        }
+
+        Ok(layer)
+    }
+}
+
+impl DiskLayer for VdiLayer {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        if offset >= self.size {
+            return Ok(0);
+        }
+        let block = (offset / self.block_size) as usize;
+        let within = offset % self.block_size;
+        let len = buf.len().min((self.block_size - within) as usize).min((self.size - offset) as usize);
+
+        match self.blocks.get(block).copied().unwrap_or(VDI_BLOCK_FREE) {
+            VDI_BLOCK_FREE => read_parent(&mut self.parent, offset, &mut buf[..len]),
+            VDI_BLOCK_ZERO => {
+                buf[..len].fill(0);
+                Ok(len)
+            }
+            index => {
+                let position = self.data_offset
+                    + index as u64 * (self.block_size + self.block_extra)
+                    + self.block_extra
+                    + within;
+                self.file.seek(SeekFrom::Start(position))?;
+                self.file.read_exact(&mut buf[..len])?;
+                Ok(len)
+            }
+        }
+    }
+
+    fn info(&self) -> VirtualDiskInfo {
+        VirtualDiskInfo {
+            format: "vdi".to_string(),
+            variant: match self.image_type {
+                VDI_TYPE_FIXED => "fixed",
+                VDI_TYPE_DIFFERENCING => "differencing",
+                _ => "dynamic",
+            }
+            .to_string(),
+            path: self.path.clone(),
+            virtual_size: self.size,
+            sector_size: SECTOR as u32,
+            parent: self.parent.as_ref().map(|p| Box::new(p.info())),
+        }
+    }
+}
+
+/// VirtualBox links differencing images to their parent by UUID only: look for a
+/// .vdi with that UUID next to the child and one directory up (Snapshots/ layout)
+fn find_vdi_by_uuid(child: &Path, uuid: &[u8]) -> Option<PathBuf> {
+    let dir = child.parent().unwrap_or_else(|| Path::new("."));
+    let search = [Some(dir), dir.parent()];
+
+    for folder in search.into_iter().flatten() {
+        let Ok(entries) = std::fs::read_dir(folder) else { continue };
+        for entry in entries.flatten() {
+            let candidate = entry.path();
+            let is_vdi = candidate.extension().is_some_and(|e| e.eq_ignore_ascii_case("vdi"));
+            if !is_vdi || candidate == child {
+                continue;
+            }
+            let mut header = [0u8; 512];
+            let Ok(mut file) = File::open(&candidate) else { continue };
+            if read_exact_at(&mut file, 0, &mut header).is_ok()
+                && le32(&header, 64) == VDI_SIGNATURE
+                && header[392..408] == *uuid
+            {
+                return Some(candidate);
+            }
+        }
+    }
+    None
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+fn read_exact_at(file: &mut File, offset: u64, buf: &mut [u8]) -> Result<(), String> {
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| format!("Failed to seek to image offset {}: {}", offset, e))?;
+    file.read_exact(buf)
+        .map_err(|e| format!("Failed to read {} bytes at image offset {}: {}", buf.len(), offset, e))
+}
+
+/// Read as much as is available at `offset` (short at end of file)
+fn read_up_to(file: &mut File, offset: u64, buf: &mut [u8]) -> Result<usize, String> {
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| format!("Failed to seek to image offset {}: {}", offset, e))?;
+    let mut filled = 0;
+    while filled < buf.len() {
+        match file.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(format!("Failed to read at image offset {}: {}", offset, e)),
+        }
+    }
+    Ok(filled)
+}
+
+/// Byte length of a table whose entry count comes from an untrusted header.
+/// Entries past those the virtual disk size needs are not read; a table that
+/// still does not fit in the container file is refused before it is allocated.
+fn table_length(path: &Path, what: &str, entries: u64, needed: u64, entry_size: u64, file_size: u64) -> Result<usize, String> {
+    entries
+        .min(needed)
+        .checked_mul(entry_size)
+        .filter(|&length| length <= file_size)
+        .map(|length| length as usize)
+        .ok_or_else(|| format!("{} of {} is larger than the image file", what, path.display()))
+}
+
+fn io_error(message: String) -> std::io::Error {
+    std::io::Error::other(message)
+}
+
+fn utf16le(data: &[u8]) -> String {
+    let units: Vec<u16> = data.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+    String::from_utf16_lossy(&units).trim_end_matches('\0').to_string()
+}
+
+fn le16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn le32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn le64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn be32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn be64(data: &[u8], offset: usize) -> u64 {
+    u64::from_be_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    #[test]
+    fn test_crc32c_and_guid_layout() {
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+        let bat = guid_bytes(VHDX_BAT_GUID);
+        assert_eq!(&bat[..4], &[0x66, 0x77, 0xC2, 0x2D]);
+        assert_eq!(&bat[8..], &[0x9D, 0x64, 0x11, 0x5E, 0x9B, 0xFD, 0x4A, 0x08]);
+    }
+
+    /// Read `len` bytes at `offset` of an opened container
+    fn read_virtual(disk: &mut VirtualDisk, offset: u64, len: usize) -> Vec<u8> {
+        let mut data = vec![0u8; len];
+        disk.seek(SeekFrom::Start(offset)).unwrap();
+        disk.read_exact(&mut data).unwrap();
+        data
+    }
+
+    fn filled(data: &[u8], byte: u8) -> bool {
+        data.iter().all(|&b| b == byte)
+    }
+
+    fn set_vhd_checksum(data: &mut [u8], checksum_offset: usize) {
+        data[checksum_offset..checksum_offset + 4].fill(0);
+        let sum = data.iter().fold(0u32, |acc, &b| acc.wrapping_add(b as u32));
+        data[checksum_offset..checksum_offset + 4].copy_from_slice(&(!sum).to_be_bytes());
+    }
+
+    fn vhd_footer(size: u64, disk_type: u32) -> Vec<u8> {
+        let mut footer = vec![0u8; 512];
+        footer[0..8].copy_from_slice(b"conectix");
+        footer[16..24].copy_from_slice(&512u64.to_be_bytes()); // Dynamic header offset
+        footer[48..56].copy_from_slice(&size.to_be_bytes());
+        footer[60..64].copy_from_slice(&disk_type.to_be_bytes());
+        set_vhd_checksum(&mut footer, 64);
+        footer
+    }
+
+    /// Two 4 KB blocks: block 0 unallocated, block 1 at sector 4 filled with 0x55
+    fn vhd_sparse(disk_type: u32, parent_name: &str, sector_bitmap: u8) -> Vec<u8> {
+        let mut image = vhd_footer(8192, disk_type);
+        let mut header = vec![0u8; 1024];
+        header[0..8].copy_from_slice(b"cxsparse");
+        header[8..16].fill(0xFF);
+        header[16..24].copy_from_slice(&1536u64.to_be_bytes());
+        header[28..32].copy_from_slice(&2u32.to_be_bytes());
+        header[32..36].copy_from_slice(&4096u32.to_be_bytes());
+        for (i, unit) in parent_name.encode_utf16().enumerate() {
+            header[64 + i * 2..66 + i * 2].copy_from_slice(&unit.to_be_bytes());
+        }
+        set_vhd_checksum(&mut header, 36);
+        image.extend_from_slice(&header);
+
+        let mut bat = vec![0xFFu8; 512];
+        bat[4..8].copy_from_slice(&4u32.to_be_bytes());
+        image.extend_from_slice(&bat);
+        let mut bitmap = vec![0u8; 512];
+        bitmap[0] = sector_bitmap;
+        image.extend_from_slice(&bitmap);
+        image.extend_from_slice(&[0x55u8; 4096]);
+        image.extend_from_slice(&vhd_footer(8192, disk_type));
+        image
+    }
+
+    #[test]
+    fn test_vhd_fixed_dynamic_and_differencing() {
+        let dir = std::env::temp_dir();
+        let fixed = dir.join("vdisk_test_fixed.vhd");
+        let dynamic = dir.join("vdisk_test_dynamic.vhd");
+        let parent = dir.join("vdisk_test_vhd_parent.raw");
+        let child = dir.join("vdisk_test_child.vhd");
+
+        let mut image: Vec<u8> = (1..=4u8).flat_map(|sector| [sector; 512]).collect();
+        image.extend_from_slice(&vhd_footer(2048, VHD_TYPE_FIXED));
+        std::fs::write(&fixed, &image).unwrap();
+        let mut disk = VirtualDisk::open(fixed.to_str().unwrap()).unwrap().unwrap();
+        assert_eq!((disk.size(), disk.info().variant.as_str()), (2048, "fixed"));
+        assert!(filled(&read_virtual(&mut disk, 1024, 512), 3));
+
+        std::fs::write(&dynamic, vhd_sparse(VHD_TYPE_DYNAMIC, "", 0)).unwrap();
+        let mut disk = VirtualDisk::open(dynamic.to_str().unwrap()).unwrap().unwrap();
+        assert_eq!((disk.size(), disk.info().variant.as_str()), (8192, "dynamic"));
+        assert!(filled(&read_virtual(&mut disk, 0, 4096), 0));
+        assert!(filled(&read_virtual(&mut disk, 4096, 4096), 0x55));
+
+        // Only sector 1 of block 1 is in the child; the rest comes from the parent
+        std::fs::write(&parent, vec![0x22u8; 8192]).unwrap();
+        std::fs::write(&child, vhd_sparse(VHD_TYPE_DIFFERENCING, "vdisk_test_vhd_parent.raw", 0x40)).unwrap();
+        let mut disk = VirtualDisk::open(child.to_str().unwrap()).unwrap().unwrap();
+        assert_eq!(disk.info().parent.unwrap().format, "raw");
+        assert!(filled(&read_virtual(&mut disk, 0, 4096), 0x22));
+        let block = read_virtual(&mut disk, 4096, 4096);
+        assert!(filled(&block[..512], 0x22));
+        assert!(filled(&block[512..1024], 0x55));
+        assert!(filled(&block[1024..], 0x22));
+
+        for path in [fixed, dynamic, parent, child] {
+            std::fs::remove_file(&path).ok();
+        }
+    }
+
+    const MIB: usize = 1024 * 1024;
+    const VHDX_TEST_BLOCK: u64 = 1 << 31; // Chunk ratio 2 with 512-byte sectors
+
+    /// VHDX of three 2 GiB blocks: log at 1 MiB, metadata at 2 MiB, BAT at 3 MiB.
+    /// `log_page` is replayed over one 4 KB page of the file.
+    fn vhdx_image(bat: &[u64], parent_name: Option<&str>, log_page: Option<(u64, u8)>) -> Vec<u8> {
+        let mut image = vec![0u8; 4 * MIB];
+        image[0..8].copy_from_slice(b"vhdxfile");
+        let log_guid = [0x4Cu8; 16];
+
+        let header = &mut image[64 * 1024..68 * 1024];
+        header[0..4].copy_from_slice(b"head");
+        header[8..16].copy_from_slice(&1u64.to_le_bytes());
+        header[66..68].copy_from_slice(&1u16.to_le_bytes());
+        if log_page.is_some() {
+            header[48..64].copy_from_slice(&log_guid);
+            header[68..72].copy_from_slice(&8192u32.to_le_bytes());
+            header[72..80].copy_from_slice(&(MIB as u64).to_le_bytes());
+        }
+        let checksum = crc32c(header);
+        header[4..8].copy_from_slice(&checksum.to_le_bytes());
+
+        let regions = &mut image[192 * 1024..256 * 1024];
+        regions[0..4].copy_from_slice(b"regi");
+        regions[8..12].copy_from_slice(&2u32.to_le_bytes());
+        for (i, (guid, offset, length)) in [(VHDX_BAT_GUID, 3 * MIB, MIB), (VHDX_METADATA_GUID, 2 * MIB, 64 * 1024)].into_iter().enumerate() {
+            let entry = &mut regions[16 + i * 32..48 + i * 32];
+            entry[0..16].copy_from_slice(&guid_bytes(guid));
+            entry[16..24].copy_from_slice(&(offset as u64).to_le_bytes());
+            entry[24..28].copy_from_slice(&(length as u32).to_le_bytes());
+        }
+        let checksum = crc32c(regions);
+        regions[4..8].copy_from_slice(&checksum.to_le_bytes());
+
+        let mut locator = vec![0u8; 32];
+        locator[18..20].copy_from_slice(&1u16.to_le_bytes());
+        let key: Vec<u8> = "relative_path".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let value: Vec<u8> = parent_name.unwrap_or("").encode_utf16().flat_map(u16::to_le_bytes).collect();
+        locator[20..24].copy_from_slice(&32u32.to_le_bytes());
+        locator[24..28].copy_from_slice(&(32 + key.len() as u32).to_le_bytes());
+        locator[28..30].copy_from_slice(&(key.len() as u16).to_le_bytes());
+        locator[30..32].copy_from_slice(&(value.len() as u16).to_le_bytes());
+        locator.extend_from_slice(&key);
+        locator.extend_from_slice(&value);
+
+        let mut parameters = (VHDX_TEST_BLOCK as u32).to_le_bytes().to_vec();
+        parameters.extend_from_slice(&(if parent_name.is_some() { 2u32 } else { 0 }).to_le_bytes());
+        let items: [(&str, Vec<u8>); 4] = [
+            (VHDX_FILE_PARAMETERS_GUID, parameters),
+            (VHDX_VIRTUAL_DISK_SIZE_GUID, (3 * VHDX_TEST_BLOCK).to_le_bytes().to_vec()),
+            (VHDX_LOGICAL_SECTOR_SIZE_GUID, 512u32.to_le_bytes().to_vec()),
+            (VHDX_PARENT_LOCATOR_GUID, locator),
+        ];
+        let metadata = &mut image[2 * MIB..2 * MIB + 64 * 1024];
+        metadata[0..8].copy_from_slice(b"metadata");
+        metadata[10..12].copy_from_slice(&(items.len() as u16).to_le_bytes());
+        let mut value_offset = 4096;
+        for (i, (guid, value)) in items.iter().enumerate() {
+            let entry = &mut metadata[32 + i * 32..64 + i * 32];
+            entry[0..16].copy_from_slice(&guid_bytes(guid));
+            entry[16..20].copy_from_slice(&(value_offset as u32).to_le_bytes());
+            entry[20..24].copy_from_slice(&(value.len() as u32).to_le_bytes());
+            metadata[value_offset..value_offset + value.len()].copy_from_slice(value);
+            value_offset += 1024;
+        }
+
+        for (i, entry) in bat.iter().enumerate() {
+            image[3 * MIB + i * 8..3 * MIB + (i + 1) * 8].copy_from_slice(&entry.to_le_bytes());
+        }
+
+        if let Some((file_offset, byte)) = log_page {
+            // One entry: header sector with a single data descriptor, then its data sector
+            let log = &mut image[MIB..MIB + 8192];
+            log[0..4].copy_from_slice(b"loge");
+            log[8..12].copy_from_slice(&8192u32.to_le_bytes());
+            log[16..24].copy_from_slice(&5u64.to_le_bytes());
+            log[24..28].copy_from_slice(&1u32.to_le_bytes());
+            log[32..48].copy_from_slice(&log_guid);
+            log[64..68].copy_from_slice(b"desc");
+            log[68..80].fill(byte); // Trailing and leading bytes of the page
+            log[80..88].copy_from_slice(&file_offset.to_le_bytes());
+            log[88..96].copy_from_slice(&5u64.to_le_bytes());
+            log[4096..4100].copy_from_slice(b"data");
+            log[4104..8188].fill(byte);
+            log[8188..8192].copy_from_slice(&5u32.to_le_bytes());
+            let checksum = crc32c(log);
+            log[4..8].copy_from_slice(&checksum.to_le_bytes());
+        }
+        image
+    }
+
+    #[test]
+    fn test_vhdx_chunk_ratio_and_log_replay() {
+        let path = std::env::temp_dir().join("vdisk_test_dynamic.vhdx");
+        // Block 2 follows chunk 0's sector bitmap entry, so it sits at BAT index 3
+        let bat = [(4 * MIB as u64) | VHDX_BLOCK_FULLY_PRESENT, 0, 0, (5 * MIB as u64) | VHDX_BLOCK_FULLY_PRESENT];
+        let mut image = vhdx_image(&bat, None, Some((5 * MIB as u64, 0xCC)));
+        image.resize(5 * MIB + 8192, 0);
+        image[4 * MIB..4 * MIB + 1024].fill(0xAA);
+        image[5 * MIB..5 * MIB + 8192].fill(0xBB);
+        std::fs::write(&path, &image).unwrap();
+
+        let mut disk = VirtualDisk::open(path.to_str().unwrap()).unwrap().unwrap();
+        assert_eq!((disk.size(), disk.info().variant.as_str()), (3 * VHDX_TEST_BLOCK, "dynamic"));
+        assert!(filled(&read_virtual(&mut disk, 0, 1024), 0xAA));
+        assert!(filled(&read_virtual(&mut disk, VHDX_TEST_BLOCK, 1024), 0));
+        let block = read_virtual(&mut disk, 2 * VHDX_TEST_BLOCK, 8192);
+        assert!(filled(&block[..4096], 0xCC)); // Page rewritten by the log
+        assert!(filled(&block[4096..], 0xBB));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_vhdx_differencing_sector_bitmap() {
+        let dir = std::env::temp_dir();
+        let parent = dir.join("vdisk_test_vhdx_parent.raw");
+        let child = dir.join("vdisk_test_child.vhdx");
+        std::fs::write(&parent, vec![0x22u8; 4096]).unwrap();
+
+        // Block 0 is partially present: only sector 1 is set in chunk 0's bitmap
+        let bat = [(4 * MIB as u64) | VHDX_BLOCK_PARTIALLY_PRESENT, 0, (5 * MIB as u64) | VHDX_BLOCK_FULLY_PRESENT];
+        let mut image = vhdx_image(&bat, Some("vdisk_test_vhdx_parent.raw"), None);
+        image.resize(5 * MIB + 4096, 0);
+        image[4 * MIB..4 * MIB + 2048].fill(0xAA);
+        image[5 * MIB] = 0b10;
+        std::fs::write(&child, &image).unwrap();
+
+        let mut disk = VirtualDisk::open(child.to_str().unwrap()).unwrap().unwrap();
+        let info = disk.info();
+        assert_eq!((info.variant.as_str(), info.parent.unwrap().format.as_str()), ("differencing", "raw"));
+        let data = read_virtual(&mut disk, 0, 2048);
+        assert!(filled(&data[..512], 0x22));
+        assert!(filled(&data[512..1024], 0xAA));
+        assert!(filled(&data[1024..], 0x22));
+        // Not present in the child and past the end of the parent
+        assert!(filled(&read_virtual(&mut disk, VHDX_TEST_BLOCK, 512), 0));
+
+        std::fs::remove_file(&parent).ok();
+        std::fs::remove_file(&child).ok();
+    }
+
+    /// Hosted sparse extent: 4 grains of 4 KB, 2 per grain table. Only table 0
+    /// and its grain 0 (0x33) are allocated.
+    fn vmdk_sparse(compressed: bool) -> Vec<u8> {
+        let mut image = vec![0u8; 2048];
+        image[0..4].copy_from_slice(b"KDMV");
+        image[4..8].copy_from_slice(&1u32.to_le_bytes());
+        image[8..12].copy_from_slice(&(if compressed { VMDK_FLAG_COMPRESSED } else { 0 }).to_le_bytes());
+        image[12..20].copy_from_slice(&32u64.to_le_bytes());
+        image[20..28].copy_from_slice(&8u64.to_le_bytes());
+        image[44..48].copy_from_slice(&2u32.to_le_bytes());
+        image[56..64].copy_from_slice(&1u64.to_le_bytes());
+        image[77..79].copy_from_slice(&(compressed as u16).to_le_bytes());
+        image[512..516].copy_from_slice(&2u32.to_le_bytes());
+        image[1024..1028].copy_from_slice(&4u32.to_le_bytes());
+
+        if compressed {
+            let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&[0x33u8; 4096]).unwrap();
+            let grain = encoder.finish().unwrap();
+            image.extend_from_slice(&0u64.to_le_bytes());
+            image.extend_from_slice(&(grain.len() as u32).to_le_bytes());
+            image.extend_from_slice(&grain);
+        } else {
+            image.extend_from_slice(&[0x33u8; 4096]);
+        }
+        image
+    }
+
+    #[test]
+    fn test_vmdk_sparse_and_compressed_grains() {
+        let path = std::env::temp_dir().join("vdisk_test_sparse.vmdk");
+        for compressed in [false, true] {
+            std::fs::write(&path, vmdk_sparse(compressed)).unwrap();
+            let mut disk = VirtualDisk::open(path.to_str().unwrap()).unwrap().unwrap();
+            assert_eq!((disk.size(), disk.info().variant.as_str()), (16384, "monolithicSparse"));
+            assert!(filled(&read_virtual(&mut disk, 0, 4096), 0x33));
+            assert!(filled(&read_virtual(&mut disk, 4096, 4096), 0)); // Unallocated grain
+            assert!(filled(&read_virtual(&mut disk, 8192, 8192), 0)); // Unallocated grain table
+        }
+        std::fs::remove_file(&path).ok();
+    }
+
+    /// VDI of 1 KB blocks; `data` holds the allocated blocks in order
+    fn vdi_image(image_type: u32, uuid: u8, parent_uuid: u8, blocks: &[u32], data: &[u8]) -> Vec<u8> {
+        let mut image = vec![0u8; 1024];
+        image[64..68].copy_from_slice(&VDI_SIGNATURE.to_le_bytes());
+        image[68..72].copy_from_slice(&0x0001_0001u32.to_le_bytes());
+        image[76..80].copy_from_slice(&image_type.to_le_bytes());
+        image[340..344].copy_from_slice(&512u32.to_le_bytes());
+        image[344..348].copy_from_slice(&1024u32.to_le_bytes());
+        image[368..376].copy_from_slice(&(blocks.len() as u64 * 1024).to_le_bytes());
+        image[376..380].copy_from_slice(&1024u32.to_le_bytes());
+        image[384..388].copy_from_slice(&(blocks.len() as u32).to_le_bytes());
+        image[392..408].fill(uuid);
+        image[424..440].fill(parent_uuid);
+        for (i, block) in blocks.iter().enumerate() {
+            image[512 + i * 4..516 + i * 4].copy_from_slice(&block.to_le_bytes());
+        }
+        image.extend_from_slice(data);
+        image
+    }
+
+    #[test]
+    fn test_vdi_differencing_block_map() {
+        let dir = std::env::temp_dir();
+        let parent = dir.join("vdisk_test_parent.vdi");
+        let child = dir.join("vdisk_test_child.vdi");
+        let parent_data: Vec<u8> = [0x66u8, 0x77, 0x99].iter().flat_map(|&b| [b; 1024]).collect();
+        std::fs::write(&parent, vdi_image(1, 0xA1, 0, &[0, 1, 2], &parent_data)).unwrap();
+        let child_blocks = [0, VDI_BLOCK_FREE, VDI_BLOCK_ZERO];
+        std::fs::write(&child, vdi_image(VDI_TYPE_DIFFERENCING, 0xB2, 0xA1, &child_blocks, &[0x88u8; 1024])).unwrap();
+
+        let mut disk = VirtualDisk::open(child.to_str().unwrap()).unwrap().unwrap();
+        let info = disk.info();
+        assert_eq!((info.variant.as_str(), info.parent.unwrap().variant.as_str()), ("differencing", "dynamic"));
+        assert!(filled(&read_virtual(&mut disk, 0, 1024), 0x88));
+        assert!(filled(&read_virtual(&mut disk, 1024, 1024), 0x77)); // Free block: read from the parent
+        assert!(filled(&read_virtual(&mut disk, 2048, 1024), 0)); // Zero block hides the parent's data
+
+        std::fs::remove_file(&parent).ok();
+        std::fs::remove_file(&child).ok();
+    }
+
+    #[test]
+    fn test_qcow2_overlay_with_backing_file() {
+        let dir = std::env::temp_dir();
+        let backing = dir.join("vdisk_test_backing.raw");
+        let overlay = dir.join("vdisk_test_overlay.qcow2");
+        std::fs::write(&backing, vec![0x22u8; 2048]).unwrap();
+
+        // 512-byte clusters: allocated, unallocated (→ backing), zero, compressed
+        let mut image = vec![0u8; 2048];
+        image[0..4].copy_from_slice(b"QFI\xfb");
+        image[4..8].copy_from_slice(&3u32.to_be_bytes());
+        image[8..16].copy_from_slice(&200u64.to_be_bytes());
+        image[16..20].copy_from_slice(&(b"vdisk_test_backing.raw".len() as u32).to_be_bytes());
+        image[20..24].copy_from_slice(&9u32.to_be_bytes());
+        image[24..32].copy_from_slice(&2048u64.to_be_bytes());
+        image[36..40].copy_from_slice(&1u32.to_be_bytes());
+        image[40..48].copy_from_slice(&512u64.to_be_bytes());
+        image[200..222].copy_from_slice(b"vdisk_test_backing.raw");
+        image[512..520].copy_from_slice(&(1024u64 | 1 << 63).to_be_bytes());
+        image[1024..1032].copy_from_slice(&(1536u64 | 1 << 63).to_be_bytes());
+        image[1040..1048].copy_from_slice(&QCOW2_ZERO_CLUSTER.to_be_bytes());
+        image[1048..1056].copy_from_slice(&(2048u64 | QCOW2_COMPRESSED).to_be_bytes());
+        image[1536..2048].fill(0x11);
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&[0x44u8; 512]).unwrap();
+        image.extend_from_slice(&encoder.finish().unwrap());
+        std::fs::write(&overlay, &image).unwrap();
+
+        let mut disk = VirtualDisk::open(overlay.to_str().unwrap()).unwrap().unwrap();
+        assert_eq!(disk.size(), 2048);
+        assert_eq!(disk.info().parent.unwrap().format, "raw");
+
+        let mut data = Vec::new();
+        disk.read_to_end(&mut data).unwrap();
+        assert!(data[..512].iter().all(|&b| b == 0x11));
+        assert!(data[512..1024].iter().all(|&b| b == 0x22));
+        assert!(data[1024..1536].iter().all(|&b| b == 0));
+        assert!(data[1536..].iter().all(|&b| b == 0x44));
+
+        std::fs::remove_file(&backing).ok();
+        std::fs::remove_file(&overlay).ok();
+    }
+
+    #[test]
+    fn test_oversized_tables_are_refused() {
+        let path = std::env::temp_dir().join("vdisk_test_oversized.img");
+        let open_error = |image: &[u8]| {
+            std::fs::write(&path, image).unwrap();
+            VirtualDisk::open(path.to_str().unwrap()).err().unwrap_or_default()
+        };
+
+        // 2^32 - 1 L1 entries would be a 32 GiB allocation
+        let mut qcow2 = vec![0u8; 4096];
+        qcow2[0..4].copy_from_slice(b"QFI\xfb");
+        qcow2[4..8].copy_from_slice(&3u32.to_be_bytes());
+        qcow2[20..24].copy_from_slice(&16u32.to_be_bytes());
+        qcow2[24..32].copy_from_slice(&(1u64 << 50).to_be_bytes());
+        qcow2[36..40].copy_from_slice(&u32::MAX.to_be_bytes());
+        qcow2[40..48].copy_from_slice(&512u64.to_be_bytes());
+        assert!(open_error(&qcow2).contains("QCOW2 L1 table"));
+
+        let mut vdi = vec![0u8; 4096];
+        vdi[64..68].copy_from_slice(&VDI_SIGNATURE.to_le_bytes());
+        vdi[68..72].copy_from_slice(&0x0001_0001u32.to_le_bytes());
+        vdi[340..344].copy_from_slice(&512u32.to_le_bytes());
+        vdi[368..376].copy_from_slice(&(1u64 << 40).to_le_bytes());
+        vdi[376..380].copy_from_slice(&(1u32 << 20).to_le_bytes());
+        vdi[384..388].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(open_error(&vdi).contains("VDI block map"));
+
+        // A capacity in sectors that overflows as a byte count
+        let mut vmdk = vec![0u8; 4096];
+        vmdk[0..4].copy_from_slice(b"KDMV");
+        vmdk[12..20].copy_from_slice(&u64::MAX.to_le_bytes());
+        vmdk[20..28].copy_from_slice(&8u64.to_le_bytes());
+        vmdk[44..48].copy_from_slice(&512u32.to_le_bytes());
+        assert!(open_error(&vmdk).contains("out-of-range geometry"));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_plain_file_is_not_a_container() {
+        let path = std::env::temp_dir().join("vdisk_test_plain.dd");
+        std::fs::write(&path, vec![0u8; 4096]).unwrap();
+        assert!(VirtualDisk::open(path.to_str().unwrap()).unwrap().is_none());
+        std::fs::remove_file(&path).ok();
+    }
+}